@@ -6,17 +6,45 @@
 // TODO: rename to "primitive"
 #![allow(dead_code)]
 
+use crate::common::*;
+use crate::gast::UserAssumedFunId;
+use crate::id_vector::ToUsize;
 use crate::names::*;
 use crate::types;
+use crate::types::UserAssumedTyId;
 use crate::ullbc_ast;
+use rustc_session::Session;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use std::sync::OnceLock;
 
 // Assumed types
 pub static BOX_NAME: [&str; 3] = ["alloc", "boxed", "Box"];
 pub static VEC_NAME: [&str; 3] = ["alloc", "vec", "Vec"];
 pub static OPTION_NAME: [&str; 3] = ["core", "option", "Option"];
+pub static CELL_NAME: [&str; 3] = ["core", "cell", "Cell"];
+pub static REF_CELL_NAME: [&str; 3] = ["core", "cell", "RefCell"];
+pub static RC_NAME: [&str; 3] = ["alloc", "rc", "Rc"];
+pub static ARC_NAME: [&str; 3] = ["alloc", "sync", "Arc"];
+// This type is not primitive: we use it to identify the primitive functions
+// which take ranges as inputs (see [ArgTyKind])
+pub static RANGE_NAME: [&str; 4] = ["core", "ops", "range", "Range"];
+// Those types are not primitive: the `?` operator is desugared to matches on
+// their values, so we always reveal their variants (see
+// [is_always_transparent_adt]). We also use `Result` to identify the primitive
+// functions which implement the `?` operator (see [ArgTyKind]).
+pub static RESULT_NAME: [&str; 3] = ["core", "result", "Result"];
+pub static CONTROL_FLOW_NAME: [&str; 4] = ["core", "ops", "control_flow", "ControlFlow"];
+// Those types are not primitive: we use them to identify the primitive
+// functions which implement the `for` loops (see [ArgTyKind])
+pub static RANGE_INCLUSIVE_NAME: [&str; 4] = ["core", "ops", "range", "RangeInclusive"];
+pub static SLICE_ITER_TY_NAME: [&str; 4] = ["core", "slice", "iter", "Iter"];
+pub static SLICE_ITER_MUT_TY_NAME: [&str; 4] = ["core", "slice", "iter", "IterMut"];
 
 pub static OPTION_NONE_VARIANT_ID: types::VariantId::Id = types::VariantId::ZERO;
 pub static OPTION_SOME_VARIANT_ID: types::VariantId::Id = types::VariantId::ONE;
+pub static RESULT_OK_VARIANT_ID: types::VariantId::Id = types::VariantId::ZERO;
+pub static RESULT_ERR_VARIANT_ID: types::VariantId::Id = types::VariantId::ONE;
 
 //
 // Assumed functions/traits
@@ -27,9 +55,9 @@ pub static REPLACE_NAME: [&str; 3] = ["core", "mem", "replace"];
 
 // Boxes
 pub static BOX_NEW_NAME: [&str; 4] = ["alloc", "boxed", "Box", "new"];
-// This is a trait: for now we assume it is only used on boxes
+// This is a trait: it is primitive only when applied to boxes, `Rc` and `Arc`
 pub static DEREF_DEREF_NAME: [&str; 5] = ["core", "ops", "deref", "Deref", "deref"];
-// This is a trait: for now we assume it is only used on boxes
+// This is a trait: it is primitive only when applied to boxes
 pub static DEREF_DEREF_MUT_NAME: [&str; 5] = ["core", "ops", "deref", "DerefMut", "deref_mut"];
 pub static BOX_FREE_NAME: [&str; 3] = ["alloc", "alloc", "box_free"];
 
@@ -38,11 +66,65 @@ pub static VEC_NEW_NAME: [&str; 4] = ["alloc", "vec", "Vec", "new"];
 pub static VEC_PUSH_NAME: [&str; 4] = ["alloc", "vec", "Vec", "push"];
 pub static VEC_INSERT_NAME: [&str; 4] = ["alloc", "vec", "Vec", "insert"];
 pub static VEC_LEN_NAME: [&str; 4] = ["alloc", "vec", "Vec", "len"];
-// This is a trait: for now we assume it is only used on vectors
+// This is a trait: it is primitive only when applied to vectors, slices
+// and arrays
 pub static INDEX_NAME: [&str; 5] = ["core", "ops", "index", "Index", "index"];
-// This is a trait: for now we assume it is only used on vectors
+// This is a trait: it is primitive only when applied to vectors, slices
+// and arrays
 pub static INDEX_MUT_NAME: [&str; 5] = ["core", "ops", "index", "IndexMut", "index_mut"];
 
+// Slices. The methods of slices are defined in `impl<T> [T]` blocks (see
+// [crate::names_utils::function_def_id_to_name])
+pub static SLICE_LEN_NAME: [&str; 4] = ["core", "slice", "Slice", "len"];
+// This is generic over the index: it is primitive only when applied to `usize`
+pub static SLICE_GET_NAME: [&str; 4] = ["core", "slice", "Slice", "get"];
+// This is generic over the index: it is primitive only when applied to `usize`
+pub static SLICE_GET_MUT_NAME: [&str; 4] = ["core", "slice", "Slice", "get_mut"];
+pub static SLICE_SWAP_NAME: [&str; 4] = ["core", "slice", "Slice", "swap"];
+pub static SLICE_SPLIT_AT_NAME: [&str; 4] = ["core", "slice", "Slice", "split_at"];
+pub static SLICE_SPLIT_AT_MUT_NAME: [&str; 4] = ["core", "slice", "Slice", "split_at_mut"];
+pub static SLICE_ITER_NAME: [&str; 4] = ["core", "slice", "Slice", "iter"];
+pub static SLICE_ITER_MUT_NAME: [&str; 4] = ["core", "slice", "Slice", "iter_mut"];
+pub static SLICE_TO_VEC_NAME: [&str; 4] = ["alloc", "slice", "Slice", "to_vec"];
+
+// Arrays
+pub static ARRAY_AS_SLICE_NAME: [&str; 4] = ["core", "array", "Array", "as_slice"];
+pub static ARRAY_AS_MUT_SLICE_NAME: [&str; 4] = ["core", "array", "Array", "as_mut_slice"];
+
+// Cells
+pub static CELL_NEW_NAME: [&str; 4] = ["core", "cell", "Cell", "new"];
+pub static CELL_GET_NAME: [&str; 4] = ["core", "cell", "Cell", "get"];
+pub static CELL_SET_NAME: [&str; 4] = ["core", "cell", "Cell", "set"];
+pub static REF_CELL_NEW_NAME: [&str; 4] = ["core", "cell", "RefCell", "new"];
+pub static REF_CELL_BORROW_NAME: [&str; 4] = ["core", "cell", "RefCell", "borrow"];
+pub static REF_CELL_BORROW_MUT_NAME: [&str; 4] = ["core", "cell", "RefCell", "borrow_mut"];
+
+// Shared ownership
+pub static RC_NEW_NAME: [&str; 4] = ["alloc", "rc", "Rc", "new"];
+pub static ARC_NEW_NAME: [&str; 4] = ["alloc", "sync", "Arc", "new"];
+// This is a trait: it is primitive only when applied to `Rc` and `Arc`
+pub static CLONE_NAME: [&str; 4] = ["core", "clone", "Clone", "clone"];
+
+// The `?` operator. Those are traits: they are primitive only when applied
+// to `Result` and `Option`
+pub static TRY_BRANCH_NAME: [&str; 5] = ["core", "ops", "try_trait", "Try", "branch"];
+pub static FROM_RESIDUAL_NAME: [&str; 5] =
+    ["core", "ops", "try_trait", "FromResidual", "from_residual"];
+
+// Iterators (used by the `for` loops). Those are traits: they are primitive
+// only when applied to ranges, slices and the slice iterators
+pub static INTO_ITER_NAME: [&str; 6] = [
+    "core",
+    "iter",
+    "traits",
+    "collect",
+    "IntoIterator",
+    "into_iter",
+];
+pub static ITERATOR_NEXT_NAME: [&str; 6] =
+    ["core", "iter", "traits", "iterator", "Iterator", "next"];
+pub static RANGE_INCLUSIVE_NEW_NAME: [&str; 5] = ["core", "ops", "range", "RangeInclusive", "new"];
+
 // Pointers
 pub static PTR_UNIQUE_NAME: [&str; 3] = ["core", "ptr", "Unique"];
 pub static PTR_NON_NULL_NAME: [&str; 3] = ["core", "ptr", "NonNull"];
@@ -50,9 +132,201 @@ pub static PTR_NON_NULL_NAME: [&str; 3] = ["core", "ptr", "NonNull"];
 // We ignore this trait, which is implicitly given to all the type parameters
 pub static MARKER_SIZED_NAME: [&str; 3] = ["core", "marker", "Sized"];
 
+//
+// User-declared primitives
+//
+
+/// A type declared as primitive by the user
+#[derive(Debug, Deserialize, Serialize)]
+pub struct UserAssumedTy {
+    /// The Rust path of the type (for instance: `utils::bignum::BigNum`)
+    pub path: String,
+    /// The identifier of the primitive type, for the backends
+    pub id: String,
+    /// The type parameters which are not filtered (see [type_to_used_params]).
+    /// We check its length when registering the type.
+    pub used_params: Vec<bool>,
+    /// The variances of the type in the parameters which are not filtered
+    /// (see [type_to_variances]). We check its length when loading the file.
+    #[serde(default)]
+    pub variances: Option<Vec<types::Variance>>,
+}
+
+/// A function declared as primitive by the user. We check the lengths of
+/// the filters when registering the calls to the function.
+#[derive(Debug, Deserialize, Serialize)]
+pub struct UserAssumedFun {
+    /// The Rust path of the function (for instance: `utils::bignum::add`)
+    pub path: String,
+    /// The identifier of the primitive function, for the backends
+    pub id: String,
+    /// See [FunInfo]
+    pub used_type_params: Vec<bool>,
+    /// See [FunInfo]
+    pub used_args: Vec<bool>,
+    /// The signature of the primitive function, in the syntax of the
+    /// backend. We don't interpret it, and export it as is.
+    pub signature: Option<String>,
+}
+
+/// The primitive types and functions declared by the user in a TOML file
+/// given with `--assumed`, for instance:
+/// ```toml
+/// [[types]]
+/// path = "utils::bignum::BigNum"
+/// id = "BigNum"
+/// used_params = []
+///
+/// [[types]]
+/// path = "utils::bignum::Wrapper"
+/// id = "Wrapper"
+/// used_params = [true]
+/// variances = ["Covariant"]
+///
+/// [[functions]]
+/// path = "utils::bignum::add"
+/// id = "bignum_add"
+/// used_type_params = []
+/// used_args = [true, true]
+/// signature = "BigNum -> BigNum -> BigNum"
+/// ```
+///
+/// Only external types and functions can be declared as primitive: we
+/// reject the paths which start with the name of the current crate (or
+/// with `crate`) when loading the file. The primitives are identified by
+/// their index in those lists ([types::AssumedTy::User] and
+/// [ullbc_ast::AssumedFunId::User]), and we list them in the output files.
+///
+/// The variances of the types are optional: if they are not given, we
+/// consider that the types are invariant in their parameters (see
+/// [type_to_variances]).
+#[derive(Debug, Default, Deserialize, Serialize)]
+pub struct UserAssumedDecls {
+    #[serde(default)]
+    pub types: Vec<UserAssumedTy>,
+    #[serde(default)]
+    pub functions: Vec<UserAssumedFun>,
+}
+
+/// The user-declared primitives. We load them once, when starting the
+/// translation (see [load_user_assumed_decls]).
+static USER_ASSUMED_DECLS: OnceLock<UserAssumedDecls> = OnceLock::new();
+
+/// Load the primitives declared by the user in a TOML file.
+/// Must be called at most once, before the translation starts.
+///
+/// `crate_name`: the name of the crate we translate (see [UserAssumedDecls]).
+pub(crate) fn load_user_assumed_decls(sess: &Session, file: &Path, crate_name: &str) -> Result<()> {
+    let decls = match std::fs::read_to_string(file) {
+        std::io::Result::Ok(content) => match toml::from_str::<UserAssumedDecls>(&content) {
+            std::result::Result::Ok(decls) => decls,
+            std::result::Result::Err(err) => {
+                let msg = format!("Could not parse {file:?}: {err}");
+                err_no_span(sess, &msg);
+                return Err(());
+            }
+        },
+        std::io::Result::Err(err) => {
+            let msg = format!("Could not read {file:?}: {err}");
+            err_no_span(sess, &msg);
+            return Err(());
+        }
+    };
+
+    // Check that the user only declares external types and functions
+    let is_local = |path: &str| {
+        let krate = path.split("::").next().unwrap();
+        krate == crate_name || krate == "crate"
+    };
+    for path in decls.types.iter().map(|decl| &decl.path) {
+        if is_local(path) {
+            let msg = format!(
+                "The type {path} belongs to the current crate: only external types can be declared as primitive"
+            );
+            err_no_span(sess, &msg);
+            return Err(());
+        }
+    }
+    for path in decls.functions.iter().map(|decl| &decl.path) {
+        if is_local(path) {
+            let msg = format!(
+                "The function {path} belongs to the current crate: only external functions can be declared as primitive"
+            );
+            err_no_span(sess, &msg);
+            return Err(());
+        }
+    }
+
+    // Check that the variances are given for the used parameters
+    for decl in &decls.types {
+        if let Some(variances) = &decl.variances {
+            let num_used_params = decl.used_params.iter().filter(|used| **used).count();
+            if variances.len() != num_used_params {
+                let msg = format!(
+                    "The type {} has {} used parameters but {} variances",
+                    decl.path,
+                    num_used_params,
+                    variances.len()
+                );
+                err_no_span(sess, &msg);
+                return Err(());
+            }
+        }
+    }
+
+    if USER_ASSUMED_DECLS.set(decls).is_err() {
+        err_no_span(
+            sess,
+            "The primitives declared by the user were already loaded",
+        );
+        return Err(());
+    }
+
+    // Check that the user doesn't redefine the built-in primitives (we
+    // look up the built-in primitives first)
+    let decls = get_user_assumed_decls();
+    for decl in &decls.types {
+        let name = Name::from(decl.path.split("::").map(|s| s.to_string()).collect());
+        if !get_type_id_from_name(&name).unwrap().is_user() {
+            let msg = format!("The type {} is already a primitive type", decl.path);
+            err_no_span(sess, &msg);
+            return Err(());
+        }
+    }
+    for decl in &decls.functions {
+        let name = Name::from(decl.path.split("::").map(|s| s.to_string()).collect());
+        if !matches!(get_fun_id_from_name_full(&name), Some(FunId::User(_))) {
+            let msg = format!("The function {} is already a primitive function", decl.path);
+            err_no_span(sess, &msg);
+            return Err(());
+        }
+    }
+    Ok(())
+}
+
+/// Return the user-declared primitives (there are none if we didn't call
+/// [load_user_assumed_decls]).
+pub fn get_user_assumed_decls() -> &'static UserAssumedDecls {
+    USER_ASSUMED_DECLS.get_or_init(UserAssumedDecls::default)
+}
+
+pub fn get_user_assumed_ty(id: UserAssumedTyId::Id) -> &'static UserAssumedTy {
+    &get_user_assumed_decls().types[id.to_usize()]
+}
+
+pub fn get_user_assumed_fun(id: UserAssumedFunId::Id) -> &'static UserAssumedFun {
+    &get_user_assumed_decls().functions[id.to_usize()]
+}
+
+fn name_equals_path(name: &Name, path: &str) -> bool {
+    let path: Vec<&str> = path.split("::").collect();
+    name.equals_ref_name(&path)
+}
+
 /// We redefine identifiers for assumed functions here, instead of reusing the
 /// identifiers from [ullbc_ast], because some of the functions (the panic functions)
 /// will actually not be translated to functions: there are thus missing identifiers.
+#[derive(Debug, Clone, Copy)]
 enum FunId {
     /// `core::panicking::panic`
     Panic,
@@ -60,96 +334,486 @@ enum FunId {
     BeginPanic,
     Replace,
     BoxNew,
-    BoxDeref,
-    BoxDerefMut,
+    /// `core::ops::deref::Deref::deref`: the primitive function depends on
+    /// the `Self` type
+    Deref,
+    /// `core::ops::deref::DerefMut::deref_mut`: see [FunId::Deref]
+    DerefMut,
     BoxFree,
     VecNew,
     VecPush,
     VecInsert,
     VecLen,
-    VecIndex,
-    VecIndexMut,
+    /// `core::ops::index::Index::index`: the primitive function depends on
+    /// the `Self` type and the index type
+    Index,
+    /// `core::ops::index::IndexMut::index_mut`: see [FunId::Index]
+    IndexMut,
+    SliceLen,
+    SliceGet,
+    SliceGetMut,
+    SliceSwap,
+    SliceSplitAt,
+    SliceSplitAtMut,
+    SliceIter,
+    SliceIterMut,
+    SliceToVec,
+    ArrayAsSlice,
+    ArrayAsMutSlice,
+    CellNew,
+    CellGet,
+    CellSet,
+    RefCellNew,
+    RefCellBorrow,
+    RefCellBorrowMut,
+    RcNew,
+    ArcNew,
+    /// `core::clone::Clone::clone`: see [FunId::Deref]
+    Clone,
+    /// `core::ops::try_trait::Try::branch`: see [FunId::Deref]
+    TryBranch,
+    /// `core::ops::try_trait::FromResidual::from_residual`: see [FunId::Index]
+    FromResidual,
+    /// `core::iter::traits::collect::IntoIterator::into_iter`: see [FunId::Deref]
+    IntoIter,
+    /// `core::iter::traits::iterator::Iterator::next`: see [FunId::Deref]
+    IteratorNext,
+    RangeInclusiveNew,
+    User(UserAssumedFunId::Id),
 }
 
+/// Some of the assumed functions are generic over types they don't own:
+/// the trait methods (`Deref::deref`, `Index::index`, etc.) and the
+/// slice methods generic over the index (`[T]::get`, etc.). We consider
+/// them as primitive only when they are applied to some specific types,
+/// which also determine the primitive function (for instance, `Index::index`
+/// applied to `Vec<T>` and `usize` is [ullbc_ast::AssumedFunId::VecIndex]).
+///
+/// This describes the type arguments of those functions, for the purpose
+/// of selecting the primitive function (see [get_fun_id_from_name]).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ArgTyKind {
+    Assumed(types::AssumedTy),
+    Slice,
+    Array,
+    Usize,
+    /// `core::ops::range::Range<usize>`
+    RangeUsize,
+    /// `core::ops::range::Range<T>`, where `T` is not `usize`
+    Range,
+    /// `core::ops::range::RangeInclusive`
+    RangeInclusive,
+    /// `core::slice::iter::Iter`
+    SliceIter,
+    /// `core::slice::iter::IterMut`
+    SliceIterMut,
+    /// `&[T]`
+    SharedSliceRef,
+    /// `&mut [T]`
+    MutSliceRef,
+    /// `core::result::Result`
+    Result,
+    Other,
+}
+
+/// A built-in primitive type. The built-in primitives are described by tables,
+/// like the user-declared ones (see [UserAssumedTy]), and we look them up first.
+struct BuiltinAssumedTy {
+    name: &'static [&'static str],
+    id: types::AssumedTy,
+    /// See [type_to_used_params]
+    used_params: &'static [bool],
+    /// See [type_to_variances]
+    variances: &'static [types::Variance],
+}
+
+static BUILTIN_ASSUMED_TYPES: &[BuiltinAssumedTy] = {
+    use types::AssumedTy;
+    use types::Variance::*;
+    &[
+        BuiltinAssumedTy {
+            name: &BOX_NAME,
+            id: AssumedTy::Box,
+            used_params: &[true, false],
+            variances: &[Covariant],
+        },
+        BuiltinAssumedTy {
+            name: &VEC_NAME,
+            id: AssumedTy::Vec,
+            used_params: &[true, false],
+            variances: &[Covariant],
+        },
+        BuiltinAssumedTy {
+            name: &OPTION_NAME,
+            id: AssumedTy::Option,
+            used_params: &[true],
+            variances: &[Covariant],
+        },
+        BuiltinAssumedTy {
+            name: &PTR_UNIQUE_NAME,
+            id: AssumedTy::PtrUnique,
+            used_params: &[true],
+            variances: &[Covariant],
+        },
+        BuiltinAssumedTy {
+            name: &PTR_NON_NULL_NAME,
+            id: AssumedTy::PtrNonNull,
+            used_params: &[true],
+            variances: &[Covariant],
+        },
+        // The cells allow to mutate their content through shared borrows
+        BuiltinAssumedTy {
+            name: &CELL_NAME,
+            id: AssumedTy::Cell,
+            used_params: &[true],
+            variances: &[Invariant],
+        },
+        BuiltinAssumedTy {
+            name: &REF_CELL_NAME,
+            id: AssumedTy::RefCell,
+            used_params: &[true],
+            variances: &[Invariant],
+        },
+        BuiltinAssumedTy {
+            name: &RC_NAME,
+            id: AssumedTy::Rc,
+            used_params: &[true],
+            variances: &[Covariant],
+        },
+        BuiltinAssumedTy {
+            name: &ARC_NAME,
+            id: AssumedTy::Arc,
+            used_params: &[true],
+            variances: &[Covariant],
+        },
+    ]
+};
+
+/// A built-in primitive function: see [BuiltinAssumedTy] and [UserAssumedFun].
+struct BuiltinAssumedFun {
+    name: &'static [&'static str],
+    id: FunId,
+    /// See [FunInfo]
+    used_type_params: &'static [bool],
+    /// See [FunInfo]
+    used_args: &'static [bool],
+}
+
+/// Shortcut to build the entries of [BUILTIN_ASSUMED_FUNS]
+const fn builtin_fun(
+    name: &'static [&'static str],
+    id: FunId,
+    used_type_params: &'static [bool],
+    used_args: &'static [bool],
+) -> BuiltinAssumedFun {
+    BuiltinAssumedFun {
+        name,
+        id,
+        used_type_params,
+        used_args,
+    }
+}
+
+static BUILTIN_ASSUMED_FUNS: &[BuiltinAssumedFun] = &[
+    builtin_fun(&PANIC_NAME, FunId::Panic, &[], &[true]),
+    builtin_fun(&BEGIN_PANIC_NAME, FunId::BeginPanic, &[true], &[true]),
+    builtin_fun(&REPLACE_NAME, FunId::Replace, &[true], &[true, true]),
+    builtin_fun(&BOX_NEW_NAME, FunId::BoxNew, &[true], &[true]),
+    builtin_fun(&DEREF_DEREF_NAME, FunId::Deref, &[true], &[true]),
+    builtin_fun(&DEREF_DEREF_MUT_NAME, FunId::DerefMut, &[true], &[true]),
+    builtin_fun(
+        &BOX_FREE_NAME,
+        FunId::BoxFree,
+        &[true, false],
+        &[true, false],
+    ),
+    builtin_fun(&VEC_NEW_NAME, FunId::VecNew, &[true], &[]),
+    builtin_fun(
+        &VEC_PUSH_NAME,
+        FunId::VecPush,
+        &[true, false],
+        &[true, true],
+    ),
+    builtin_fun(
+        &VEC_INSERT_NAME,
+        FunId::VecInsert,
+        &[true, false],
+        &[true, true, true],
+    ),
+    builtin_fun(&VEC_LEN_NAME, FunId::VecLen, &[true, false], &[true]),
+    // The second type parameter is for the index type (`usize` or
+    // `Range<usize>`): it is determined by the primitive function
+    builtin_fun(&INDEX_NAME, FunId::Index, &[true, false], &[true, true]),
+    builtin_fun(
+        &INDEX_MUT_NAME,
+        FunId::IndexMut,
+        &[true, false],
+        &[true, true],
+    ),
+    builtin_fun(&SLICE_LEN_NAME, FunId::SliceLen, &[true], &[true]),
+    // The second type parameter is for the index type (`usize`)
+    builtin_fun(
+        &SLICE_GET_NAME,
+        FunId::SliceGet,
+        &[true, false],
+        &[true, true],
+    ),
+    builtin_fun(
+        &SLICE_GET_MUT_NAME,
+        FunId::SliceGetMut,
+        &[true, false],
+        &[true, true],
+    ),
+    builtin_fun(
+        &SLICE_SWAP_NAME,
+        FunId::SliceSwap,
+        &[true],
+        &[true, true, true],
+    ),
+    builtin_fun(
+        &SLICE_SPLIT_AT_NAME,
+        FunId::SliceSplitAt,
+        &[true],
+        &[true, true],
+    ),
+    builtin_fun(
+        &SLICE_SPLIT_AT_MUT_NAME,
+        FunId::SliceSplitAtMut,
+        &[true],
+        &[true, true],
+    ),
+    builtin_fun(&SLICE_ITER_NAME, FunId::SliceIter, &[true], &[true]),
+    builtin_fun(&SLICE_ITER_MUT_NAME, FunId::SliceIterMut, &[true], &[true]),
+    builtin_fun(&SLICE_TO_VEC_NAME, FunId::SliceToVec, &[true], &[true]),
+    // The second parameter is the length of the array
+    builtin_fun(
+        &ARRAY_AS_SLICE_NAME,
+        FunId::ArrayAsSlice,
+        &[true, true],
+        &[true],
+    ),
+    builtin_fun(
+        &ARRAY_AS_MUT_SLICE_NAME,
+        FunId::ArrayAsMutSlice,
+        &[true, true],
+        &[true],
+    ),
+    builtin_fun(&CELL_NEW_NAME, FunId::CellNew, &[true], &[true]),
+    builtin_fun(&CELL_GET_NAME, FunId::CellGet, &[true], &[true]),
+    builtin_fun(&CELL_SET_NAME, FunId::CellSet, &[true], &[true, true]),
+    builtin_fun(&REF_CELL_NEW_NAME, FunId::RefCellNew, &[true], &[true]),
+    builtin_fun(
+        &REF_CELL_BORROW_NAME,
+        FunId::RefCellBorrow,
+        &[true],
+        &[true],
+    ),
+    builtin_fun(
+        &REF_CELL_BORROW_MUT_NAME,
+        FunId::RefCellBorrowMut,
+        &[true],
+        &[true],
+    ),
+    builtin_fun(&RC_NEW_NAME, FunId::RcNew, &[true], &[true]),
+    builtin_fun(&ARC_NEW_NAME, FunId::ArcNew, &[true], &[true]),
+    builtin_fun(&CLONE_NAME, FunId::Clone, &[true], &[true]),
+    builtin_fun(&TRY_BRANCH_NAME, FunId::TryBranch, &[true], &[true]),
+    // The second type parameter is the type of the residual
+    builtin_fun(
+        &FROM_RESIDUAL_NAME,
+        FunId::FromResidual,
+        &[true, true],
+        &[true],
+    ),
+    builtin_fun(&INTO_ITER_NAME, FunId::IntoIter, &[true], &[true]),
+    builtin_fun(&ITERATOR_NEXT_NAME, FunId::IteratorNext, &[true], &[true]),
+    builtin_fun(
+        &RANGE_INCLUSIVE_NEW_NAME,
+        FunId::RangeInclusiveNew,
+        &[true],
+        &[true, true],
+    ),
+];
+
 pub fn get_type_id_from_name(name: &TypeName) -> Option<types::AssumedTy> {
-    if name.equals_ref_name(&BOX_NAME) {
-        Option::Some(types::AssumedTy::Box)
-    } else if name.equals_ref_name(&VEC_NAME) {
-        Option::Some(types::AssumedTy::Vec)
-    } else if name.equals_ref_name(&OPTION_NAME) {
-        Option::Some(types::AssumedTy::Option)
-    } else if name.equals_ref_name(&PTR_UNIQUE_NAME) {
-        Option::Some(types::AssumedTy::PtrUnique)
-    } else if name.equals_ref_name(&PTR_NON_NULL_NAME) {
-        Option::Some(types::AssumedTy::PtrNonNull)
-    } else {
-        Option::None
+    match BUILTIN_ASSUMED_TYPES
+        .iter()
+        .find(|decl| name.equals_ref_name(decl.name))
+    {
+        Option::Some(decl) => Option::Some(decl.id),
+        Option::None => get_user_assumed_decls()
+            .types
+            .iter()
+            .position(|decl| name_equals_path(name, &decl.path))
+            .map(|i| types::AssumedTy::User(UserAssumedTyId::Id::new(i))),
     }
 }
 
+fn get_builtin_assumed_ty(id: types::AssumedTy) -> &'static BuiltinAssumedTy {
+    BUILTIN_ASSUMED_TYPES
+        .iter()
+        .find(|decl| decl.id == id)
+        .unwrap()
+}
+
 pub fn get_name_from_type_id(id: types::AssumedTy) -> Vec<String> {
-    use types::AssumedTy;
-    match id {
-        AssumedTy::Box => BOX_NAME.iter().map(|s| s.to_string()).collect(),
-        AssumedTy::Vec => VEC_NAME.iter().map(|s| s.to_string()).collect(),
-        AssumedTy::Option => OPTION_NAME.iter().map(|s| s.to_string()).collect(),
-        AssumedTy::PtrUnique => PTR_UNIQUE_NAME.iter().map(|s| s.to_string()).collect(),
-        AssumedTy::PtrNonNull => PTR_NON_NULL_NAME.iter().map(|s| s.to_string()).collect(),
-    }
+    let name: Vec<&str> = match id {
+        types::AssumedTy::User(id) => get_user_assumed_ty(id).path.split("::").collect(),
+        _ => get_builtin_assumed_ty(id).name.to_vec(),
+    };
+    name.into_iter().map(|s| s.to_string()).collect()
 }
 
 fn get_fun_id_from_name_full(name: &FunName) -> Option<FunId> {
-    if name.equals_ref_name(&PANIC_NAME) {
-        Option::Some(FunId::Panic)
-    } else if name.equals_ref_name(&BEGIN_PANIC_NAME) {
-        Option::Some(FunId::BeginPanic)
-    } else if name.equals_ref_name(&REPLACE_NAME) {
-        Option::Some(FunId::Replace)
-    } else if name.equals_ref_name(&BOX_NEW_NAME) {
-        Option::Some(FunId::BoxNew)
-    } else if name.equals_ref_name(&DEREF_DEREF_NAME) {
-        Option::Some(FunId::BoxDeref)
-    } else if name.equals_ref_name(&DEREF_DEREF_MUT_NAME) {
-        Option::Some(FunId::BoxDerefMut)
-    } else if name.equals_ref_name(&BOX_FREE_NAME) {
-        Option::Some(FunId::BoxFree)
-    } else if name.equals_ref_name(&VEC_NEW_NAME) {
-        Option::Some(FunId::VecNew)
-    } else if name.equals_ref_name(&VEC_PUSH_NAME) {
-        Option::Some(FunId::VecPush)
-    } else if name.equals_ref_name(&VEC_INSERT_NAME) {
-        Option::Some(FunId::VecInsert)
-    } else if name.equals_ref_name(&VEC_LEN_NAME) {
-        Option::Some(FunId::VecLen)
-    } else if name.equals_ref_name(&INDEX_NAME) {
-        Option::Some(FunId::VecIndex)
-    } else if name.equals_ref_name(&INDEX_MUT_NAME) {
-        Option::Some(FunId::VecIndexMut)
-    } else {
-        Option::None
+    match BUILTIN_ASSUMED_FUNS
+        .iter()
+        .find(|decl| name.equals_ref_name(decl.name))
+    {
+        Option::Some(decl) => Option::Some(decl.id),
+        Option::None => get_user_assumed_decls()
+            .functions
+            .iter()
+            .position(|decl| name_equals_path(name, &decl.path))
+            .map(|i| FunId::User(UserAssumedFunId::Id::new(i))),
     }
 }
 
-pub fn get_fun_id_from_name(name: &FunName) -> Option<ullbc_ast::AssumedFunId> {
-    match get_fun_id_from_name_full(name) {
-        Option::Some(id) => {
-            let id = match id {
-                FunId::Panic | FunId::BeginPanic => unreachable!(),
-                FunId::Replace => ullbc_ast::AssumedFunId::Replace,
-                FunId::BoxNew => ullbc_ast::AssumedFunId::BoxNew,
-                FunId::BoxDeref => ullbc_ast::AssumedFunId::BoxDeref,
-                FunId::BoxDerefMut => ullbc_ast::AssumedFunId::BoxDerefMut,
-                FunId::BoxFree => ullbc_ast::AssumedFunId::BoxFree,
-                FunId::VecNew => ullbc_ast::AssumedFunId::VecNew,
-                FunId::VecPush => ullbc_ast::AssumedFunId::VecPush,
-                FunId::VecInsert => ullbc_ast::AssumedFunId::VecInsert,
-                FunId::VecLen => ullbc_ast::AssumedFunId::VecLen,
-                FunId::VecIndex => ullbc_ast::AssumedFunId::VecIndex,
-                FunId::VecIndexMut => ullbc_ast::AssumedFunId::VecIndexMut,
-            };
-            Option::Some(id)
-        }
-        Option::None => Option::None,
+/// Return the identifier of the primitive function, if the function is
+/// primitive when applied to type arguments of the given kinds (see
+/// [ArgTyKind]).
+pub fn get_fun_id_from_name(
+    name: &FunName,
+    type_args: &[ArgTyKind],
+) -> Option<ullbc_ast::AssumedFunId> {
+    use ullbc_ast::AssumedFunId;
+    let id = match get_fun_id_from_name_full(name)? {
+        FunId::Panic | FunId::BeginPanic => unreachable!(),
+        FunId::Replace => AssumedFunId::Replace,
+        FunId::BoxNew => AssumedFunId::BoxNew,
+        FunId::Deref => match type_args[0] {
+            ArgTyKind::Assumed(types::AssumedTy::Box) => AssumedFunId::BoxDeref,
+            ArgTyKind::Assumed(types::AssumedTy::Rc) => AssumedFunId::RcDeref,
+            ArgTyKind::Assumed(types::AssumedTy::Arc) => AssumedFunId::ArcDeref,
+            _ => return Option::None,
+        },
+        FunId::DerefMut => match type_args[0] {
+            ArgTyKind::Assumed(types::AssumedTy::Box) => AssumedFunId::BoxDerefMut,
+            _ => return Option::None,
+        },
+        FunId::BoxFree => AssumedFunId::BoxFree,
+        FunId::VecNew => AssumedFunId::VecNew,
+        FunId::VecPush => AssumedFunId::VecPush,
+        FunId::VecInsert => AssumedFunId::VecInsert,
+        FunId::VecLen => AssumedFunId::VecLen,
+        FunId::Index => match (type_args[0], type_args[1]) {
+            (ArgTyKind::Assumed(types::AssumedTy::Vec), ArgTyKind::Usize) => AssumedFunId::VecIndex,
+            (ArgTyKind::Slice, ArgTyKind::Usize) => AssumedFunId::SliceIndexShared,
+            (ArgTyKind::Slice, ArgTyKind::RangeUsize) => AssumedFunId::SliceSubsliceShared,
+            (ArgTyKind::Array, ArgTyKind::Usize) => AssumedFunId::ArrayIndexShared,
+            (ArgTyKind::Array, ArgTyKind::RangeUsize) => AssumedFunId::ArraySubsliceShared,
+            _ => return Option::None,
+        },
+        FunId::IndexMut => match (type_args[0], type_args[1]) {
+            (ArgTyKind::Assumed(types::AssumedTy::Vec), ArgTyKind::Usize) => {
+                AssumedFunId::VecIndexMut
+            }
+            (ArgTyKind::Slice, ArgTyKind::Usize) => AssumedFunId::SliceIndexMut,
+            (ArgTyKind::Slice, ArgTyKind::RangeUsize) => AssumedFunId::SliceSubsliceMut,
+            (ArgTyKind::Array, ArgTyKind::Usize) => AssumedFunId::ArrayIndexMut,
+            (ArgTyKind::Array, ArgTyKind::RangeUsize) => AssumedFunId::ArraySubsliceMut,
+            _ => return Option::None,
+        },
+        FunId::SliceLen => AssumedFunId::SliceLen,
+        // Primitive only when the index is a `usize`
+        FunId::SliceGet if type_args[1] == ArgTyKind::Usize => AssumedFunId::SliceGet,
+        FunId::SliceGetMut if type_args[1] == ArgTyKind::Usize => AssumedFunId::SliceGetMut,
+        FunId::SliceSwap => AssumedFunId::SliceSwap,
+        FunId::SliceSplitAt => AssumedFunId::SliceSplitAt,
+        FunId::SliceSplitAtMut => AssumedFunId::SliceSplitAtMut,
+        FunId::SliceIter => AssumedFunId::SliceIter,
+        FunId::SliceIterMut => AssumedFunId::SliceIterMut,
+        FunId::SliceToVec => AssumedFunId::SliceToVec,
+        FunId::ArrayAsSlice => AssumedFunId::ArrayToSliceShared,
+        FunId::ArrayAsMutSlice => AssumedFunId::ArrayToSliceMut,
+        FunId::CellNew => AssumedFunId::CellNew,
+        FunId::CellGet => AssumedFunId::CellGet,
+        FunId::CellSet => AssumedFunId::CellSet,
+        FunId::RefCellNew => AssumedFunId::RefCellNew,
+        FunId::RefCellBorrow => AssumedFunId::RefCellBorrow,
+        FunId::RefCellBorrowMut => AssumedFunId::RefCellBorrowMut,
+        FunId::RcNew => AssumedFunId::RcNew,
+        FunId::ArcNew => AssumedFunId::ArcNew,
+        FunId::Clone => match type_args[0] {
+            ArgTyKind::Assumed(types::AssumedTy::Rc) => AssumedFunId::RcClone,
+            ArgTyKind::Assumed(types::AssumedTy::Arc) => AssumedFunId::ArcClone,
+            _ => return Option::None,
+        },
+        FunId::TryBranch => match type_args[0] {
+            ArgTyKind::Result => AssumedFunId::ResultBranch,
+            ArgTyKind::Assumed(types::AssumedTy::Option) => AssumedFunId::OptionBranch,
+            _ => return Option::None,
+        },
+        // The residual must be the one of the `Self` type
+        FunId::FromResidual => match (type_args[0], type_args[1]) {
+            (ArgTyKind::Result, ArgTyKind::Result) => AssumedFunId::ResultFromResidual,
+            (
+                ArgTyKind::Assumed(types::AssumedTy::Option),
+                ArgTyKind::Assumed(types::AssumedTy::Option),
+            ) => AssumedFunId::OptionFromResidual,
+            _ => return Option::None,
+        },
+        // We simplify the calls to `into_iter` in [crate::simplify_iterators]
+        FunId::IntoIter => match type_args[0] {
+            ArgTyKind::RangeUsize
+            | ArgTyKind::Range
+            | ArgTyKind::RangeInclusive
+            | ArgTyKind::SliceIter
+            | ArgTyKind::SliceIterMut
+            | ArgTyKind::SharedSliceRef
+            | ArgTyKind::MutSliceRef => AssumedFunId::IntoIter,
+            _ => return Option::None,
+        },
+        FunId::IteratorNext => match type_args[0] {
+            ArgTyKind::RangeUsize | ArgTyKind::Range => AssumedFunId::RangeNext,
+            ArgTyKind::RangeInclusive => AssumedFunId::RangeInclusiveNext,
+            ArgTyKind::SliceIter => AssumedFunId::SliceIterNext,
+            ArgTyKind::SliceIterMut => AssumedFunId::SliceIterMutNext,
+            _ => return Option::None,
+        },
+        FunId::RangeInclusiveNew => AssumedFunId::RangeInclusiveNew,
+        FunId::User(id) => AssumedFunId::User(id),
+        FunId::SliceGet | FunId::SliceGetMut => return Option::None,
+    };
+    Option::Some(id)
+}
+
+/// Return the identifier of an intrinsic, if we recognize it. `name` is the
+/// name of the intrinsic without its path (for instance, `size_of` for
+/// `core::intrinsics::size_of`).
+pub fn get_intrinsic_id_from_name(name: &str) -> Option<ullbc_ast::IntrinsicFunId> {
+    use ullbc_ast::IntrinsicFunId;
+    match name {
+        "assume" => Option::Some(IntrinsicFunId::Assume),
+        "copy_nonoverlapping" => Option::Some(IntrinsicFunId::CopyNonOverlapping),
+        "size_of" => Option::Some(IntrinsicFunId::SizeOf),
+        "min_align_of" => Option::Some(IntrinsicFunId::MinAlignOf),
+        "transmute" => Option::Some(IntrinsicFunId::Transmute),
+        "likely" => Option::Some(IntrinsicFunId::Likely),
+        "unlikely" => Option::Some(IntrinsicFunId::Unlikely),
+        _ => Option::None,
+    }
+}
+
+pub fn get_name_from_intrinsic_id(id: ullbc_ast::IntrinsicFunId) -> &'static str {
+    use ullbc_ast::IntrinsicFunId;
+    match id {
+        IntrinsicFunId::Assume => "assume",
+        IntrinsicFunId::CopyNonOverlapping => "copy_nonoverlapping",
+        IntrinsicFunId::SizeOf => "size_of",
+        IntrinsicFunId::MinAlignOf => "min_align_of",
+        IntrinsicFunId::Transmute => "transmute",
+        IntrinsicFunId::Likely => "likely",
+        IntrinsicFunId::Unlikely => "unlikely",
     }
 }
 
@@ -159,29 +823,65 @@ pub fn get_fun_id_from_name(name: &FunName) -> Option<ullbc_ast::AssumedFunId> {
 /// (`std::alloc::Allocator`): we ignore it.
 pub fn type_to_used_params(name: &TypeName) -> Option<Vec<bool>> {
     trace!("{}", name);
-    match get_type_id_from_name(name) {
-        Option::None => Option::None,
-        Option::Some(id) => {
-            use types::AssumedTy;
-            let id = match id {
-                AssumedTy::Box => {
-                    vec![true, false]
-                }
-                AssumedTy::Vec => {
-                    vec![true, false]
-                }
-                AssumedTy::Option => {
-                    vec![true]
-                }
-                AssumedTy::PtrUnique | AssumedTy::PtrNonNull => {
-                    vec![true]
-                }
-            };
-            Option::Some(id)
+    let used_params = match get_type_id_from_name(name)? {
+        types::AssumedTy::User(id) => get_user_assumed_ty(id).used_params.clone(),
+        id => get_builtin_assumed_ty(id).used_params.to_vec(),
+    };
+    Option::Some(used_params)
+}
+
+/// Return `true` if we reveal the variants of an external ADT even if the user
+/// didn't ask to extract the external ADTs (see
+/// [crate::register::CrateInfo::transparent_external_adts]): we need them to
+/// reconstruct the `?` operator (see [crate::reconstruct_try]).
+pub fn is_always_transparent_adt(name: &TypeName) -> bool {
+    name.equals_ref_name(&RESULT_NAME) || name.equals_ref_name(&CONTROL_FLOW_NAME)
+}
+
+/// Return the variances of an assumed type in its (used) type parameters
+/// (see [type_to_used_params]).
+///
+/// If the user didn't give the variances of a type they declared, we
+/// consider that the type is invariant in its parameters (this is the
+/// conservative choice).
+pub fn type_to_variances(id: types::AssumedTy) -> Vec<types::Variance> {
+    match id {
+        types::AssumedTy::User(id) => {
+            let decl = get_user_assumed_ty(id);
+            match &decl.variances {
+                Some(variances) => variances.clone(),
+                None => decl
+                    .used_params
+                    .iter()
+                    .filter(|used| **used)
+                    .map(|_| types::Variance::Invariant)
+                    .collect(),
+            }
         }
+        _ => get_builtin_assumed_ty(id).variances.to_vec(),
     }
 }
 
+/// Return the variances of the assumed types. We export them for the
+/// backends, which need them to abstract the function signatures.
+///
+/// We only list the user-declared types whose variances were given by the
+/// user: we don't export the default variances (see [type_to_variances]).
+pub fn get_assumed_types_variances() -> Vec<(types::AssumedTy, Vec<types::Variance>)> {
+    let builtin = BUILTIN_ASSUMED_TYPES
+        .iter()
+        .map(|decl| (decl.id, decl.variances.to_vec()));
+    let user = get_user_assumed_decls()
+        .types
+        .iter()
+        .enumerate()
+        .filter_map(|(i, decl)| {
+            let id = types::AssumedTy::User(UserAssumedTyId::Id::new(i));
+            decl.variances.clone().map(|variances| (id, variances))
+        });
+    builtin.chain(user).collect()
+}
+
 pub struct FunInfo {
     pub used_type_params: Vec<bool>,
     // TODO: rename. "value_args"?
@@ -191,66 +891,24 @@ pub struct FunInfo {
 /// See the comments for [type_to_used_params]
 pub fn function_to_info(name: &FunName) -> Option<FunInfo> {
     trace!("{}", name);
-    match get_fun_id_from_name_full(name) {
-        Option::None => Option::None,
-        Option::Some(id) => {
-            let info = match id {
-                FunId::Panic => FunInfo {
-                    used_type_params: vec![],
-                    used_args: vec![true],
-                },
-                FunId::BeginPanic => FunInfo {
-                    used_type_params: vec![true],
-                    used_args: vec![true],
-                },
-                FunId::Replace => FunInfo {
-                    used_type_params: vec![true],
-                    used_args: vec![true, true],
-                },
-                FunId::BoxNew => FunInfo {
-                    used_type_params: vec![true],
-                    used_args: vec![true],
-                },
-                FunId::BoxDeref => FunInfo {
-                    used_type_params: vec![true],
-                    used_args: vec![true],
-                },
-                FunId::BoxDerefMut => FunInfo {
-                    used_type_params: vec![true],
-                    used_args: vec![true],
-                },
-                FunId::BoxFree => FunInfo {
-                    used_type_params: vec![true, false],
-                    used_args: vec![true, false],
-                },
-                FunId::VecNew => FunInfo {
-                    used_type_params: vec![true],
-                    used_args: vec![],
-                },
-                FunId::VecPush => FunInfo {
-                    used_type_params: vec![true, false],
-                    used_args: vec![true, true],
-                },
-                FunId::VecInsert => FunInfo {
-                    used_type_params: vec![true, false],
-                    used_args: vec![true, true, true],
-                },
-                FunId::VecLen => FunInfo {
-                    used_type_params: vec![true, false],
-                    used_args: vec![true],
-                },
-                FunId::VecIndex => FunInfo {
-                    // The second type parameter is for the index type (`usize` for vectors)
-                    used_type_params: vec![true, false],
-                    used_args: vec![true, true],
-                },
-                FunId::VecIndexMut => FunInfo {
-                    // The second type parameter is for the index type (`usize` for vectors)
-                    used_type_params: vec![true, false],
-                    used_args: vec![true, true],
-                },
-            };
-            Option::Some(info)
+    let info = match get_fun_id_from_name_full(name)? {
+        FunId::User(id) => {
+            let decl = get_user_assumed_fun(id);
+            FunInfo {
+                used_type_params: decl.used_type_params.clone(),
+                used_args: decl.used_args.clone(),
+            }
         }
-    }
+        _ => {
+            let decl = BUILTIN_ASSUMED_FUNS
+                .iter()
+                .find(|decl| name.equals_ref_name(decl.name))
+                .unwrap();
+            FunInfo {
+                used_type_params: decl.used_type_params.to_vec(),
+                used_args: decl.used_args.to_vec(),
+            }
+        }
+    };
+    Option::Some(info)
 }
@@ -5,6 +5,7 @@
 #![feature(box_syntax, box_patterns)]
 #![feature(is_some_and)]
 #![feature(cell_leak)] // For Ref::leak
+#![feature(once_cell)] // For OnceLock
 // For rustdoc: prevents overflows
 #![recursion_limit = "256"]
 
@@ -57,6 +58,7 @@ mod meta_utils;
 mod names;
 mod names_utils;
 mod reconstruct_asserts;
+mod reconstruct_try;
 mod regions_hierarchy;
 mod register;
 mod regularize_constant_adts;
@@ -65,8 +67,10 @@ mod remove_read_discriminant;
 mod remove_unused_locals;
 mod reorder_decls;
 mod rust_to_local_ids;
+mod simplify_iterators;
 mod simplify_ops;
 mod translate_functions_to_ullbc;
+mod translate_traits;
 mod translate_types;
 mod types;
 mod types_utils;
@@ -200,7 +204,11 @@ fn main() {
     // We don't need to check this case in order to use the default Rustc callbacks
     // instead of the Charon callback: because there is nothing to build, Rustc will
     // take care of everything and actually not call us back.
-    RunCompiler::new(&compiler_args, &mut CharonCallbacks { options })
-        .run()
-        .unwrap();
+    //
+    // The errors reported to the user abort the compilation: we catch them
+    // to exit with the same code as Rustc.
+    let exit_code = rustc_driver::catch_with_exit_code(|| {
+        RunCompiler::new(&compiler_args, &mut CharonCallbacks { options }).run()
+    });
+    std::process::exit(exit_code);
 }
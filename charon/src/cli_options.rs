@@ -51,6 +51,29 @@ pub struct CliOpts {
     /// Otherwise, use the standard borrow checker.
     #[structopt(long = "polonius")]
     pub use_polonius: bool,
+    /// Keep the cleanup blocks executed when unwinding after a panic, and the
+    /// edges leading to them from the calls and the drops. This is only
+    /// supported when extracting the unstructured LLBC (`--ullbc`).
+    #[structopt(long = "unwind")]
+    pub unwind: bool,
+    /// Extract the definitions of the external enumerations and of the
+    /// external structures whose fields are all public (e.g., `Ordering`),
+    /// instead of treating them as opaque types. Note that we always extract
+    /// the definitions of `Result` and `ControlFlow`, which are used by the
+    /// `?` operator.
+    #[structopt(long = "transparent-external-adts")]
+    pub transparent_external_adts: bool,
+    /// A list of paths (e.g., `utils` or `utils::vec`) identifying external
+    /// functions whose bodies we extract, when their MIR is available in the
+    /// metadata of their crate (which is the case of the generic and the
+    /// `#[inline]` functions). All the functions whose path starts with one
+    /// of those paths are extracted.
+    #[structopt(long = "transparent-extern")]
+    pub transparent_extern: Vec<String>,
+    /// A TOML file declaring additional primitive types and functions (see
+    /// [crate::assumed::UserAssumedDecls] for the format).
+    #[structopt(long = "assumed", parse(from_os_str))]
+    pub assumed_file: Option<PathBuf>,
     #[structopt(
         long = "no-code-duplication",
         help = "Check that no code duplication happens during control-flow reconstruction
@@ -12,6 +12,19 @@ use std::iter::FromIterator;
 /// Our redefinition of Result - we don't care much about the I/O part.
 pub type Result<T> = std::result::Result<T, ()>;
 
+/// The error returned by the translation entry points.
+///
+/// The errors are reported to the user (see [span_err]) or in the log as soon
+/// as they are detected: this type only signals that the translation failed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TranslationError;
+
+impl From<()> for TranslationError {
+    fn from(_: ()) -> Self {
+        TranslationError
+    }
+}
+
 /// Propagate the error from a callback to the caller :
 /// Used to avoid saving, checking and returning the result by hand.
 /// The callback will not be called again if it returned an error.
@@ -59,6 +72,13 @@ pub fn span_err(sess: &Session, span: rustc_span::Span, msg: &str) {
     sess.span_err(span, msg);
 }
 
+/// Report an error which is not linked to a location in the source code
+/// (for instance, an error in a configuration file).
+pub fn err_no_span(sess: &Session, msg: &str) {
+    log::error!("{}", msg);
+    sess.err(msg);
+}
+
 pub fn span_warn(sess: &Session, span: rustc_span::Span, msg: &str) {
     log::warn!("{}:\n{}", meta::span_to_string(sess, span), msg);
     sess.span_warn(span, msg);
@@ -12,26 +12,81 @@ fn statement_diverges(divergent: &HashMap<ast::FunDeclId::Id, bool>, st: &llbc::
         | RawStatement::SetDiscriminant(_, _)
         | RawStatement::Drop(_)
         | RawStatement::Assert(_)
-        | RawStatement::Panic
+        | RawStatement::Intrinsic(_, _)
+        | RawStatement::Panic(_)
         | RawStatement::Return
         | RawStatement::Break(_)
         | RawStatement::Continue(_)
         | RawStatement::Nop => false,
         RawStatement::Call(call) => match &call.func {
-            ast::FunId::Regular(id) => *divergent.get(id).unwrap(),
-            ast::FunId::Assumed(id) => match id {
-                ast::AssumedFunId::Replace
-                | ast::AssumedFunId::BoxNew
-                | ast::AssumedFunId::BoxDeref
-                | ast::AssumedFunId::BoxDerefMut
-                | ast::AssumedFunId::BoxFree
-                | ast::AssumedFunId::VecNew
-                | ast::AssumedFunId::VecPush
-                | ast::AssumedFunId::VecInsert
-                | ast::AssumedFunId::VecLen
-                | ast::AssumedFunId::VecIndex
-                | ast::AssumedFunId::VecIndexMut => false,
+            ast::FnOperand::Regular(func) => match func {
+                ast::FunId::Regular(id) => *divergent.get(id).unwrap(),
+                ast::FunId::Assumed(id) => match id {
+                    ast::AssumedFunId::Replace
+                    | ast::AssumedFunId::BoxNew
+                    | ast::AssumedFunId::BoxDeref
+                    | ast::AssumedFunId::BoxDerefMut
+                    | ast::AssumedFunId::BoxFree
+                    | ast::AssumedFunId::VecNew
+                    | ast::AssumedFunId::VecPush
+                    | ast::AssumedFunId::VecInsert
+                    | ast::AssumedFunId::VecLen
+                    | ast::AssumedFunId::VecIndex
+                    | ast::AssumedFunId::VecIndexMut
+                    | ast::AssumedFunId::SliceLen
+                    | ast::AssumedFunId::SliceIndexShared
+                    | ast::AssumedFunId::SliceIndexMut
+                    | ast::AssumedFunId::SliceSubsliceShared
+                    | ast::AssumedFunId::SliceSubsliceMut
+                    | ast::AssumedFunId::SliceGet
+                    | ast::AssumedFunId::SliceGetMut
+                    | ast::AssumedFunId::SliceSwap
+                    | ast::AssumedFunId::SliceSplitAt
+                    | ast::AssumedFunId::SliceSplitAtMut
+                    | ast::AssumedFunId::SliceIter
+                    | ast::AssumedFunId::SliceIterMut
+                    | ast::AssumedFunId::SliceToVec
+                    | ast::AssumedFunId::ArrayIndexShared
+                    | ast::AssumedFunId::ArrayIndexMut
+                    | ast::AssumedFunId::ArraySubsliceShared
+                    | ast::AssumedFunId::ArraySubsliceMut
+                    | ast::AssumedFunId::ArrayToSliceShared
+                    | ast::AssumedFunId::ArrayToSliceMut
+                    | ast::AssumedFunId::CellNew
+                    | ast::AssumedFunId::CellGet
+                    | ast::AssumedFunId::CellSet
+                    | ast::AssumedFunId::RefCellNew
+                    | ast::AssumedFunId::RefCellBorrow
+                    | ast::AssumedFunId::RefCellBorrowMut
+                    | ast::AssumedFunId::RcNew
+                    | ast::AssumedFunId::RcClone
+                    | ast::AssumedFunId::RcDeref
+                    | ast::AssumedFunId::ArcNew
+                    | ast::AssumedFunId::ArcClone
+                    | ast::AssumedFunId::ArcDeref
+                    | ast::AssumedFunId::ResultBranch
+                    | ast::AssumedFunId::ResultFromResidual
+                    | ast::AssumedFunId::OptionBranch
+                    | ast::AssumedFunId::OptionFromResidual
+                    | ast::AssumedFunId::IntoIter
+                    | ast::AssumedFunId::RangeNext
+                    | ast::AssumedFunId::RangeInclusiveNew
+                    | ast::AssumedFunId::RangeInclusiveNext
+                    | ast::AssumedFunId::SliceIterNext
+                    | ast::AssumedFunId::SliceIterMutNext => false,
+                    // The user-declared primitives are given a model by the
+                    // user: we consider that they terminate
+                    ast::AssumedFunId::User(_) => false,
+                },
+                // We don't know which function is called: it may diverge
+                ast::FunId::TraitMethod { .. } => true,
+                // None of the intrinsics we recognize diverge
+                ast::FunId::Intrinsic(_) => false,
+                // We don't know this intrinsic: it may diverge
+                ast::FunId::OpaqueIntrinsic(_) => true,
             },
+            // We don't know which function is called: it may diverge
+            ast::FnOperand::Move(_) => true,
         },
         RawStatement::Sequence(st1, st2) => {
             statement_diverges(divergent, st1) || statement_diverges(divergent, st2)
@@ -41,6 +96,8 @@ fn statement_diverges(divergent: &HashMap<ast::FunDeclId::Id, bool>, st: &llbc::
             tgts.iter().any(|st| statement_diverges(divergent, st))
         }
         RawStatement::Loop(_) => true,
+        // We don't know what the assembly code does: it may loop
+        RawStatement::InlineAsm(_) => true,
     }
 }
 
@@ -85,8 +142,11 @@ pub fn compute_divergent_functions(
                     divergent_map.insert(*id, true);
                 }
             }
-            DeclarationGroup::Type(_) | DeclarationGroup::Global(_) => {
-                // Ignore the type and global declarations
+            DeclarationGroup::Type(_)
+            | DeclarationGroup::Global(_)
+            | DeclarationGroup::TraitDecl(_)
+            | DeclarationGroup::TraitImpl(_) => {
+                // Ignore the type, global and trait declarations
                 continue;
             }
         }
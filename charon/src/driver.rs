@@ -1,5 +1,6 @@
 #![allow(dead_code)]
 
+use crate::assumed;
 use crate::cli_options;
 use crate::divergent;
 use crate::export;
@@ -8,6 +9,7 @@ use crate::get_mir::MirLevel;
 use crate::insert_assign_return_unit;
 use crate::llbc_ast::{CtxNames, FunDeclId, GlobalDeclId};
 use crate::reconstruct_asserts;
+use crate::reconstruct_try;
 use crate::register;
 use crate::regularize_constant_adts;
 use crate::remove_drop_never;
@@ -15,8 +17,10 @@ use crate::remove_read_discriminant;
 use crate::remove_unused_locals;
 use crate::reorder_decls;
 use crate::rust_to_local_ids;
+use crate::simplify_iterators;
 use crate::simplify_ops;
 use crate::translate_functions_to_ullbc;
+use crate::translate_traits;
 use crate::translate_types;
 use crate::ullbc_to_llbc;
 use regex::Regex;
@@ -51,7 +55,12 @@ impl Callbacks for CharonCallbacks {
             .peek_mut()
             .enter(|tcx| {
                 let session = c.session();
-                translate(session, tcx, self)
+                let res = translate(session, tcx, self);
+                // If the errors were reported to the user, stop cleanly
+                if res.is_err() {
+                    session.abort_if_errors();
+                }
+                res
             })
             .unwrap();
         Compilation::Stop
@@ -121,6 +130,16 @@ pub fn translate(sess: &Session, tcx: TyCtxt, internal: &CharonCallbacks) -> Res
     trace!();
     let options = &internal.options;
 
+    // Load the primitives declared by the user, if there are. Note that the
+    // names of the local items start with the name of the crate given by
+    // Rustc, even if the user specified a custom name.
+    if let Some(assumed_file) = &options.assumed_file {
+        let rustc_crate_name = tcx
+            .crate_name(rustc_span::def_id::LOCAL_CRATE)
+            .to_ident_string();
+        assumed::load_user_assumed_decls(sess, assumed_file, &rustc_crate_name)?;
+    }
+
     // Retrieve the crate name: if the user specified a custom name, use
     // it, otherwise retrieve it from Rustc.
     let crate_name: String = options.crate_name.as_deref().map_or_else(
@@ -166,6 +185,12 @@ pub fn translate(sess: &Session, tcx: TyCtxt, internal: &CharonCallbacks) -> Res
     let crate_info = register::CrateInfo {
         crate_name: crate_name.clone(),
         opaque_mods: HashSet::from_iter(options.opaque_modules.clone().into_iter()),
+        transparent_external_adts: options.transparent_external_adts,
+        transparent_extern: options
+            .transparent_extern
+            .iter()
+            .map(|path| path.split("::").map(|s| s.to_string()).collect())
+            .collect(),
     };
     let (files, registered_decls) = register::explore_crate(&crate_info, sess, tcx, mir_level)?;
     // panic!("PATCH registered_decls {:?}", registered_decls);
@@ -180,12 +205,19 @@ pub fn translate(sess: &Session, tcx: TyCtxt, internal: &CharonCallbacks) -> Res
     // # Step 3: generate identifiers for the types and functions, and compute
     // the mappings from rustc identifiers to our own identifiers.
     // Also compute identifiers for the files (we use them for the spans).
-    let ordered_decls = rust_to_local_ids::rust_to_local_ids(&files, &ordered_decls);
+    let ordered_decls = rust_to_local_ids::rust_to_local_ids(tcx, &files, &ordered_decls);
 
     // # Step 4: translate the types
     let (types_constraints, type_defs) =
         translate_types::translate_types(sess, tcx, &ordered_decls)?;
 
+    // Also translate the trait declarations and the trait implementations
+    // (they refer to the types, but the functions they contain are
+    // translated with the other functions at the next step)
+    let (trait_decls, trait_impls) =
+        translate_traits::translate_traits(sess, tcx, &ordered_decls, &type_defs)
+            .map_err(|_| ())?;
+
     // # Step 5: translate the functions to ULLBC (Unstructured LLBC).
     // Note that from now onwards, both type and function definitions have been
     // translated to our internal ASTs: we don't interact with rustc anymore.
@@ -196,6 +228,7 @@ pub fn translate(sess: &Session, tcx: TyCtxt, internal: &CharonCallbacks) -> Res
         &types_constraints,
         &type_defs,
         mir_level,
+        options.unwind,
     )?;
 
     //
@@ -221,7 +254,7 @@ pub fn translate(sess: &Session, tcx: TyCtxt, internal: &CharonCallbacks) -> Res
 
     // # Step 6: replace constant ([OperandConstantValue]) ADTs by regular
     // (Aggregated) ADTs.
-    regularize_constant_adts::transform(&fmt_ctx, &mut ullbc_funs, &mut ullbc_globals);
+    regularize_constant_adts::transform(&fmt_ctx, &type_defs, &mut ullbc_funs, &mut ullbc_globals);
 
     // # Step 7: extract statics and constant globals from operands (put them in
     // a let binding). This pass relies on the absence of constant ADTs from
@@ -243,6 +276,8 @@ pub fn translate(sess: &Session, tcx: TyCtxt, internal: &CharonCallbacks) -> Res
             &type_defs,
             &ullbc_funs,
             &ullbc_globals,
+            &trait_decls,
+            &trait_impls,
             &options.dest_dir,
         )?;
     } else {
@@ -255,7 +290,11 @@ pub fn translate(sess: &Session, tcx: TyCtxt, internal: &CharonCallbacks) -> Res
             &ullbc_globals,
         );
 
-        // # Step 9: simplify the calls to unops and binops
+        // # Step 9: simplify the calls to `IntoIterator::into_iter` introduced
+        // by the `for` loops over ranges and slices
+        simplify_iterators::transform(&fmt_ctx, &mut llbc_funs, &mut llbc_globals);
+
+        // # Step 10: simplify the calls to unops and binops
         // Note that we assume that the sequences have been flattened.
         simplify_ops::simplify(options.release, &fmt_ctx, &mut llbc_funs, &mut llbc_globals);
 
@@ -266,7 +305,7 @@ pub fn translate(sess: &Session, tcx: TyCtxt, internal: &CharonCallbacks) -> Res
             );
         }
 
-        // # Step 10: reconstruct the asserts
+        // # Step 11: reconstruct the asserts
         reconstruct_asserts::transform(&fmt_ctx, &mut llbc_funs, &mut llbc_globals);
 
         for def in &llbc_funs {
@@ -276,10 +315,14 @@ pub fn translate(sess: &Session, tcx: TyCtxt, internal: &CharonCallbacks) -> Res
             );
         }
 
-        // # Step 11: Remove the discriminant reads (merge them with the switches)
+        // # Step 12: reconstruct the `?` operator applied to results and options.
+        // This must be done before removing the discriminant reads.
+        reconstruct_try::transform(&fmt_ctx, &mut llbc_funs, &mut llbc_globals);
+
+        // # Step 13: Remove the discriminant reads (merge them with the switches)
         remove_read_discriminant::transform(&fmt_ctx, &mut llbc_funs, &mut llbc_globals);
 
-        // # Step 12: add the missing assignments to the return value.
+        // # Step 14: add the missing assignments to the return value.
         // When the function return type is unit, the generated MIR doesn't
         // set the return value to `()`. This can be a concern: in the case
         // of Aeneas, it means the return variable contains ⊥ upon returning.
@@ -289,15 +332,15 @@ pub fn translate(sess: &Session, tcx: TyCtxt, internal: &CharonCallbacks) -> Res
         // the main or at compile-time).
         insert_assign_return_unit::transform(&fmt_ctx, &mut llbc_funs, &mut llbc_globals);
 
-        // # Step 13: remove the drops of locals whose type is `Never` (`!`). This
+        // # Step 15: remove the drops of locals whose type is `Never` (`!`). This
         // is in preparation of the next transformation.
         remove_drop_never::transform(&fmt_ctx, &mut llbc_funs, &mut llbc_globals);
 
-        // # Step 14: remove the locals which are never used. After doing so, we
+        // # Step 16: remove the locals which are never used. After doing so, we
         // check that there are no remaining locals with type `Never`.
         remove_unused_locals::transform(&fmt_ctx, &mut llbc_funs, &mut llbc_globals);
 
-        // # Step 15: compute which functions are potentially divergent. A function
+        // # Step 17: compute which functions are potentially divergent. A function
         // is potentially divergent if it is recursive, contains a loop or transitively
         // calls a potentially divergent function.
         // Note that in the future, we may complement this basic analysis with a
@@ -306,13 +349,15 @@ pub fn translate(sess: &Session, tcx: TyCtxt, internal: &CharonCallbacks) -> Res
         // Because we don't have loops, constants are not yet touched.
         let _divergent = divergent::compute_divergent_functions(&ordered_decls, &llbc_funs);
 
-        // # Step 16: generate the files.
+        // # Step 18: generate the files.
         export::export_llbc(
             crate_name,
             &ordered_decls,
             &type_defs,
             &llbc_funs,
             &llbc_globals,
+            &trait_decls,
+            &trait_impls,
             &options.dest_dir,
         )?;
     }
@@ -1,10 +1,11 @@
+use crate::assumed::{get_assumed_types_variances, get_user_assumed_decls, UserAssumedDecls};
 use crate::common::*;
 use crate::llbc_ast;
 use crate::meta::{FileId, FileName};
 use crate::rust_to_local_ids::*;
 use crate::types::*;
 use crate::ullbc_ast;
-use crate::ullbc_ast::{FunDeclId, GlobalDeclId};
+use crate::ullbc_ast::{FunDeclId, GlobalDeclId, TraitDecls, TraitImpls};
 use serde::{Serialize, Serializer};
 use std::fs::File;
 use std::path::PathBuf;
@@ -45,17 +46,27 @@ struct GCrateSerializer<'a, FD: Serialize + Clone, GD: Serialize + Clone> {
     types: &'a TypeDeclId::Vector<TypeDecl>,
     functions: &'a FunDeclId::Vector<FD>,
     globals: &'a GlobalDeclId::Vector<GD>,
+    trait_decls: &'a TraitDecls,
+    trait_impls: &'a TraitImpls,
+    /// The primitives declared by the user
+    assumed: &'a UserAssumedDecls,
+    /// The variances of the assumed types in their (used) type parameters.
+    /// The user-declared types whose variances are unknown are not listed.
+    assumed_variances: Vec<(AssumedTy, Vec<Variance>)>,
 }
 
 /// Export the translated definitions to a JSON file.
 ///
 /// This is a generic function, used both for LLBC and ULLBC.
+#[allow(clippy::too_many_arguments)]
 pub fn gexport<FD: Serialize + Clone, GD: Serialize + Clone>(
     crate_name: String,
     ordered_decls: &OrderedDecls,
     type_defs: &TypeDecls,
     fun_defs: &FunDeclId::Vector<FD>,
     global_defs: &GlobalDeclId::Vector<GD>,
+    trait_decls: &TraitDecls,
+    trait_impls: &TraitImpls,
     dest_dir: &Option<PathBuf>,
     extension: &str,
 ) -> Result<()> {
@@ -85,6 +96,10 @@ pub fn gexport<FD: Serialize + Clone, GD: Serialize + Clone>(
         types: &type_defs.types,
         functions: fun_defs,
         globals: global_defs,
+        trait_decls,
+        trait_impls,
+        assumed: get_user_assumed_decls(),
+        assumed_variances: get_assumed_types_variances(),
     };
 
     // Create the directory, if necessary (note that if the target directory
@@ -124,12 +139,15 @@ pub fn gexport<FD: Serialize + Clone, GD: Serialize + Clone>(
 }
 
 /// Export the translated ULLBC definitions to a JSON file.
+#[allow(clippy::too_many_arguments)]
 pub fn export_ullbc(
     crate_name: String,
     ordered_decls: &OrderedDecls,
     type_defs: &TypeDecls,
     fun_defs: &FunDeclId::Vector<ullbc_ast::FunDecl>,
     global_defs: &GlobalDeclId::Vector<ullbc_ast::GlobalDecl>,
+    trait_decls: &TraitDecls,
+    trait_impls: &TraitImpls,
     dest_dir: &Option<PathBuf>,
 ) -> Result<()> {
     gexport(
@@ -138,18 +156,23 @@ pub fn export_ullbc(
         type_defs,
         fun_defs,
         global_defs,
+        trait_decls,
+        trait_impls,
         dest_dir,
         "ullbc",
     )
 }
 
 /// Export the translated LLBC definitions to a JSON file.
+#[allow(clippy::too_many_arguments)]
 pub fn export_llbc(
     crate_name: String,
     ordered_decls: &OrderedDecls,
     type_defs: &TypeDecls,
     fun_defs: &FunDeclId::Vector<llbc_ast::FunDecl>,
     global_defs: &GlobalDeclId::Vector<llbc_ast::GlobalDecl>,
+    trait_decls: &TraitDecls,
+    trait_impls: &TraitImpls,
     dest_dir: &Option<PathBuf>,
 ) -> Result<()> {
     gexport(
@@ -158,6 +181,8 @@ pub fn export_llbc(
         type_defs,
         fun_defs,
         global_defs,
+        trait_decls,
+        trait_impls,
         dest_dir,
         "llbc",
     )
@@ -1,6 +1,7 @@
 //! Implements expressions: paths, operands, rvalues, lvalues
 
 pub use crate::expressions_utils::*;
+use crate::gast::FunId;
 use crate::types::*;
 use crate::values::*;
 use im::Vector; // TODO: im::Vector is not necessary anymore
@@ -53,6 +54,25 @@ pub enum ProjectionElem {
     /// (for pretty printing for instance). We retrieve it through
     /// type-checking.
     Field(FieldProjKind, FieldId::Id),
+    /// Indexing an array or a slice with a local variable (`a[i]`). As in MIR,
+    /// the index is stored in a local variable.
+    Index(VarId::Id),
+    /// Indexing an array or a slice with a constant offset. This is introduced
+    /// by slice patterns, for instance for `x` and `y` in `[x, .., y]`.
+    ///
+    /// If `from_end` is true, the index is `len - offset`. `min_length` is the
+    /// minimal length of the array or slice.
+    ConstantIndex {
+        offset: u64,
+        min_length: u64,
+        from_end: bool,
+    },
+    /// A sub-slice or a sub-array, introduced by slice patterns (for instance,
+    /// for `rest` in `[x, rest @ ..]`).
+    ///
+    /// If `from_end` is true, this selects the elements `from..(len - to)`,
+    /// otherwise it selects the elements `from..to`.
+    Subslice { from: u64, to: u64, from_end: bool },
 }
 
 #[derive(Debug, PartialEq, Eq, Copy, Clone, EnumIsA, EnumAsGetters, Serialize)]
@@ -65,6 +85,10 @@ pub enum FieldProjKind {
     /// If we project from a tuple, the projection kind gives the arity of the
     #[serde(rename = "ProjTuple")]
     Tuple(usize),
+    /// Projection on the field of a union (a read or a write, reading the
+    /// memory as if it were of the type of the field)
+    #[serde(rename = "ProjUnion")]
+    Union(TypeDeclId::Id),
 }
 
 #[derive(Debug, PartialEq, Eq, Copy, Clone, EnumIsA, EnumAsGetters, Serialize)]
@@ -83,20 +107,40 @@ pub enum BorrowKind {
 }
 
 /// Unary operation
-#[derive(Debug, PartialEq, Eq, Copy, Clone, EnumIsA, VariantName, Serialize)]
+#[derive(Debug, PartialEq, Eq, Clone, EnumIsA, VariantName, Serialize)]
 pub enum UnOp {
     Not,
     /// This can overflow. In practice, rust introduces an assert before
     /// (in debug mode) to check that it is not equal to the minimum integer
-    /// value (for the proper type).
+    /// value (for the proper type). Note that negating a floating-point
+    /// number can't fail, and there is no assert in this case.
     Neg,
-    /// Casts are rvalues in MIR, but we treat them as unops. For now, we
-    /// only support for integer to integer, but we can also do from integers/booleans
-    /// to integers/booleans. For now, we don't handle pointer casts.
+    /// Casts are rvalues in MIR, but we treat them as unops.
+    Cast(CastKind),
+}
+
+/// The kind of a cast. See [UnOp::Cast].
+///
+/// In every case, the first type gives the source type, and the second one
+/// gives the destination type.
+#[derive(Debug, PartialEq, Eq, Clone, EnumIsA, VariantName, Serialize)]
+pub enum CastKind {
+    /// Conversion between scalar types: integers, floating-point numbers,
+    /// booleans and characters (`x as u32`, `x as f64`, `b as u8`,
+    /// `c as u32`, `x as char`...).
+    Scalar(ETy, ETy),
+    /// Conversion of a fieldless enumeration to an integer
+    /// (`MyEnum::A as i32`): we read the discriminant of the enumeration.
     ///
-    /// The first integer type gives the source type, the second one gives
-    /// the destination type.
-    Cast(IntegerTy, IntegerTy),
+    /// MIR decomposes this cast into a discriminant read followed by a
+    /// cast of the discriminant: we merge those back.
+    EnumDiscriminant(ETy, ETy),
+    /// Unsizing coercion of a pointer, for instance from `&T` to
+    /// `&dyn Trait`, from `Box<T>` to `Box<dyn Trait>`, or from `&[T; N]`
+    /// to `&[T]`.
+    Unsize(ETy, ETy),
+    /// Cast involving raw pointers.
+    RawPtr(PtrCastKind, ETy, ETy),
 }
 
 /// Binary operations.
@@ -173,6 +217,20 @@ pub enum OperandConstantValue {
     ///
     /// Same as for constants, except that statics are accessed through references.
     StaticId(GlobalDeclId::Id),
+    ///
+    /// A function item used as a value (for instance, `f` in `let g = f;`).
+    FnPtr(FnPtr),
+}
+
+/// A function item used as a value. See [OperandConstantValue::FnPtr].
+#[derive(Debug, PartialEq, Eq, Clone, Serialize)]
+pub enum FnPtr {
+    /// A function, with its generic arguments (as for the function calls,
+    /// see [crate::ullbc_ast::RawTerminator::Call])
+    Fun(FunId, Vec<ErasedRegion>, Vec<ETy>, Vec<ConstGeneric>),
+    /// The constructor of a tuple structure or of an enumeration variant,
+    /// used as a function. For instance, `Some` in `x.map(Some)`.
+    Ctor(AggregateKind),
 }
 
 /// TODO: we could factor out [Rvalue] and function calls (for LLBC, not ULLBC).
@@ -206,12 +264,33 @@ pub enum Rvalue<R> {
     /// to have a look at expansion of `Bottom` values for explanations about the
     /// other case.
     Aggregate(AggregateKind, Vec<Operand>),
+    /// Array initialization by repetition of a value: `[x; 32]`.
+    /// We store the type of the elements and the length of the array.
+    Repeat(Operand, ETy, ConstGeneric),
+    /// Length of an array or of a slice.
+    Len(Place),
+    /// Raw pointer to a place: `&raw const x` or `&raw mut x` (`AddressOf`
+    /// in MIR). This is also introduced by the coercions from references
+    /// to raw pointers.
+    RawPtr(Place, RefKind),
     /// Not present in MIR: we introduce it when replacing constant variables
     /// in operands in [extract_global_assignments.rs]
     Global(GlobalDeclId::Id),
 }
 
-#[derive(Debug, Clone)]
+/// The kind of a cast involving raw pointers. See [CastKind::RawPtr].
+#[derive(Debug, PartialEq, Eq, Copy, Clone, EnumIsA, VariantName, Serialize)]
+pub enum PtrCastKind {
+    /// Cast between raw pointers, for instance `p as *mut u8` or
+    /// `p as *const T` with `p: *mut T`.
+    PtrToPtr,
+    /// Cast of a raw pointer to an integer: `p as usize`.
+    PtrToInt,
+    /// Cast of an integer to a raw pointer: `a as *const T`.
+    IntToPtr,
+}
+
+#[derive(Debug, PartialEq, Eq, Clone)]
 pub enum AggregateKind {
     Tuple,
     // TODO: treat Option in a general manner (we should extract the definitions
@@ -223,5 +302,8 @@ pub enum AggregateKind {
         Option<VariantId::Id>,
         Vec<ErasedRegion>,
         Vec<ETy>,
+        Vec<ConstGeneric>,
     ),
+    /// An array literal (`[x, y, z]`): we store the type of the elements.
+    Array(ETy),
 }
@@ -5,6 +5,8 @@ use crate::assumed;
 use crate::common::*;
 use crate::expressions::*;
 use crate::formatter::Formatter;
+use crate::gast::FunId;
+use crate::gast_utils::trait_decl_id_to_pretty_string;
 use crate::types::*;
 use crate::ullbc_ast::GlobalDeclId;
 use crate::values;
@@ -51,7 +53,27 @@ impl std::string::ToString for UnOp {
         match self {
             UnOp::Not => "~".to_string(),
             UnOp::Neg => "-".to_string(),
-            UnOp::Cast(src, tgt) => format!("cast<{src},{tgt}>"),
+            UnOp::Cast(kind) => kind.fmt_with_ctx(&values::DummyFormatter {}),
+        }
+    }
+}
+
+impl CastKind {
+    pub fn fmt_with_ctx<'a, T>(&'a self, ctx: &T) -> String
+    where
+        T: Formatter<TypeVarId::Id> + Formatter<TypeDeclId::Id> + Formatter<&'a ErasedRegion>,
+    {
+        match self {
+            CastKind::Scalar(src, tgt)
+            | CastKind::EnumDiscriminant(src, tgt)
+            | CastKind::RawPtr(_, src, tgt) => {
+                format!("cast<{},{}>", src.fmt_with_ctx(ctx), tgt.fmt_with_ctx(ctx))
+            }
+            CastKind::Unsize(src, tgt) => format!(
+                "unsize<{},{}>",
+                src.fmt_with_ctx(ctx),
+                tgt.fmt_with_ctx(ctx)
+            ),
         }
     }
 }
@@ -115,10 +137,35 @@ impl Place {
                     FieldProjKind::Tuple(_) => {
                         out = format!("({out}).{field_id}");
                     }
+                    FieldProjKind::Union(adt_id) => {
+                        let field_name = ctx.format_object((*adt_id, None, *field_id));
+                        out = format!("({out}).{field_name}");
+                    }
                     FieldProjKind::Option(_) => {
                         out = format!("({out}).{field_id}");
                     }
                 },
+                ProjectionElem::Index(var_id) => {
+                    out = format!("({out})[{}]", ctx.format_object(*var_id));
+                }
+                ProjectionElem::ConstantIndex {
+                    offset,
+                    min_length: _,
+                    from_end,
+                } => {
+                    if *from_end {
+                        out = format!("({out})[-{offset}]");
+                    } else {
+                        out = format!("({out})[{offset}]");
+                    }
+                }
+                ProjectionElem::Subslice { from, to, from_end } => {
+                    if *from_end {
+                        out = format!("({out})[{from}..-{to}]");
+                    } else {
+                        out = format!("({out})[{from}..{to}]");
+                    }
+                }
             }
         }
 
@@ -157,6 +204,49 @@ impl OperandConstantValue {
             }
             OperandConstantValue::ConstantId(id) => ctx.format_object(*id),
             OperandConstantValue::StaticId(id) => format!("alloc: &{}", ctx.format_object(*id)),
+            OperandConstantValue::FnPtr(fn_ptr) => fn_ptr.fmt_with_ctx(ctx),
+        }
+    }
+}
+
+impl FnPtr {
+    pub fn fmt_with_ctx<T>(&self, ctx: &T) -> String
+    where
+        T: Formatter<TypeDeclId::Id>,
+    {
+        match self {
+            FnPtr::Fun(fun_id, _, _, _) => match fun_id {
+                // We don't have the function names in the context: the
+                // printing utilities are mostly for debugging anyway
+                FunId::Regular(id) => format!("@Fun{id}"),
+                FunId::Assumed(aid) => format!("@{aid:?}"),
+                FunId::TraitMethod {
+                    trait_id,
+                    method,
+                    impl_source: _,
+                } => format!("{}::{method}", trait_decl_id_to_pretty_string(*trait_id)),
+                FunId::Intrinsic(id) => {
+                    format!("@{}", assumed::get_name_from_intrinsic_id(*id))
+                }
+                FunId::OpaqueIntrinsic(name) => format!("@{name}"),
+            },
+            FnPtr::Ctor(kind) => match kind {
+                AggregateKind::Tuple => "@Tuple".to_string(),
+                AggregateKind::Array(_) => unreachable!(),
+                AggregateKind::Option(variant_id, _) => {
+                    if *variant_id == assumed::OPTION_NONE_VARIANT_ID {
+                        "@Option::None".to_string()
+                    } else {
+                        "@Option::Some".to_string()
+                    }
+                }
+                AggregateKind::Adt(def_id, variant_id, _, _, _) => match variant_id {
+                    None => ctx.format_object(*def_id),
+                    Some(variant_id) => {
+                        format!("{}::@Variant{variant_id}", ctx.format_object(*def_id))
+                    }
+                },
+            },
         }
     }
 }
@@ -215,6 +305,9 @@ impl Rvalue {
                 }
                 BorrowKind::Shallow => format!("&shallow {}", place.fmt_with_ctx(ctx)),
             },
+            Rvalue::UnaryOp(UnOp::Cast(kind), x) => {
+                format!("{}({})", kind.fmt_with_ctx(ctx), x.fmt_with_ctx(ctx))
+            }
             Rvalue::UnaryOp(unop, x) => {
                 format!("{}({})", unop.to_string(), x.fmt_with_ctx(ctx))
             }
@@ -231,6 +324,7 @@ impl Rvalue {
                 let ops_s: Vec<String> = ops.iter().map(|op| op.fmt_with_ctx(ctx)).collect();
                 match kind {
                     AggregateKind::Tuple => format!("({})", ops_s.join(", ")),
+                    AggregateKind::Array(_) => format!("[{}]", ops_s.join(", ")),
                     AggregateKind::Option(variant_id, _) => {
                         if *variant_id == assumed::OPTION_NONE_VARIANT_ID {
                             assert!(ops.is_empty());
@@ -242,7 +336,7 @@ impl Rvalue {
                             unreachable!();
                         }
                     }
-                    AggregateKind::Adt(def_id, variant_id, _, _) => {
+                    AggregateKind::Adt(def_id, variant_id, _, _, _) => {
                         // Format every field
                         let mut fields = vec![];
                         for (i, op) in ops.iter().enumerate() {
@@ -259,6 +353,12 @@ impl Rvalue {
                     }
                 }
             }
+            Rvalue::Repeat(op, _, len) => format!("[{}; {}]", op.fmt_with_ctx(ctx), len),
+            Rvalue::Len(place) => format!("len({})", place.fmt_with_ctx(ctx)),
+            Rvalue::RawPtr(place, kind) => match kind {
+                RefKind::Shared => format!("&raw const {}", place.fmt_with_ctx(ctx)),
+                RefKind::Mut => format!("&raw mut {}", place.fmt_with_ctx(ctx)),
+            },
             Rvalue::Global(gid) => ctx.format_object(*gid),
         }
     }
@@ -300,9 +400,9 @@ impl Serialize for AggregateKind {
 
                 vs.end()
             }
-            AggregateKind::Adt(def_id, opt_variant_id, regions, tys) => {
+            AggregateKind::Adt(def_id, opt_variant_id, regions, tys, cgs) => {
                 let mut vs =
-                    serializer.serialize_tuple_variant("AggregateKind", 1, "AggregatedAdt", 4)?;
+                    serializer.serialize_tuple_variant("AggregateKind", 1, "AggregatedAdt", 5)?;
 
                 vs.serialize_field(def_id)?;
                 vs.serialize_field(opt_variant_id)?;
@@ -310,6 +410,16 @@ impl Serialize for AggregateKind {
                 vs.serialize_field(&regions)?;
                 let tys = VecSerializer::new(tys);
                 vs.serialize_field(&tys)?;
+                let cgs = VecSerializer::new(cgs);
+                vs.serialize_field(&cgs)?;
+
+                vs.end()
+            }
+            AggregateKind::Array(ty) => {
+                let mut vs =
+                    serializer.serialize_tuple_variant("AggregateKind", 3, "AggregatedArray", 1)?;
+
+                vs.serialize_field(ty)?;
 
                 vs.end()
             }
@@ -324,8 +434,19 @@ impl Serialize for OperandConstantValue {
     {
         match self {
             // [OperandConstantValue] exists only to handle temporary cases inherited from the MIR:
-            // for the final (U)LLBC format, we simply export the underlying constant value.
+            // for the final (U)LLBC format, we simply export the underlying constant value
+            // (or the function item, for the functions used as values).
             OperandConstantValue::PrimitiveValue(cv) => cv.serialize(serializer),
+            OperandConstantValue::FnPtr(fn_ptr) => {
+                let mut vs = serializer.serialize_tuple_variant(
+                    "OperandConstantValue",
+                    0,
+                    "FnPtr",
+                    1,
+                )?;
+                vs.serialize_field(fn_ptr)?;
+                vs.end()
+            }
             _ => unreachable!("unexpected `{:?}`: `OperandConstantValue` fields other than `ConstantValue` are temporary and should not occur in serialized LLBC", self),
         }
     }
@@ -2,10 +2,12 @@
 #![allow(dead_code)]
 
 pub use crate::expressions::GlobalDeclId;
+use crate::expressions::{BinOp, Operand, Place};
 pub use crate::gast_utils::*;
-use crate::meta::Meta;
+use crate::meta::{Meta, Span};
 use crate::names::FunName;
 use crate::names::GlobalName;
+use crate::names::{TraitImplName, TraitName};
 use crate::regions_hierarchy::RegionGroups;
 use crate::types::*;
 use crate::values::*;
@@ -17,6 +19,9 @@ use serde::Serialize;
 pub static TAB_INCR: &str = "    ";
 
 generate_index_type!(FunDeclId);
+generate_index_type!(TraitDeclId);
+generate_index_type!(TraitImplId);
+generate_index_type!(UserAssumedFunId);
 
 /// A variable
 #[derive(Debug, Clone, Serialize)]
@@ -54,6 +59,7 @@ pub struct FunSig {
     /// The lifetime's hierarchy between the different regions.
     pub regions_hierarchy: RegionGroups,
     pub type_params: TypeVarId::Vector<TypeVar>,
+    pub const_generic_params: ConstGenericVarId::Vector<ConstGenericVar>,
     pub inputs: Vec<RTy>,
     pub output: RTy,
 }
@@ -85,6 +91,10 @@ pub struct GFunDecl<T: std::fmt::Debug + Clone + Serialize> {
     /// The signature contains the inputs/output types *with* non-erased regions.
     /// It also contains the list of region and type parameters.
     pub signature: FunSig,
+    /// `true` if the function is declared `unsafe`, or if its body performs
+    /// unsafe operations (operations on raw pointers, calls to unsafe
+    /// functions).
+    pub is_unsafe: bool,
     /// The function body, in case the function is not opaque.
     /// Opaque functions are: external functions, or local functions tagged
     /// as opaque.
@@ -102,8 +112,74 @@ pub struct GGlobalDecl<T: std::fmt::Debug + Clone + Serialize> {
     pub body: Option<GExprBody<T>>,
 }
 
+/// The name of a trait item (for now: a method name)
+pub type TraitItemName = String;
+
+/// A trait declaration.
+///
+/// The trait methods are registered as regular function declarations: the
+/// required methods are opaque (they don't have a body) while the provided
+/// methods are transparent. The method signatures are thus given by the
+/// corresponding [GFunDecl]s.
+#[derive(Debug, Clone, Serialize)]
+pub struct TraitDecl {
+    pub def_id: TraitDeclId::Id,
+    /// The meta data associated with the declaration.
+    pub meta: Meta,
+    pub name: TraitName,
+    pub region_params: RegionVarId::Vector<RegionVar>,
+    /// The type parameters of the trait. Note that the first type parameter
+    /// is always `Self`: the trait arguments given in a [TraitImpl] (and in
+    /// a [crate::types::TraitRef]) follow the same convention.
+    pub type_params: TypeVarId::Vector<TypeVar>,
+    pub const_generic_params: ConstGenericVarId::Vector<ConstGenericVar>,
+    /// The names of the associated types declared by the trait.
+    pub types: Vec<TraitItemName>,
+    /// The trait methods, in case the trait is not opaque.
+    /// Opaque traits are: external traits, or local traits tagged as opaque.
+    pub methods: Option<Vec<(TraitItemName, FunDeclId::Id)>>,
+}
+
+/// A trait implementation, i.e., an `impl Trait for Type { ... }` block.
+///
+/// Inherent `impl` blocks are not translated to declarations: we only
+/// translate the functions they contain.
+#[derive(Debug, Clone, Serialize)]
+pub struct TraitImpl {
+    pub def_id: TraitImplId::Id,
+    /// The meta data associated with the declaration.
+    pub meta: Meta,
+    pub name: TraitImplName,
+    /// The generic parameters introduced by the `impl` block.
+    /// Contrary to [TraitDecl::type_params], there is no implicit `Self`
+    /// parameter.
+    pub region_params: RegionVarId::Vector<RegionVar>,
+    pub type_params: TypeVarId::Vector<TypeVar>,
+    pub const_generic_params: ConstGenericVarId::Vector<ConstGenericVar>,
+    /// The implemented trait
+    pub impl_trait: TraitDeclId::Id,
+    /// The region arguments given to the implemented trait
+    pub trait_region_args: Vec<Region<RegionVarId::Id>>,
+    /// The type arguments given to the implemented trait: they are in one to
+    /// one correspondence with [TraitDecl::type_params], so the first one is
+    /// the type for which we implement the trait (`Self`).
+    pub trait_type_args: Vec<RTy>,
+    /// The const generic arguments given to the implemented trait
+    pub trait_const_generic_args: Vec<ConstGeneric>,
+    /// The definitions of the associated types.
+    /// `None` if the implementation is opaque.
+    pub types: Option<Vec<(TraitItemName, RTy)>>,
+    /// Map from the trait methods to the functions implementing them.
+    /// The methods which are not listed here are provided by the trait.
+    /// `None` if the implementation is opaque.
+    pub methods: Option<Vec<(TraitItemName, FunDeclId::Id)>>,
+}
+
+pub type TraitDecls = TraitDeclId::Vector<TraitDecl>;
+pub type TraitImpls = TraitImplId::Vector<TraitImpl>;
+
 /// A function identifier. See [crate::ullbc_ast::Terminator]
-#[derive(Debug, Clone, EnumIsA, EnumAsGetters, VariantName, Serialize)]
+#[derive(Debug, PartialEq, Eq, Clone, EnumIsA, EnumAsGetters, VariantName, Serialize)]
 pub enum FunId {
     /// A "regular" function (function local to the crate, external function
     /// not treated as a primitive one).
@@ -112,11 +188,52 @@ pub enum FunId {
     /// `alloc::boxed::Box::new`).
     /// TODO: rename to "Primitive"
     Assumed(AssumedFunId),
+    /// A trait method whose implementation could not be statically resolved.
+    /// Note that whenever the implementation is known, we directly refer to
+    /// the function implementing the method, through [FunId::Regular].
+    TraitMethod {
+        /// The trait declaring the method
+        trait_id: TraitDeclId::Id,
+        method: TraitItemName,
+        impl_source: TraitImplSource,
+    },
+    /// A compiler intrinsic that we recognize (`core::intrinsics::size_of`, etc.).
+    Intrinsic(IntrinsicFunId),
+    /// A compiler intrinsic that we don't recognize: we only keep its name
+    /// (for instance: `"ctpop"` for `core::intrinsics::ctpop`).
+    OpaqueIntrinsic(String),
+}
+
+/// The function called by a function call. See [crate::ullbc_ast::Terminator]
+#[derive(Debug, Clone, EnumIsA, EnumAsGetters, VariantName, Serialize)]
+pub enum FnOperand {
+    /// A function we statically know: the generic arguments of the call
+    /// apply to this function.
+    Regular(FunId),
+    /// A function pointer stored in a place, for instance `f` in `f(x)`
+    /// where `f` has type `fn(u32) -> u32`. The generic arguments of
+    /// the call are empty in this case.
+    Move(Place),
+}
+
+/// Where the implementation of a trait method comes from, when we could not
+/// resolve it statically. See [FunId::TraitMethod].
+#[derive(Debug, PartialEq, Eq, Clone, Copy, EnumIsA, Serialize)]
+pub enum TraitImplSource {
+    /// The implementation is provided by a trait clause of the current
+    /// definition, for instance: `fn f<T: Trait>(x: T)`.
+    Param,
+    /// The implementation is automatically generated by the compiler (for
+    /// instance, `Clone` for tuples).
+    Builtin,
+    /// The method is called on a trait object (`dyn Trait`): the
+    /// implementation is looked up at runtime, in the vtable of the object.
+    Dyn,
 }
 
 /// An assumed function identifier, identifying a function coming from a
 /// standard library.
-#[derive(Debug, Clone, Copy, EnumIsA, EnumAsGetters, Serialize)]
+#[derive(Debug, PartialEq, Eq, Clone, Copy, EnumIsA, EnumAsGetters, Serialize)]
 pub enum AssumedFunId {
     /// `core::mem::replace`
     Replace,
@@ -156,4 +273,186 @@ pub enum AssumedFunId {
     VecIndex,
     /// `core::ops::index::IndexMut::index_mut<alloc::vec::Vec<T>, usize>`
     VecIndexMut,
+    /// `core::slice::[T]::len`
+    SliceLen,
+    /// `core::ops::index::Index::index<[T], usize>`
+    SliceIndexShared,
+    /// `core::ops::index::IndexMut::index_mut<[T], usize>`
+    SliceIndexMut,
+    /// `core::ops::index::Index::index<[T], core::ops::range::Range<usize>>`
+    SliceSubsliceShared,
+    /// `core::ops::index::IndexMut::index_mut<[T], core::ops::range::Range<usize>>`
+    SliceSubsliceMut,
+    /// `core::slice::[T]::get<usize>`
+    SliceGet,
+    /// `core::slice::[T]::get_mut<usize>`
+    SliceGetMut,
+    /// `core::slice::[T]::swap`
+    SliceSwap,
+    /// `core::slice::[T]::split_at`
+    SliceSplitAt,
+    /// `core::slice::[T]::split_at_mut`
+    SliceSplitAtMut,
+    /// `core::slice::[T]::iter`
+    SliceIter,
+    /// `core::slice::[T]::iter_mut`
+    SliceIterMut,
+    /// `alloc::slice::[T]::to_vec`
+    SliceToVec,
+    /// `core::ops::index::Index::index<[T; N], usize>`
+    ArrayIndexShared,
+    /// `core::ops::index::IndexMut::index_mut<[T; N], usize>`
+    ArrayIndexMut,
+    /// `core::ops::index::Index::index<[T; N], core::ops::range::Range<usize>>`
+    ArraySubsliceShared,
+    /// `core::ops::index::IndexMut::index_mut<[T; N], core::ops::range::Range<usize>>`
+    ArraySubsliceMut,
+    /// `core::array::[T; N]::as_slice`
+    ArrayToSliceShared,
+    /// `core::array::[T; N]::as_mut_slice`
+    ArrayToSliceMut,
+    /// `core::cell::Cell::new`
+    CellNew,
+    /// `core::cell::Cell::get`
+    CellGet,
+    /// `core::cell::Cell::set`
+    CellSet,
+    /// `core::cell::RefCell::new`
+    RefCellNew,
+    /// `core::cell::RefCell::borrow`. Note that the returned guard
+    /// (`core::cell::Ref`) is not an assumed type.
+    RefCellBorrow,
+    /// `core::cell::RefCell::borrow_mut`. Note that the returned guard
+    /// (`core::cell::RefMut`) is not an assumed type.
+    RefCellBorrowMut,
+    /// `alloc::rc::Rc::new`
+    RcNew,
+    /// `core::clone::Clone::clone<alloc::rc::Rc<T>>`
+    RcClone,
+    /// `core::ops::deref::Deref::<alloc::rc::Rc<T>>::deref`
+    RcDeref,
+    /// `alloc::sync::Arc::new`
+    ArcNew,
+    /// `core::clone::Clone::clone<alloc::sync::Arc<T>>`
+    ArcClone,
+    /// `core::ops::deref::Deref::<alloc::sync::Arc<T>>::deref`
+    ArcDeref,
+    /// `core::ops::try_trait::Try::branch<core::result::Result<T, E>>`
+    ResultBranch,
+    /// `core::ops::try_trait::FromResidual::from_residual<core::result::Result<T, F>, core::result::Result<core::convert::Infallible, E>>`
+    ResultFromResidual,
+    /// `core::ops::try_trait::Try::branch<core::option::Option<T>>`
+    OptionBranch,
+    /// `core::ops::try_trait::FromResidual::from_residual<core::option::Option<T>, core::option::Option<core::convert::Infallible>>`
+    OptionFromResidual,
+    /// `core::iter::traits::collect::IntoIterator::into_iter<I>`, where `I` is
+    /// a range, a slice iterator or a borrowed slice. We remove those calls in
+    /// [crate::simplify_iterators].
+    IntoIter,
+    /// `core::iter::traits::iterator::Iterator::next<core::ops::range::Range<T>>`
+    RangeNext,
+    /// `core::ops::range::RangeInclusive::new`
+    RangeInclusiveNew,
+    /// `core::iter::traits::iterator::Iterator::next<core::ops::range::RangeInclusive<T>>`
+    RangeInclusiveNext,
+    /// `core::iter::traits::iterator::Iterator::next<core::slice::iter::Iter<T>>`
+    SliceIterNext,
+    /// `core::iter::traits::iterator::Iterator::next<core::slice::iter::IterMut<T>>`
+    SliceIterMutNext,
+    /// A function declared as primitive by the user (see
+    /// [crate::assumed::UserAssumedDecls])
+    User(UserAssumedFunId::Id),
+}
+
+/// A compiler intrinsic, that is a function declared in `core::intrinsics`
+/// with the `rust-intrinsic` ABI.
+///
+/// Note that we translate calls to `core::intrinsics::unreachable` to the
+/// `unreachable` terminator.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, EnumIsA, VariantName, Serialize)]
+pub enum IntrinsicFunId {
+    /// `core::intrinsics::assume`
+    Assume,
+    /// `core::intrinsics::copy_nonoverlapping`
+    CopyNonOverlapping,
+    /// `core::intrinsics::size_of`
+    SizeOf,
+    /// `core::intrinsics::min_align_of`
+    MinAlignOf,
+    /// `core::intrinsics::transmute`
+    Transmute,
+    /// `core::intrinsics::likely`
+    Likely,
+    /// `core::intrinsics::unlikely`
+    Unlikely,
+}
+
+/// The payload of a panic.
+#[derive(Debug, Clone, Serialize)]
+pub struct PanicInfo {
+    /// The panic message, if we could retrieve it: for instance, for
+    /// `panic!("invariant broken")`, or for the panics introduced by
+    /// `unreachable!()` or `assert!(...)`. For now, we don't retrieve the
+    /// messages built from format strings with arguments.
+    pub message: Option<String>,
+    /// The location of the panic in the user code. If the panic comes from
+    /// a macro like `panic!`, this is the location of the macro call.
+    pub location: Span,
+}
+
+/// The reason why an assertion is checked.
+#[derive(Debug, Clone, EnumIsA, VariantName, Serialize)]
+pub enum AssertKind {
+    /// Array or slice index out of bounds.
+    BoundsCheck,
+    /// Overflow of an arithmetic operation: we store the operation.
+    Overflow(BinOp),
+    /// Overflow when negating the minimal value of a signed integer type.
+    OverflowNeg,
+    /// Division by zero.
+    DivisionByZero,
+    /// Remainder by zero.
+    RemainderByZero,
+    /// Assertion introduced by the user (`assert!(...)`). Those assertions
+    /// are compiled to conditional panics: we reconstruct them in
+    /// [crate::reconstruct_asserts], and keep the information about the panic.
+    User(PanicInfo),
+}
+
+/// An inline assembly block (`asm!`). We don't interpret the assembly code:
+/// we only keep enough information to treat it as an opaque effect.
+#[derive(Debug, Clone, Serialize)]
+pub struct InlineAsm {
+    /// The assembly template, where the operands are referred to with
+    /// placeholders like `{0}`.
+    pub template: String,
+    pub operands: Vec<InlineAsmOperand>,
+    /// The options given to `asm!` (`pure`, `nomem`, `noreturn`, etc.).
+    pub options: Vec<String>,
+}
+
+/// An operand of an inline assembly block. The registers (or register
+/// classes) are printed as in the source code: `reg`, `"eax"`, etc.
+#[derive(Debug, Clone, EnumIsA, VariantName, Serialize)]
+pub enum InlineAsmOperand {
+    /// `in(reg) value`
+    In { reg: String, value: Operand },
+    /// `out(reg) place` or `lateout(reg) place`. The place is `None` if the
+    /// output is discarded (`out(reg) _`).
+    Out {
+        reg: String,
+        late: bool,
+        place: Option<Place>,
+    },
+    /// `inout(reg) value => place` or `inlateout(reg) value => place`
+    InOut {
+        reg: String,
+        late: bool,
+        in_value: Operand,
+        out_place: Option<Place>,
+    },
+    /// `const value`
+    Const(Operand),
+    /// `sym path`: we only keep the name of the function or static.
+    Sym(String),
 }
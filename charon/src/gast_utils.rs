@@ -1,6 +1,7 @@
 //! Implementations for [crate::gast]
 #![allow(dead_code)]
 
+use crate::assumed;
 use crate::expressions::*;
 use crate::formatter::Formatter;
 use crate::gast::*;
@@ -48,6 +49,10 @@ pub fn make_locals_generator(locals: &mut VarId::Vector<Var>) -> impl FnMut(ETy)
     }
 }
 
+pub fn trait_decl_id_to_pretty_string(id: TraitDeclId::Id) -> String {
+    format!("@Trait{id}")
+}
+
 impl std::string::ToString for Var {
     fn to_string(&self) -> String {
         let id = var_id_to_pretty_string(self.index);
@@ -72,11 +77,72 @@ impl Var {
     }
 }
 
+impl std::string::ToString for PanicInfo {
+    fn to_string(&self) -> String {
+        match &self.message {
+            Some(msg) => format!("panic({msg:?})"),
+            None => "panic".to_string(),
+        }
+    }
+}
+
+impl InlineAsm {
+    /// Perform a type substitution - actually simply clone the object
+    pub fn substitute(&self, _subst: &ETypeSubst) -> Self {
+        self.clone()
+    }
+
+    pub fn fmt_with_ctx<T>(&self, ctx: &T) -> String
+    where
+        T: Formatter<VarId::Id>
+            + Formatter<TypeDeclId::Id>
+            + Formatter<GlobalDeclId::Id>
+            + Formatter<(TypeDeclId::Id, Option<VariantId::Id>, FieldId::Id)>,
+    {
+        let fmt_place = |place: &Option<Place>| match place {
+            Some(place) => place.fmt_with_ctx(ctx),
+            None => "_".to_string(),
+        };
+        let mut args: Vec<String> = vec![format!("{:?}", self.template)];
+        for op in &self.operands {
+            args.push(match op {
+                InlineAsmOperand::In { reg, value } => {
+                    format!("in({reg}) {}", value.fmt_with_ctx(ctx))
+                }
+                InlineAsmOperand::Out { reg, late, place } => {
+                    let kind = if *late { "lateout" } else { "out" };
+                    format!("{kind}({reg}) {}", fmt_place(place))
+                }
+                InlineAsmOperand::InOut {
+                    reg,
+                    late,
+                    in_value,
+                    out_place,
+                } => {
+                    let kind = if *late { "inlateout" } else { "inout" };
+                    format!(
+                        "{kind}({reg}) {} => {}",
+                        in_value.fmt_with_ctx(ctx),
+                        fmt_place(out_place)
+                    )
+                }
+                InlineAsmOperand::Const(value) => format!("const {}", value.fmt_with_ctx(ctx)),
+                InlineAsmOperand::Sym(name) => format!("sym {name}"),
+            })
+        }
+        if !self.options.is_empty() {
+            args.push(format!("options({})", self.options.join(", ")));
+        }
+        format!("asm!({})", args.join(", "))
+    }
+}
+
 pub fn fmt_call<'a, 'b, T>(
     ctx: &'b T,
-    func: &'a FunId,
+    func: &'a FnOperand,
     region_args: &'a Vec<ErasedRegion>,
     type_args: &'a Vec<ETy>,
+    const_generic_args: &'a [ConstGeneric],
     args: &'a [Operand],
 ) -> String
 where
@@ -89,41 +155,121 @@ where
         + Formatter<(TypeDeclId::Id, VariantId::Id)>
         + Formatter<(TypeDeclId::Id, Option<VariantId::Id>, FieldId::Id)>,
 {
-    let rt_args = if region_args.len() + type_args.len() == 0 {
+    let rt_args = if region_args.len() + type_args.len() + const_generic_args.len() == 0 {
         "".to_string()
     } else {
         let regions_s: Vec<String> = region_args.iter().map(|x| x.to_string()).collect();
         let mut types_s: Vec<String> = type_args.iter().map(|x| x.fmt_with_ctx(ctx)).collect();
+        let mut cgs_s: Vec<String> = const_generic_args.iter().map(|x| x.to_string()).collect();
         let mut s = regions_s;
         s.append(&mut types_s);
+        s.append(&mut cgs_s);
         format!("<{}>", s.join(", "))
     };
     let args: Vec<String> = args.iter().map(|x| x.fmt_with_ctx(ctx)).collect();
     let args = args.join(", ");
 
     let f = match func {
-        FunId::Regular(def_id) => format!("{}{}", ctx.format_object(*def_id), rt_args),
-        FunId::Assumed(assumed) => match assumed {
-            AssumedFunId::Replace => format!("core::mem::replace{rt_args}"),
-            AssumedFunId::BoxNew => format!("alloc::boxed::Box{rt_args}::new"),
-            AssumedFunId::BoxDeref => {
-                format!("core::ops::deref::Deref<alloc::boxed::Box{rt_args}>::deref",)
-            }
-            AssumedFunId::BoxDerefMut => {
-                format!("core::ops::deref::DerefMut<alloc::boxed::Box{rt_args}>::deref_mut",)
-            }
-            AssumedFunId::BoxFree => format!("alloc::alloc::box_free{rt_args}"),
-            AssumedFunId::VecNew => format!("alloc::vec::Vec{rt_args}::new"),
-            AssumedFunId::VecPush => format!("alloc::vec::Vec{rt_args}::push"),
-            AssumedFunId::VecInsert => format!("alloc::vec::Vec{rt_args}::insert"),
-            AssumedFunId::VecLen => format!("alloc::vec::Vec{rt_args}::len"),
-            AssumedFunId::VecIndex => {
-                format!("core::ops::index::Index<alloc::vec::Vec{rt_args}>::index")
-            }
-            AssumedFunId::VecIndexMut => {
-                format!("core::ops::index::IndexMut<alloc::vec::Vec{rt_args}>::index_mut",)
-            }
+        FnOperand::Regular(func) => match func {
+            FunId::Regular(def_id) => format!("{}{}", ctx.format_object(*def_id), rt_args),
+            FunId::Assumed(assumed) => match assumed {
+                AssumedFunId::Replace => format!("core::mem::replace{rt_args}"),
+                AssumedFunId::BoxNew => format!("alloc::boxed::Box{rt_args}::new"),
+                AssumedFunId::BoxDeref => {
+                    format!("core::ops::deref::Deref<alloc::boxed::Box{rt_args}>::deref",)
+                }
+                AssumedFunId::BoxDerefMut => {
+                    format!("core::ops::deref::DerefMut<alloc::boxed::Box{rt_args}>::deref_mut",)
+                }
+                AssumedFunId::BoxFree => format!("alloc::alloc::box_free{rt_args}"),
+                AssumedFunId::VecNew => format!("alloc::vec::Vec{rt_args}::new"),
+                AssumedFunId::VecPush => format!("alloc::vec::Vec{rt_args}::push"),
+                AssumedFunId::VecInsert => format!("alloc::vec::Vec{rt_args}::insert"),
+                AssumedFunId::VecLen => format!("alloc::vec::Vec{rt_args}::len"),
+                AssumedFunId::VecIndex => {
+                    format!("core::ops::index::Index<alloc::vec::Vec{rt_args}>::index")
+                }
+                AssumedFunId::VecIndexMut => {
+                    format!("core::ops::index::IndexMut<alloc::vec::Vec{rt_args}>::index_mut",)
+                }
+                AssumedFunId::SliceLen => format!("core::slice::len{rt_args}"),
+                AssumedFunId::SliceIndexShared => format!("core::slice::index{rt_args}"),
+                AssumedFunId::SliceIndexMut => format!("core::slice::index_mut{rt_args}"),
+                AssumedFunId::SliceSubsliceShared => format!("core::slice::subslice{rt_args}"),
+                AssumedFunId::SliceSubsliceMut => format!("core::slice::subslice_mut{rt_args}"),
+                AssumedFunId::SliceGet => format!("core::slice::get{rt_args}"),
+                AssumedFunId::SliceGetMut => format!("core::slice::get_mut{rt_args}"),
+                AssumedFunId::SliceSwap => format!("core::slice::swap{rt_args}"),
+                AssumedFunId::SliceSplitAt => format!("core::slice::split_at{rt_args}"),
+                AssumedFunId::SliceSplitAtMut => format!("core::slice::split_at_mut{rt_args}"),
+                AssumedFunId::SliceIter => format!("core::slice::iter{rt_args}"),
+                AssumedFunId::SliceIterMut => format!("core::slice::iter_mut{rt_args}"),
+                AssumedFunId::SliceToVec => format!("alloc::slice::to_vec{rt_args}"),
+                AssumedFunId::ArrayIndexShared => format!("core::array::index{rt_args}"),
+                AssumedFunId::ArrayIndexMut => format!("core::array::index_mut{rt_args}"),
+                AssumedFunId::ArraySubsliceShared => format!("core::array::subslice{rt_args}"),
+                AssumedFunId::ArraySubsliceMut => format!("core::array::subslice_mut{rt_args}"),
+                AssumedFunId::ArrayToSliceShared => format!("core::array::to_slice{rt_args}"),
+                AssumedFunId::ArrayToSliceMut => format!("core::array::to_slice_mut{rt_args}"),
+                AssumedFunId::CellNew => format!("core::cell::Cell{rt_args}::new"),
+                AssumedFunId::CellGet => format!("core::cell::Cell{rt_args}::get"),
+                AssumedFunId::CellSet => format!("core::cell::Cell{rt_args}::set"),
+                AssumedFunId::RefCellNew => format!("core::cell::RefCell{rt_args}::new"),
+                AssumedFunId::RefCellBorrow => format!("core::cell::RefCell{rt_args}::borrow"),
+                AssumedFunId::RefCellBorrowMut => {
+                    format!("core::cell::RefCell{rt_args}::borrow_mut")
+                }
+                AssumedFunId::RcNew => format!("alloc::rc::Rc{rt_args}::new"),
+                AssumedFunId::RcClone => {
+                    format!("core::clone::Clone<alloc::rc::Rc{rt_args}>::clone")
+                }
+                AssumedFunId::RcDeref => {
+                    format!("core::ops::deref::Deref<alloc::rc::Rc{rt_args}>::deref")
+                }
+                AssumedFunId::ArcNew => format!("alloc::sync::Arc{rt_args}::new"),
+                AssumedFunId::ArcClone => {
+                    format!("core::clone::Clone<alloc::sync::Arc{rt_args}>::clone")
+                }
+                AssumedFunId::ArcDeref => {
+                    format!("core::ops::deref::Deref<alloc::sync::Arc{rt_args}>::deref")
+                }
+                AssumedFunId::ResultBranch | AssumedFunId::OptionBranch => {
+                    format!("core::ops::try_trait::Try::branch{rt_args}")
+                }
+                AssumedFunId::ResultFromResidual | AssumedFunId::OptionFromResidual => {
+                    format!("core::ops::try_trait::FromResidual::from_residual{rt_args}")
+                }
+                AssumedFunId::IntoIter => {
+                    format!("core::iter::traits::collect::IntoIterator::into_iter{rt_args}")
+                }
+                AssumedFunId::RangeNext
+                | AssumedFunId::RangeInclusiveNext
+                | AssumedFunId::SliceIterNext
+                | AssumedFunId::SliceIterMutNext => {
+                    format!("core::iter::traits::iterator::Iterator::next{rt_args}")
+                }
+                AssumedFunId::RangeInclusiveNew => {
+                    format!("core::ops::range::RangeInclusive{rt_args}::new")
+                }
+                AssumedFunId::User(id) => {
+                    format!("{}{rt_args}", assumed::get_user_assumed_fun(*id).path)
+                }
+            },
+            FunId::TraitMethod {
+                trait_id,
+                method,
+                impl_source: _,
+            } => format!(
+                "{}::{method}{rt_args}",
+                trait_decl_id_to_pretty_string(*trait_id)
+            ),
+            FunId::Intrinsic(id) => format!(
+                "core::intrinsics::{}{rt_args}",
+                assumed::get_name_from_intrinsic_id(*id)
+            ),
+            FunId::OpaqueIntrinsic(name) => format!("core::intrinsics::{name}{rt_args}"),
         },
+        FnOperand::Move(p) => format!("(move {})", p.fmt_with_ctx(ctx)),
     };
 
     format!("{f}({args})")
@@ -202,13 +348,23 @@ impl FunSig {
             + Formatter<&'a Region<RegionVarId::Id>>,
     {
         // Type parameters
-        let params = if self.region_params.len() + self.type_params.len() == 0 {
+        let params = if self.region_params.len()
+            + self.type_params.len()
+            + self.const_generic_params.len()
+            == 0
+        {
             "".to_string()
         } else {
             let regions: Vec<String> = self.region_params.iter().map(|x| x.to_string()).collect();
             let mut types: Vec<String> = self.type_params.iter().map(|x| x.to_string()).collect();
+            let mut cgs: Vec<String> = self
+                .const_generic_params
+                .iter()
+                .map(|x| x.to_string())
+                .collect();
             let mut params = regions;
             params.append(&mut types);
+            params.append(&mut cgs);
             format!("<{}>", params.join(", "))
         };
 
@@ -311,7 +467,11 @@ impl<T: Debug + Clone + Serialize> GFunDecl<T> {
         let name = self.name.to_string();
 
         // Type parameters
-        let params = if self.signature.region_params.len() + self.signature.type_params.len() == 0 {
+        let params = if self.signature.region_params.len()
+            + self.signature.type_params.len()
+            + self.signature.const_generic_params.len()
+            == 0
+        {
             "".to_string()
         } else {
             let regions: Vec<String> = self
@@ -326,8 +486,15 @@ impl<T: Debug + Clone + Serialize> GFunDecl<T> {
                 .iter()
                 .map(|x| x.to_string())
                 .collect();
+            let mut cgs: Vec<String> = self
+                .signature
+                .const_generic_params
+                .iter()
+                .map(|x| x.to_string())
+                .collect();
             let mut params = regions;
             params.append(&mut types);
+            params.append(&mut cgs);
             format!("<{}>", params.join(", "))
         };
 
@@ -356,11 +523,14 @@ impl<T: Debug + Clone + Serialize> GFunDecl<T> {
             format!(" -> {}", ret_ty.fmt_with_ctx(sig_ctx))
         };
 
+        // Unsafe qualifier
+        let unsafe_kw = if self.is_unsafe { "unsafe " } else { "" };
+
         // Case disjunction on the presence of a body (transparent/opaque definition)
         match &self.body {
             Option::None => {
                 // Put everything together
-                format!("{tab}fn {name}{params}({args}){ret_ty}")
+                format!("{tab}{unsafe_kw}fn {name}{params}({args}){ret_ty}")
             }
             Option::Some(body) => {
                 // Body
@@ -368,7 +538,7 @@ impl<T: Debug + Clone + Serialize> GFunDecl<T> {
                 let body = body.fmt_with_ctx(&body_tab, body_ctx);
 
                 // Put everything together
-                format!("{tab}fn {name}{params}({args}){ret_ty} {{\n{body}\n{tab}}}",)
+                format!("{tab}{unsafe_kw}fn {name}{params}({args}){ret_ty} {{\n{body}\n{tab}}}",)
             }
         }
     }
@@ -535,7 +705,7 @@ impl<'ctx, FD, GD> Formatter<(TypeDeclId::Id, Option<VariantId::Id>, FieldId::Id
                     Option::None => field_id.to_string(),
                 }
             }
-            (TypeDeclKind::Struct(fields), None) => {
+            (TypeDeclKind::Struct(fields) | TypeDeclKind::Union(fields), None) => {
                 let field = fields.get(field_id).unwrap();
                 match &field.name {
                     Option::Some(name) => name.clone(),
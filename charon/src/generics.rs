@@ -136,6 +136,16 @@ pub(crate) fn check_type_generics(tcx: TyCtxt<'_>, def_id: DefId) {
     check_generics(tcx, def_id)
 }
 
+/// Check a trait's generics
+pub(crate) fn check_trait_generics(tcx: TyCtxt<'_>, def_id: DefId) {
+    check_generics(tcx, def_id)
+}
+
+/// Check the generics of a trait implementation
+pub(crate) fn check_trait_impl_generics(tcx: TyCtxt<'_>, def_id: DefId) {
+    check_generics(tcx, def_id)
+}
+
 /// Check a global's generics (to refuse them except Sized trait)
 pub(crate) fn check_global_generics(tcx: TyCtxt<'_>, def_id: DefId) {
     assert!(tcx.generics_of(def_id).params.is_empty());
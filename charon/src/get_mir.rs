@@ -64,3 +64,39 @@ pub fn get_mir_for_def_id_and_level(
         }
     }
 }
+
+/// Indicates if the MIR of an external function is available in the crate
+/// metadata. This is the case of the generic and `#[inline]` functions (for
+/// which rustc stores the optimized MIR) and of the `const` functions (for
+/// which rustc stores the MIR used for compile-time evaluation).
+pub fn external_mir_is_available(tcx: TyCtxt<'_>, def_id: DefId) -> bool {
+    assert!(!def_id.is_local());
+    tcx.is_mir_available(def_id) || tcx.is_const_fn_raw(def_id)
+}
+
+/// The level of the MIR we retrieve for a definition: we can only retrieve
+/// the optimized MIR of the external definitions (see [get_mir_for_def_id]).
+pub fn get_mir_level_for_def_id(def_id: DefId, level: MirLevel) -> MirLevel {
+    if def_id.is_local() {
+        level
+    } else {
+        MirLevel::Optimized
+    }
+}
+
+/// Query the MIR for a function which may be external.
+///
+/// For the external functions, we ignore the level and retrieve the MIR
+/// stored in the crate metadata (see [external_mir_is_available]).
+pub fn get_mir_for_def_id(tcx: TyCtxt<'_>, def_id: DefId, level: MirLevel) -> &Body<'_> {
+    match def_id.as_local() {
+        Some(local_id) => get_mir_for_def_id_and_level(tcx, local_id, level),
+        None => {
+            if tcx.is_mir_available(def_id) {
+                tcx.optimized_mir(def_id)
+            } else {
+                tcx.mir_for_ctfe(def_id)
+            }
+        }
+    }
+}
@@ -15,6 +15,7 @@
 #![feature(rustc_private, register_tool)]
 #![feature(box_syntax, box_patterns)]
 #![feature(cell_leak)] // For Ref::leak
+#![feature(once_cell)] // For OnceLock
 // For rustdoc: prevents overflows
 #![recursion_limit = "256"]
 
@@ -67,6 +68,7 @@ pub mod meta_utils;
 pub mod names;
 pub mod names_utils;
 pub mod reconstruct_asserts;
+pub mod reconstruct_try;
 pub mod regions_hierarchy;
 pub mod register;
 pub mod regularize_constant_adts;
@@ -75,8 +77,10 @@ pub mod remove_read_discriminant;
 pub mod remove_unused_locals;
 pub mod reorder_decls;
 pub mod rust_to_local_ids;
+pub mod simplify_iterators;
 pub mod simplify_ops;
 pub mod translate_functions_to_ullbc;
+pub mod translate_traits;
 pub mod translate_types;
 pub mod types;
 pub mod types_utils;
@@ -12,7 +12,10 @@ pub use crate::llbc_ast_utils::*;
 use crate::meta::Meta;
 use crate::types::*;
 use crate::ullbc_ast::*;
-pub use crate::ullbc_ast::{CtxNames, FunDeclId, GlobalDeclId, Var};
+pub use crate::ullbc_ast::{
+    AssertKind, CtxNames, FunDeclId, GlobalDeclId, InlineAsm, InlineAsmOperand, IntrinsicFunId,
+    PanicInfo, Var,
+};
 use crate::values::*;
 use macros::{EnumAsGetters, EnumIsA, EnumToGetters, VariantIndexArity, VariantName};
 use serde::Serialize;
@@ -21,18 +24,20 @@ use serde::Serialize;
 pub struct Assert {
     pub cond: Operand,
     pub expected: bool,
+    pub kind: AssertKind,
 }
 
 /// TODO: factor out with [Rvalue]
 #[derive(Debug, Clone, Serialize)]
 pub struct Call {
-    pub func: FunId,
+    pub func: FnOperand,
     /// Technically this is useless, but we still keep it because we might
     /// want to introduce some information (and the way we encode from MIR
     /// is as simple as possible - and in MIR we also have a vector of erased
     /// regions).
     pub region_args: Vec<ErasedRegion>,
     pub type_args: Vec<ETy>,
+    pub const_generic_args: Vec<ConstGeneric>,
     pub args: Vec<Operand>,
     pub dest: Place,
 }
@@ -47,8 +52,12 @@ R: Clone + std::cmp::Eq, {
     Drop(Place),
     Assert(Assert),
     Call(Call),
+    /// See [crate::ullbc_ast::RawStatement::Intrinsic]
+    Intrinsic(IntrinsicFunId, Vec<Operand>),
+    /// Inline assembly, which we treat as an opaque effect
+    InlineAsm(InlineAsm),
     /// Panic also handles "unreachable"
-    Panic,
+    Panic(PanicInfo),
     Return,
     /// Break to outer loops.
     /// The `usize` gives the index of the outer loop to break to:
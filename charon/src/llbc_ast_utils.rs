@@ -3,6 +3,7 @@
 #![allow(dead_code)]
 use std::ops::DerefMut;
 
+use crate::assumed;
 use crate::common::*;
 use crate::formatter::Formatter;
 use crate::llbc_ast::{
@@ -118,11 +119,13 @@ pub fn transform_statements<F: FnMut(Statement) -> Statement>(
         }
         RawStatement::Assign(p, r) => RawStatement::Assign(p, r),
         RawStatement::Call(c) => RawStatement::Call(c),
+        RawStatement::Intrinsic(id, args) => RawStatement::Intrinsic(id, args),
+        RawStatement::InlineAsm(asm) => RawStatement::InlineAsm(asm),
         RawStatement::Assert(a) => RawStatement::Assert(a),
         RawStatement::FakeRead(p) => RawStatement::FakeRead(p),
         RawStatement::SetDiscriminant(p, vid) => RawStatement::SetDiscriminant(p, vid),
         RawStatement::Drop(p) => RawStatement::Drop(p),
-        RawStatement::Panic => RawStatement::Panic,
+        RawStatement::Panic(info) => RawStatement::Panic(info),
         RawStatement::Return => RawStatement::Return,
         RawStatement::Break(i) => RawStatement::Break(i),
         RawStatement::Continue(i) => RawStatement::Continue(i),
@@ -260,13 +263,24 @@ impl Statement {
                     func,
                     region_args,
                     type_args,
+                    const_generic_args,
                     args,
                     dest,
                 } = call;
-                let call = fmt_call(ctx, func, region_args, type_args, args);
+                let call = fmt_call(ctx, func, region_args, type_args, const_generic_args, args);
                 format!("{}{} := {}", tab, dest.fmt_with_ctx(ctx), call)
             }
-            RawStatement::Panic => format!("{tab}panic"),
+            RawStatement::Intrinsic(id, args) => {
+                let args: Vec<String> = args.iter().map(|x| x.fmt_with_ctx(ctx)).collect();
+                format!(
+                    "{}core::intrinsics::{}({})",
+                    tab,
+                    assumed::get_name_from_intrinsic_id(*id),
+                    args.join(", ")
+                )
+            }
+            RawStatement::InlineAsm(asm) => format!("{tab}{}", asm.fmt_with_ctx(ctx)),
+            RawStatement::Panic(info) => format!("{tab}{}", info.to_string()),
             RawStatement::Return => format!("{tab}return"),
             RawStatement::Break(index) => format!("{tab}break {index}"),
             RawStatement::Continue(index) => format!("{tab}continue {index}"),
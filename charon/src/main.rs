@@ -65,6 +65,11 @@ pub fn main() {
         "Can't use --mir_promoted and --mir_optimized at the same time"
     );
 
+    assert!(
+        !options.unwind || options.ullbc,
+        "Can't use --unwind without --ullbc: the control-flow reconstruction doesn't support unwinding"
+    );
+
     if let Err(code) = process(&options) {
         std::process::exit(code);
     }
@@ -60,4 +60,6 @@ pub type TypeName = Name;
 pub type ItemName = Name;
 pub type FunName = Name;
 pub type GlobalName = Name;
+pub type TraitName = Name;
+pub type TraitImplName = Name;
 pub type HirItemName = Name;
@@ -224,6 +224,10 @@ pub fn item_def_id_to_name(tcx: TyCtxt, def_id: DefId) -> ItemName {
                     rustc_middle::ty::TyKind::Int(_) | rustc_middle::ty::TyKind::Uint(_) => {
                         format!("{ty:?}")
                     }
+                    // The `impl<T> [T]` and `impl<T, const N: usize> [T; N]`
+                    // blocks (see [crate::assumed::SLICE_LEN_NAME] for instance)
+                    rustc_middle::ty::TyKind::Slice(_) => "Slice".to_string(),
+                    rustc_middle::ty::TyKind::Array(..) => "Array".to_string(),
                     _ => { format!("Patch");
                       format!("PathElem")}
                 }));
@@ -232,6 +236,14 @@ pub fn item_def_id_to_name(tcx: TyCtxt, def_id: DefId) -> ItemName {
                 // TODO: this should work the same as for `Impl`
                 unimplemented!();
             }
+            DefPathData::ClosureExpr => {
+                // The closures are identified by a disambiguator (there may
+                // be several closures in the same function)
+                name.push(PathElem::Disambiguator(Disambiguator::Id::new(
+                    data.disambiguator as usize,
+                )));
+                name.push(PathElem::Ident("closure".to_string()));
+            }
             DefPathData::MacroNs(symbol) => {
                 assert!(data.disambiguator == 0); // Sanity check
 
@@ -287,7 +299,11 @@ pub fn global_def_id_to_name(tcx: TyCtxt, def_id: DefId) -> GlobalName {
     item_def_id_to_name(tcx, def_id)
 }
 
-pub fn trait_def_id_to_name(tcx: TyCtxt, def_id: DefId) -> FunName {
+pub fn trait_def_id_to_name(tcx: TyCtxt, def_id: DefId) -> TraitName {
+    item_def_id_to_name(tcx, def_id)
+}
+
+pub fn trait_impl_def_id_to_name(tcx: TyCtxt, def_id: DefId) -> TraitImplName {
     item_def_id_to_name(tcx, def_id)
 }
 
@@ -305,7 +321,6 @@ pub fn hir_item_to_name(tcx: TyCtxt, item: &Item) -> Option<HirItemName> {
     // satisfying below
     match &item.kind {
         ItemKind::OpaqueTy(_) => unimplemented!(),
-        ItemKind::Union(_, _) => unimplemented!(),
         ItemKind::ExternCrate(_) => {
             // We ignore this -
             // TODO: investigate when extern crates appear, and why
@@ -318,8 +333,10 @@ pub fn hir_item_to_name(tcx: TyCtxt, item: &Item) -> Option<HirItemName> {
         }
         ItemKind::Enum(_, _)
         | ItemKind::Struct(_, _)
+        | ItemKind::Union(_, _)
         | ItemKind::Fn(_, _, _)
         | ItemKind::Impl(_)
+        | ItemKind::Trait(_, _, _, _, _)
         | ItemKind::Mod(_)
         | ItemKind::Const(_, _)
         | ItemKind::Static(_, _, _)
@@ -6,7 +6,9 @@
 use take_mut::take;
 
 use crate::{
-    llbc_ast::{Assert, CtxNames, FunDecls, GlobalDecls, RawStatement, Statement, Switch},
+    llbc_ast::{
+        Assert, AssertKind, CtxNames, FunDecls, GlobalDecls, RawStatement, Statement, Switch,
+    },
     ullbc_ast::{iter_function_bodies, iter_global_bodies},
 };
 use std::iter::FromIterator;
@@ -19,7 +21,9 @@ fn transform_st(mut st: Statement) -> Statement {
         RawStatement::Drop(p) => RawStatement::Drop(p),
         RawStatement::Assert(assert) => RawStatement::Assert(assert),
         RawStatement::Call(call) => RawStatement::Call(call),
-        RawStatement::Panic => RawStatement::Panic,
+        RawStatement::Intrinsic(id, args) => RawStatement::Intrinsic(id, args),
+        RawStatement::InlineAsm(asm) => RawStatement::InlineAsm(asm),
+        RawStatement::Panic(info) => RawStatement::Panic(info),
         RawStatement::Return => RawStatement::Return,
         RawStatement::Break(i) => RawStatement::Break(i),
         RawStatement::Continue(i) => RawStatement::Continue(i),
@@ -31,12 +35,13 @@ fn transform_st(mut st: Statement) -> Statement {
 
                     // Check if the first statement is a panic: if yes, replace
                     // the if .. then ... else ... by an assertion.
-                    if st1.content.is_panic() {
+                    if let RawStatement::Panic(info) = st1.content {
                         let st1 = Statement::new(
                             st1.meta,
                             RawStatement::Assert(Assert {
                                 cond: op,
                                 expected: false,
+                                kind: AssertKind::User(info),
                             }),
                         );
                         let st1 = Box::new(st1);
@@ -0,0 +1,336 @@
+//! The `?` operator is desugared to a call to `Try::branch`, followed by a
+//! match on the returned `ControlFlow` value: in the `Break` case, we convert
+//! the residual with `FromResidual::from_residual` and return. For instance,
+//! `let y = x?;` (where `x: Result<T, E>`) gives:
+//! ```text
+//! tmp := core::ops::try_trait::Try::branch(move x);
+//! d := discriminant(tmp);
+//! switch move d {
+//!   0 => { y := copy ((tmp as Continue).0); ... }
+//!   1 => {
+//!     residual := copy ((tmp as Break).0); // residual: Result<Infallible, E>
+//!     ret := core::ops::try_trait::FromResidual::from_residual(move residual);
+//!     return
+//!   }
+//! }
+//! ```
+//! When the `?` operator is applied to a `Result` or an `Option`, this pass
+//! removes the `ControlFlow` value and the calls to the trait methods: we
+//! directly match on `x`, and return an `Err` (or `None`) value:
+//! ```text
+//! tmp := move x;
+//! d := discriminant(tmp);
+//! switch move d {
+//!   0 => { y := copy ((tmp as Ok).0); ... }
+//!   1 => {
+//!     residual := Err { 0: copy ((tmp as Err).0) };
+//!     ret := Err { 0: move ((residual as Err).0) };
+//!     return
+//!   }
+//! }
+//! ```
+//! We don't remove the call to `from_residual` if it converts the error (i.e.,
+//! if it calls `From::from`).
+//!
+//! Note that this pass must be applied before [crate::remove_read_discriminant]:
+//! the variants of `Option` are not in the same order as the variants of
+//! `ControlFlow`, so we need to update the switches on the discriminants.
+
+use std::collections::{HashMap, HashSet};
+
+use crate::assumed;
+use crate::expressions::*;
+use crate::llbc_ast::{
+    Call, CtxNames, FunDecls, GlobalDecls, InlineAsmOperand, RawStatement, Statement, Switch,
+};
+use crate::types::*;
+use crate::ullbc_ast::{
+    iter_function_bodies, iter_global_bodies, AssumedFunId, FnOperand, FunId, Var,
+};
+use crate::values::*;
+
+/// The variants of `core::ops::control_flow::ControlFlow`
+static CONTROL_FLOW_CONTINUE_VARIANT_ID: VariantId::Id = VariantId::ZERO;
+static CONTROL_FLOW_BREAK_VARIANT_ID: VariantId::Id = VariantId::ONE;
+
+/// Build the residual value of type `ty` (`Err(e)` or `None`). In the case of
+/// a `Result`, the error `e` is read from `src`, which must be an `Err` value.
+fn mk_residual(ty: &ETy, src: Operand) -> Rvalue {
+    match ty {
+        Ty::Adt(TypeId::Adt(id), regions, tys, cgs) => {
+            let variant_id = assumed::RESULT_ERR_VARIANT_ID;
+            let proj_kind = FieldProjKind::Adt(*id, Some(variant_id));
+            let proj = ProjectionElem::Field(proj_kind, FieldId::ZERO);
+            let err = match src {
+                Operand::Copy(mut p) => {
+                    p.projection.push_back(proj);
+                    Operand::Copy(p)
+                }
+                Operand::Move(mut p) => {
+                    p.projection.push_back(proj);
+                    Operand::Move(p)
+                }
+                Operand::Const(_, _) => unreachable!(),
+            };
+            let regions = regions.iter().cloned().collect();
+            let tys = tys.iter().cloned().collect();
+            let cgs = cgs.iter().cloned().collect();
+            let kind = AggregateKind::Adt(*id, Some(variant_id), regions, tys, cgs);
+            Rvalue::Aggregate(kind, vec![err])
+        }
+        Ty::Adt(TypeId::Assumed(AssumedTy::Option), _, tys, _) => {
+            let kind = AggregateKind::Option(assumed::OPTION_NONE_VARIANT_ID, tys[0].clone());
+            Rvalue::Aggregate(kind, vec![])
+        }
+        _ => unreachable!("Unexpected residual type: {:?}", ty),
+    }
+}
+
+/// Return the variant of a projection on the field of a `ControlFlow` value
+fn get_control_flow_variant(pe: &ProjectionElem) -> VariantId::Id {
+    match pe {
+        ProjectionElem::Field(FieldProjKind::Adt(_, Some(variant_id)), field_id) => {
+            assert!(*field_id == FieldId::ZERO);
+            *variant_id
+        }
+        _ => unreachable!(),
+    }
+}
+
+struct Ctx {
+    /// The variables which store the result of a call to `Try::branch`, with
+    /// the type of the residual (`Result<Infallible, E>` or `Option<Infallible>`).
+    /// Those variables now store the value to which we apply the `?` operator.
+    branch_vars: HashMap<VarId::Id, ETy>,
+    /// The variables which store the discriminant of a variable of
+    /// [Ctx::branch_vars] of type `Option`
+    option_discr_vars: HashSet<VarId::Id>,
+}
+
+impl Ctx {
+    /// If the place is rooted in a variable of [Ctx::branch_vars], replace
+    /// the projection on the `Continue` variant with a projection on the `Ok`
+    /// (or `Some`) variant.
+    fn transform_place(&self, p: &mut Place) {
+        let residual_ty = match self.branch_vars.get(&p.var_id) {
+            Some(ty) => ty,
+            None => return,
+        };
+        if let Some(pe) = p.projection.get_mut(0) {
+            // The projections on the `Break` variant are handled in
+            // [Ctx::transform_rvalue]
+            assert!(get_control_flow_variant(pe) == CONTROL_FLOW_CONTINUE_VARIANT_ID);
+            let proj_kind = match residual_ty {
+                Ty::Adt(TypeId::Adt(id), _, _, _) => {
+                    FieldProjKind::Adt(*id, Some(assumed::RESULT_OK_VARIANT_ID))
+                }
+                _ => FieldProjKind::Option(assumed::OPTION_SOME_VARIANT_ID),
+            };
+            *pe = ProjectionElem::Field(proj_kind, FieldId::ZERO);
+        }
+    }
+
+    fn transform_operand(&self, op: &mut Operand) {
+        match op {
+            Operand::Copy(p) | Operand::Move(p) => self.transform_place(p),
+            Operand::Const(_, _) => (),
+        }
+    }
+
+    fn transform_operands(&self, ops: &mut Vec<Operand>) {
+        for op in ops {
+            self.transform_operand(op)
+        }
+    }
+
+    fn transform_rvalue(&self, rv: &mut Rvalue) {
+        // Check if we read the residual stored in a `Break` value: if yes,
+        // we rebuild it
+        if let Rvalue::Use(Operand::Copy(p) | Operand::Move(p)) = rv {
+            if let Some(residual_ty) = self.branch_vars.get(&p.var_id) {
+                if p.projection.len() == 1
+                    && get_control_flow_variant(&p.projection[0]) == CONTROL_FLOW_BREAK_VARIANT_ID
+                {
+                    let src = Place {
+                        var_id: p.var_id,
+                        projection: Projection::new(),
+                    };
+                    *rv = mk_residual(residual_ty, Operand::Copy(src));
+                    return;
+                }
+            }
+        }
+
+        match rv {
+            Rvalue::Use(op) | Rvalue::UnaryOp(_, op) | Rvalue::Repeat(op, _, _) => {
+                self.transform_operand(op)
+            }
+            Rvalue::BinaryOp(_, op1, op2) => {
+                self.transform_operand(op1);
+                self.transform_operand(op2);
+            }
+            Rvalue::Ref(p, _) | Rvalue::Discriminant(p) | Rvalue::Len(p) | Rvalue::RawPtr(p, _) => {
+                self.transform_place(p)
+            }
+            Rvalue::Aggregate(_, ops) => self.transform_operands(ops),
+            Rvalue::Global(_) => (),
+        }
+    }
+
+    /// Rewrite the calls to `Try::branch` and `FromResidual::from_residual`
+    /// (see the comments at the top of the file). Return the new statement,
+    /// if we rewrote the call.
+    fn transform_call(
+        &mut self,
+        locals: &mut VarId::Vector<Var>,
+        call: &Call,
+    ) -> Option<RawStatement> {
+        let aid = match &call.func {
+            FnOperand::Regular(FunId::Assumed(aid)) => *aid,
+            _ => return None,
+        };
+        match aid {
+            AssumedFunId::ResultBranch | AssumedFunId::OptionBranch => {
+                // The destination is a variable of type `ControlFlow<R, T>`,
+                // where `R` is the type of the residual: we change its type
+                // to the type of the input value.
+                assert!(call.dest.projection.is_empty());
+                let var = locals.get_mut(call.dest.var_id).unwrap();
+                let residual_ty = var.ty.as_adt().2[0].clone();
+                var.ty = call.type_args[0].clone();
+                self.branch_vars.insert(call.dest.var_id, residual_ty);
+
+                let rv = Rvalue::Use(call.args[0].clone());
+                Some(RawStatement::Assign(call.dest.clone(), rv))
+            }
+            AssumedFunId::ResultFromResidual | AssumedFunId::OptionFromResidual => {
+                let self_ty = &call.type_args[0];
+                let residual_ty = &call.type_args[1];
+                // We can't remove the call if it converts the error
+                if aid.is_result_from_residual()
+                    && self_ty.as_adt().2[1] != residual_ty.as_adt().2[1]
+                {
+                    return None;
+                }
+
+                let rv = mk_residual(self_ty, call.args[0].clone());
+                Some(RawStatement::Assign(call.dest.clone(), rv))
+            }
+            _ => None,
+        }
+    }
+
+    fn transform_st(&mut self, locals: &mut VarId::Vector<Var>, st: &mut Statement) {
+        match &mut st.content {
+            RawStatement::Assign(p, rv) => {
+                self.transform_place(p);
+                self.transform_rvalue(rv);
+                // Remember the reads of the discriminants of options
+                if let Rvalue::Discriminant(dp) = rv {
+                    if let Some(Ty::Adt(TypeId::Assumed(AssumedTy::Option), _, _, _)) =
+                        self.branch_vars.get(&dp.var_id)
+                    {
+                        assert!(p.projection.is_empty());
+                        self.option_discr_vars.insert(p.var_id);
+                    }
+                }
+            }
+            RawStatement::FakeRead(p)
+            | RawStatement::SetDiscriminant(p, _)
+            | RawStatement::Drop(p) => self.transform_place(p),
+            RawStatement::Assert(assert) => self.transform_operand(&mut assert.cond),
+            RawStatement::Call(call) => {
+                self.transform_operands(&mut call.args);
+                self.transform_place(&mut call.dest);
+                if let Some(content) = self.transform_call(locals, call) {
+                    st.content = content;
+                }
+            }
+            RawStatement::Intrinsic(_, args) => self.transform_operands(args),
+            RawStatement::InlineAsm(asm) => {
+                for op in &mut asm.operands {
+                    match op {
+                        InlineAsmOperand::In { reg: _, value } => self.transform_operand(value),
+                        InlineAsmOperand::Out {
+                            reg: _,
+                            late: _,
+                            place,
+                        } => {
+                            if let Some(p) = place {
+                                self.transform_place(p)
+                            }
+                        }
+                        InlineAsmOperand::InOut {
+                            reg: _,
+                            late: _,
+                            in_value,
+                            out_place,
+                        } => {
+                            self.transform_operand(in_value);
+                            if let Some(p) = out_place {
+                                self.transform_place(p)
+                            }
+                        }
+                        InlineAsmOperand::Const(op) => self.transform_operand(op),
+                        InlineAsmOperand::Sym(_) => (),
+                    }
+                }
+            }
+            RawStatement::Panic(_)
+            | RawStatement::Return
+            | RawStatement::Break(_)
+            | RawStatement::Continue(_)
+            | RawStatement::Nop => (),
+            RawStatement::Switch(switch) => match switch {
+                Switch::If(op, st1, st2) => {
+                    self.transform_operand(op);
+                    self.transform_st(locals, st1);
+                    self.transform_st(locals, st2);
+                }
+                Switch::SwitchInt(op, _, targets, otherwise) => {
+                    // If we switch over the discriminant of an option, we
+                    // need to swap the branches: `Continue` (0) becomes
+                    // `Some` (1) and `Break` (1) becomes `None` (0)
+                    let swap = match op {
+                        Operand::Move(p) => self.option_discr_vars.contains(&p.var_id),
+                        _ => false,
+                    };
+                    self.transform_operand(op);
+                    for (values, tgt) in targets {
+                        if swap {
+                            for v in values.iter_mut() {
+                                *v = ScalarValue::Isize(1 - *v.as_isize());
+                            }
+                        }
+                        self.transform_st(locals, tgt);
+                    }
+                    self.transform_st(locals, otherwise);
+                }
+                Switch::Match(_, _, _) => {
+                    // This variant is introduced in a subsequent pass
+                    unreachable!();
+                }
+            },
+            RawStatement::Loop(loop_body) => self.transform_st(locals, loop_body),
+            RawStatement::Sequence(st1, st2) => {
+                self.transform_st(locals, st1);
+                self.transform_st(locals, st2);
+            }
+        }
+    }
+}
+
+/// `fmt_ctx` is used for pretty-printing purposes.
+pub fn transform(fmt_ctx: &CtxNames<'_>, funs: &mut FunDecls, globals: &mut GlobalDecls) {
+    for (name, b) in iter_function_bodies(funs).chain(iter_global_bodies(globals)) {
+        trace!(
+            "# About to reconstruct the `?` operator in decl: {name}\n{}",
+            b.fmt_with_ctx_names(fmt_ctx)
+        );
+        let mut ctx = Ctx {
+            branch_vars: HashMap::new(),
+            option_discr_vars: HashSet::new(),
+        };
+        ctx.transform_st(&mut b.locals, &mut b.body);
+    }
+}
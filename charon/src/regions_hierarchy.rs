@@ -218,6 +218,10 @@ fn add_region_constraints(
 
     match (&region, type_def_constraints) {
         (_, None) | (Region::Static, _) => (),
+        (Region::Bound(_, _), _) => {
+            // We don't dive into the function pointer types
+            unreachable!()
+        }
         (Region::Var(rid), Some(type_def_constraints)) => {
             let current_parents = type_def_constraints
                 .region_vars_constraints
@@ -249,7 +253,7 @@ fn compute_full_regions_constraints_for_ty(
     ty: &RTy,
 ) {
     match ty {
-        Ty::Adt(type_id, regions, types) => {
+        Ty::Adt(type_id, regions, types, _) => {
             // Introduce constraints for all the regions given as parameters
             for r in regions {
                 add_region_constraints(
@@ -284,6 +288,7 @@ fn compute_full_regions_constraints_for_ty(
                             im::HashSet::from_iter(additional_parents.iter().map(|r| match r {
                                 Region::Static => Region::Static,
                                 Region::Var(rid) => *region_inst.get(*rid).unwrap(),
+                                Region::Bound(_, _) => unreachable!(),
                             }));
 
                         // Add the constraints
@@ -311,6 +316,7 @@ fn compute_full_regions_constraints_for_ty(
                             let region = match r {
                                 Region::Static => Region::Static,
                                 Region::Var(rid) => *region_inst.get(*rid).unwrap(),
+                                Region::Bound(_, _) => unreachable!(),
                             };
                             parent_regions.insert(region);
                         }
@@ -332,9 +338,17 @@ fn compute_full_regions_constraints_for_ty(
                     | AssumedTy::Vec
                     | AssumedTy::Option
                     | AssumedTy::PtrUnique
-                    | AssumedTy::PtrNonNull,
+                    | AssumedTy::PtrNonNull
+                    | AssumedTy::Cell
+                    | AssumedTy::RefCell
+                    | AssumedTy::Rc
+                    | AssumedTy::Arc
+                    | AssumedTy::User(_),
                 ) => {
-                    // Explore the types given as parameters
+                    // Explore the types given as parameters. Note that the
+                    // constraints we compute don't depend on the variances
+                    // (an invariant type like `Cell` is treated like `Box`):
+                    // see the comments for [AssumedTy].
                     for fty in types {
                         compute_full_regions_constraints_for_ty(
                             updated,
@@ -348,14 +362,25 @@ fn compute_full_regions_constraints_for_ty(
                 }
             }
         }
-        Ty::Bool | Ty::Char | Ty::Never | Ty::Integer(_) | Ty::Str => {
-            // Nothing to do
+        Ty::FnPtr(_, _) => {
+            // We don't dive into the function pointers: the regions bound
+            // by the function pointer types are local to those types, and
+            // calling a function pointer doesn't introduce constraints
+            // between the regions of the current definition.
         }
-        Ty::Array(_aty) => {
-            unimplemented!();
+        Ty::Bool | Ty::Char | Ty::Never | Ty::Integer(_) | Ty::Float(_) | Ty::Str => {
+            // Nothing to do
         }
-        Ty::Slice(_sty) => {
-            unimplemented!();
+        Ty::Array(elem_ty, _) | Ty::Slice(elem_ty) => {
+            // Dive in
+            compute_full_regions_constraints_for_ty(
+                updated,
+                constraints_map,
+                acc_constraints,
+                type_def_constraints,
+                parent_regions,
+                elem_ty,
+            );
         }
         Ty::Ref(region, ref_ty, _mutability) => {
             // Add the constraint for the region in the reference
@@ -390,6 +415,72 @@ fn compute_full_regions_constraints_for_ty(
                 ptr_ty,
             );
         }
+        Ty::Projection(trait_ref, _) => {
+            // We don't know which type the projection reduces to: we
+            // conservatively treat it like a tuple of its arguments
+            for fty in trait_ref.type_args.iter() {
+                compute_full_regions_constraints_for_ty(
+                    updated,
+                    constraints_map,
+                    acc_constraints,
+                    type_def_constraints,
+                    parent_regions.clone(),
+                    fty,
+                );
+            }
+        }
+        Ty::DynTrait(preds, region) => {
+            // The region bound of the trait object outlives the object: we
+            // treat it like the region of a reference
+            add_region_constraints(
+                updated,
+                acc_constraints,
+                type_def_constraints,
+                *region,
+                &parent_regions,
+            );
+
+            // We don't know the type of the object: we conservatively treat
+            // the arguments of the predicates like the fields of a tuple
+            // contained in the object
+            let mut parent_regions = parent_regions.clone();
+            parent_regions.insert(*region);
+            for pred in preds {
+                match pred {
+                    DynPredicate::Trait(_, regions, tys) => {
+                        for r in regions {
+                            add_region_constraints(
+                                updated,
+                                acc_constraints,
+                                type_def_constraints,
+                                *r,
+                                &parent_regions,
+                            );
+                        }
+                        for fty in tys {
+                            compute_full_regions_constraints_for_ty(
+                                updated,
+                                constraints_map,
+                                acc_constraints,
+                                type_def_constraints,
+                                parent_regions.clone(),
+                                fty,
+                            );
+                        }
+                    }
+                    DynPredicate::Projection(_, _, fty) => {
+                        compute_full_regions_constraints_for_ty(
+                            updated,
+                            constraints_map,
+                            acc_constraints,
+                            type_def_constraints,
+                            parent_regions.clone(),
+                            fty,
+                        );
+                    }
+                }
+            }
+        }
         Ty::TypeVar(var_id) => {
             // Add the parent regions in the set of parent regions for the type variable
             match type_def_constraints {
@@ -473,6 +564,12 @@ fn compute_regions_constraints_for_type_decl_group(
             (*id, {
                 let mut graph = LifetimeConstraints::new();
                 graph.add_node(Region::Static);
+                // Add all the region parameters: opaque types don't have
+                // fields, and thus wouldn't register them otherwise
+                let type_def = types.get_type_def(*id).unwrap();
+                for rvar in type_def.region_params.iter() {
+                    graph.add_node(Region::Var(rvar.index));
+                }
                 graph
             })
         }));
@@ -502,8 +599,14 @@ fn compute_regions_constraints_for_type_decl_group(
                     .iter()
                     .map(|tvar| Ty::TypeVar(tvar.index)),
             );
+            let cg_params = im::Vector::from_iter(
+                type_def
+                    .const_generic_params
+                    .iter()
+                    .map(|cgvar| ConstGeneric::Var(cgvar.index)),
+            );
             let variants_fields_tys =
-                type_def.get_instantiated_variants(&region_params, &type_params);
+                type_def.get_instantiated_variants(&region_params, &type_params, &cg_params);
 
             match variants_fields_tys {
                 Option::None => {
@@ -12,23 +12,29 @@
 use crate::assumed;
 use crate::common::*;
 use crate::generics;
-use crate::get_mir::{extract_constants_at_top_level, get_mir_for_def_id_and_level, MirLevel};
+use crate::get_mir::{
+    external_mir_is_available, extract_constants_at_top_level, get_mir_for_def_id,
+    get_mir_level_for_def_id, MirLevel,
+};
 use crate::meta;
 use crate::meta::{FileInfo, FileName};
 use crate::names::Name;
 use crate::names::{
     function_def_id_to_name, global_def_id_to_name, hir_item_to_name, module_def_id_to_name,
-    type_def_id_to_name,
+    trait_def_id_to_name, trait_impl_def_id_to_name, type_def_id_to_name,
 };
 use crate::translate_functions_to_ullbc;
+use crate::translate_types::{get_closure_parent_substs, try_normalize_projection};
 use hashlink::LinkedHashMap;
 use im::Vector;
 use linked_hash_set::LinkedHashSet;
+use rustc_hir::def::DefKind;
 use rustc_hir::{
     def_id::DefId, def_id::LocalDefId, Defaultness, ImplItem, ImplItemKind, Item, ItemKind,
+    TraitFn, TraitItem, TraitItemKind,
 };
 use rustc_middle::mir;
-use rustc_middle::ty::{AdtDef, Ty, TyCtxt, TyKind};
+use rustc_middle::ty::{AdtDef, AliasKind, ExistentialPredicate, Ty, TyCtxt, TyKind};
 use rustc_session::Session;
 use rustc_span::Span;
 use std::collections::{HashMap, HashSet};
@@ -42,21 +48,38 @@ pub(crate) fn stack_to_string(stack: &Vector<DefId>) -> String {
 pub struct CrateInfo {
     pub crate_name: String,
     pub opaque_mods: HashSet<String>,
+    /// If `true`, we extract the definitions of the external ADTs whose
+    /// content is public (see [is_public_external_adt]).
+    pub transparent_external_adts: bool,
+    /// The paths of the external functions whose bodies we extract (see
+    /// [crate::cli_options::CliOpts::transparent_extern]).
+    pub transparent_extern: Vec<Vec<String>>,
 }
 
 impl CrateInfo {
     fn has_opaque_decl(&self, name: &Name) -> bool {
         name.is_in_modules(&self.crate_name, &self.opaque_mods)
     }
+
+    fn has_transparent_extern_decl(&self, name: &Name) -> bool {
+        self.transparent_extern.iter().any(|path| {
+            let path: Vec<&str> = path.iter().map(|s| s.as_str()).collect();
+            name.prefix_is_same(&path)
+        })
+    }
 }
 
 /// All kind of supported Rust top-level declarations.
 /// const and static variables are merged together in the global kind.
+/// Note that the inherent `impl` blocks are not declarations: only the trait
+/// implementations are.
 #[derive(Copy, Clone, Debug, PartialEq, Eq)]
 pub enum DeclKind {
     Type,
     Fun,
     Global,
+    TraitDecl,
+    TraitImpl,
 }
 
 pub type DeclDependencies = LinkedHashSet<DefId>;
@@ -99,6 +122,8 @@ fn get_decl_name(tcx: TyCtxt, kind: DeclKind, id: DefId) -> Name {
         DeclKind::Type => type_def_id_to_name(tcx, id),
         DeclKind::Fun => function_def_id_to_name(tcx, id),
         DeclKind::Global => global_def_id_to_name(tcx, id),
+        DeclKind::TraitDecl => trait_def_id_to_name(tcx, id),
+        DeclKind::TraitImpl => trait_impl_def_id_to_name(tcx, id),
     }
 }
 
@@ -109,7 +134,7 @@ fn is_primitive_decl(kind: DeclKind, id: DefId, name: &Name) -> bool {
     match kind {
         DeclKind::Type => assumed::type_to_used_params(name).is_some(),
         DeclKind::Fun => assumed::function_to_info(name).is_some(),
-        DeclKind::Global => false,
+        DeclKind::Global | DeclKind::TraitDecl | DeclKind::TraitImpl => false,
     }
 }
 
@@ -118,6 +143,8 @@ fn check_decl_generics(kind: DeclKind, tcx: TyCtxt, id: DefId) {
         DeclKind::Type => generics::check_type_generics(tcx, id),
         DeclKind::Fun => generics::check_function_generics(tcx, id),
         DeclKind::Global => generics::check_global_generics(tcx, id),
+        DeclKind::TraitDecl => generics::check_trait_generics(tcx, id),
+        DeclKind::TraitImpl => generics::check_trait_impl_generics(tcx, id),
     }
 }
 
@@ -303,6 +330,38 @@ impl DeclarationsRegister {
         }
     }
 
+    /// Registers an external declaration and its dependencies recursively.
+    ///
+    /// Similar to [DeclarationsRegister::register_local_declaration], but
+    /// for the external declarations whose content we want to reveal.
+    fn register_external_declaration<
+        F: FnOnce(&mut DeclarationsRegister) -> Result<DeclDependencies>,
+    >(
+        &mut self,
+        ctx: &RegisterContext,
+        stack: &Vector<DefId>,
+        id: DefId,
+        kind: DeclKind,
+        list_dependencies: F,
+    ) -> Result<()> {
+        trace!(
+            "Registering transparent external declaration {:?}\n\nStack:\n{}",
+            id,
+            stack_to_string(stack)
+        );
+
+        self.add_begin(id);
+
+        // Register the file
+        self.register_file_from_def_id(ctx, id);
+
+        check_decl_generics(kind, ctx.rustc, id);
+
+        let deps = list_dependencies(self)?;
+        self.add_end(Declaration::new_transparent(id, kind, deps));
+        Ok(())
+    }
+
     /// Returns all registered files and declarations.
     /// Verifies that no known id or dependency is missing.
     fn get_files_and_declarations(self) -> (HashMap<FileName, FileInfo>, RegisteredDeclarations) {
@@ -351,7 +410,7 @@ fn explore_local_hir_type_item(
             trace!("enum");
             unreachable!();
         }
-        ItemKind::Struct(_, _) | ItemKind::Enum(_, _) => {
+        ItemKind::Struct(_, _) | ItemKind::Enum(_, _) | ItemKind::Union(_, _) => {
             trace!("adt");
 
             // Retrieve the MIR adt from the def id and register it, retrieve
@@ -405,7 +464,7 @@ fn explore_local_adt(
         // in case of an enum.
         let hir_variants: &[rustc_hir::Variant] = match &item.kind {
             ItemKind::Enum(enum_def, _) => enum_def.variants,
-            ItemKind::Struct(_, _) => {
+            ItemKind::Struct(_, _) | ItemKind::Union(_, _) => {
                 // Nothing to return
                 &[]
             }
@@ -437,11 +496,57 @@ fn explore_local_adt(
     })
 }
 
+/// Return `true` if the content of an external ADT is public, and can thus
+/// be revealed: enumerations, and structures whose fields are all public.
+fn is_public_external_adt(adt: &AdtDef) -> bool {
+    if adt.is_enum() {
+        true
+    } else if adt.is_struct() {
+        adt.all_fields().all(|field_def| field_def.vis.is_public())
+    } else {
+        // Unions
+        false
+    }
+}
+
+/// Register an external MIR ADT, whose content is public (see
+/// [is_public_external_adt]).
+///
+/// `stack`: see the explanations for [explore_local_hir_item].
+fn explore_external_adt(
+    ctx: &RegisterContext,
+    stack: Vector<DefId>,
+    decls: &mut DeclarationsRegister,
+    adt: &AdtDef,
+) -> Result<()> {
+    trace!("> adt: {:?}", adt);
+
+    let adt_did = adt.did();
+    // We don't have access to the HIR: we use the span of the whole ADT
+    let span = ctx.rustc.def_span(adt_did);
+
+    // Update the stack for when we explore the ADT body
+    let mut nstack = stack.clone();
+    nstack.push_back(adt_did);
+
+    decls.register_external_declaration(ctx, &stack, adt_did, DeclKind::Type, |decls| {
+        // Use a dummy substitution to instantiate the type parameters
+        let substs = rustc_middle::ty::subst::InternalSubsts::identity_for_item(ctx.rustc, adt_did);
+
+        let mut ty_deps = DeclDependencies::new();
+        for field_def in adt.all_fields() {
+            let ty = field_def.ty(ctx.rustc, substs);
+            explore_mir_ty(ctx, nstack.clone(), decls, &span, &mut ty_deps, &ty)?;
+        }
+        Ok(ty_deps)
+    })
+}
+
 /// Auxiliary function to register a list of type parameters.
 ///
 /// `stack`: see the explanations for [explore_local_hir_item].
 fn explore_mir_substs<'tcx>(
-    ctx: &RegisterContext,
+    ctx: &RegisterContext<'tcx, '_, '_>,
     stack: Vector<DefId>,
     decls: &mut DeclarationsRegister,
     span: &Span,
@@ -494,13 +599,13 @@ fn explore_mir_substs<'tcx>(
 /// before calling this function.
 ///
 /// `stack`: see the explanations for [explore_local_hir_item].
-fn explore_mir_ty(
-    ctx: &RegisterContext,
+fn explore_mir_ty<'tcx>(
+    ctx: &RegisterContext<'tcx, '_, '_>,
     stack: Vector<DefId>,
     decls: &mut DeclarationsRegister,
     span: &Span,
     ty_deps: &mut DeclDependencies,
-    ty: &Ty,
+    ty: &Ty<'tcx>,
 ) -> Result<()> {
     trace!("> ty: {:?}", ty);
 
@@ -544,6 +649,24 @@ fn explore_mir_ty(
                 assumed::type_to_used_params(&name)
             };
 
+            // The parameters filter may come from the user: check its length
+            // (see [assumed::UserAssumedDecls])
+            if let Some(used_params) = &used_params {
+                if used_params.len() != substs.len() {
+                    span_err(
+                        ctx.sess,
+                        *span,
+                        &format!(
+                            "The primitive type {} has {} generic parameter(s), but its filter has {} element(s)",
+                            name,
+                            substs.len(),
+                            used_params.len()
+                        ),
+                    );
+                    return Err(());
+                }
+            }
+
             // We probably don't need to check if the type is local...
             let is_prim = !adt_did.is_local() && used_params.is_some();
             // Add this ADT to the list of dependencies, only if it is not
@@ -574,12 +697,26 @@ fn explore_mir_ty(
                     substs,
                 )?;
 
-                // Register the external ADT as an opaque declaration.
-                // In the future, we will explore the ADT, to reveal its public
-                // information (public fields in case of a structure, variants in
-                // case of a public enumeration).
-                decls.register_opaque_declaration(ctx, &stack, adt_did, DeclKind::Type, &name);
-                Ok(())
+                // Register the external ADT. If the user asked for it (or if
+                // we need it, see [assumed::is_always_transparent_adt]), we
+                // explore the ADT to reveal its public information (public
+                // fields in case of a structure, variants in case of an
+                // enumeration). Otherwise, we register it as an opaque
+                // declaration.
+                if (ctx.crate_info.transparent_external_adts
+                    || assumed::is_always_transparent_adt(&name))
+                    && !is_prim
+                    && is_public_external_adt(adt)
+                {
+                    if decls.knows(&adt_did) {
+                        trace!("Adt already registered");
+                        return Ok(());
+                    }
+                    explore_external_adt(ctx, stack, decls, adt)
+                } else {
+                    decls.register_opaque_declaration(ctx, &stack, adt_did, DeclKind::Type, &name);
+                    Ok(())
+                }
             } else {
                 // Explore the type parameters instantiation
                 explore_mir_substs(
@@ -630,10 +767,11 @@ fn explore_mir_ty(
             Ok(())
         }
 
-        TyKind::RawPtr(_) => {
+        TyKind::RawPtr(ty_and_mut) => {
             // A raw pointer
             trace!("RawPtr");
-            Ok(())
+
+            explore_mir_ty(ctx, stack, decls, span, ty_deps, &ty_and_mut.ty)
         }
         TyKind::Foreign(_) => {
             // A raw pointer
@@ -647,30 +785,109 @@ fn explore_mir_ty(
             Err(())
         }
 
-        TyKind::FnDef(_, _) => {
-            // We shouldn't get there
+        TyKind::FnDef(def_id, substs) => {
+            // A function item: we translate it to a function pointer type,
+            // so we only need to explore its (instantiated) signature. The
+            // function itself is registered when exploring the body which
+            // uses the function item.
             trace!("FnDef");
-            unreachable!();
+            let sig = ctx.rustc.bound_fn_sig(*def_id).subst(ctx.rustc, substs);
+            for param_ty in sig.skip_binder().inputs_and_output.iter() {
+                explore_mir_ty(ctx, stack.clone(), decls, span, ty_deps, &param_ty)?;
+            }
+            Ok(())
         }
 
         TyKind::FnPtr(sig) => {
             trace!("FnPtr");
-            for param_ty in sig.inputs_and_output().no_bound_vars().unwrap().iter() {
+            // The signature may bind regions: we ignore the binder
+            for param_ty in sig.inputs_and_output().skip_binder().iter() {
                 explore_mir_ty(ctx, stack.clone(), decls, span, ty_deps, &param_ty)?;
             }
             Ok(())
         }
 
-        TyKind::Dynamic(_, _, _) => {
+        TyKind::Dynamic(preds, _, _) => {
             // A trait object
             trace!("Dynamic");
-            trace!("Patch");
+
+            // Explore the predicates: we need to register the traits, and
+            // to explore their arguments. The predicates may bind regions:
+            // we ignore the binders.
+            for pred in preds.iter() {
+                let trait_id = match pred.skip_binder() {
+                    ExistentialPredicate::Trait(trait_ref) => {
+                        explore_mir_substs(
+                            ctx,
+                            stack.clone(),
+                            decls,
+                            span,
+                            ty_deps,
+                            Option::None,
+                            &trait_ref.substs,
+                        )?;
+                        trait_ref.def_id
+                    }
+                    ExistentialPredicate::Projection(proj) => {
+                        let ty = proj.term.ty().unwrap();
+                        explore_mir_ty(ctx, stack.clone(), decls, span, ty_deps, &ty)?;
+                        proj.trait_ref(ctx.rustc).def_id
+                    }
+                    ExistentialPredicate::AutoTrait(trait_id) => trait_id,
+                };
+
+                ty_deps.insert(trait_id);
+                let trait_name = trait_def_id_to_name(ctx.rustc, trait_id);
+                explore_dependency_item(
+                    ctx,
+                    stack.clone(),
+                    decls,
+                    trait_id,
+                    DeclKind::TraitDecl,
+                    &trait_name,
+                )?;
+            }
             Ok(())
         }
-        TyKind::Closure(_, _) => {
+        TyKind::Closure(closure_id, substs) => {
             trace!("Closure");
-            trace!("Patch");
-            Ok(())
+
+            // We translate the closure type to the type of its state: we
+            // need to explore the types of the captured variables, as well
+            // as the generic arguments of the parent function (the other
+            // arguments of the substitution are introduced by rustc).
+            for upvar_ty in substs.as_closure().upvar_tys() {
+                explore_mir_ty(ctx, stack.clone(), decls, span, ty_deps, &upvar_ty)?;
+            }
+            let parent_substs = get_closure_parent_substs(ctx.rustc, *closure_id, substs);
+            explore_mir_substs(
+                ctx,
+                stack.clone(),
+                decls,
+                span,
+                ty_deps,
+                Option::None,
+                &parent_substs,
+            )?;
+
+            // The state type is generated from the closure itself: we
+            // register the closure (its body gets translated, and its state
+            // type is introduced at the same time, see
+            // [crate::rust_to_local_ids]).
+            ty_deps.insert(*closure_id);
+            if decls.knows(closure_id) {
+                trace!("Closure already registered");
+                return Ok(());
+            }
+            match closure_id.as_local() {
+                Some(local_id) => {
+                    explore_local_item_with_body(ctx, stack, decls, local_id, DeclKind::Fun)
+                }
+                None => {
+                    span_err(ctx.sess, *span, "External closures are not supported");
+                    Err(())
+                }
+            }
         }
 
         TyKind::Generator(_, _, _) | TyKind::GeneratorWitness(_) => {
@@ -679,8 +896,43 @@ fn explore_mir_ty(
             Err(())
         }
 
-        TyKind::Alias(_, _) => {
-            unimplemented!();
+        TyKind::Alias(AliasKind::Projection, alias_ty) => {
+            trace!("Projection");
+
+            // If we can normalize the projection, we explore the normalized
+            // type (this is what we translate it to)
+            if let Some(nty) = try_normalize_projection(ctx.rustc, *ty) {
+                return explore_mir_ty(ctx, stack, decls, span, ty_deps, &nty);
+            }
+
+            // Otherwise, the type refers to the trait: explore it
+            let (trait_ref, _) = alias_ty.trait_ref_and_own_substs(ctx.rustc);
+            explore_mir_substs(
+                ctx,
+                stack.clone(),
+                decls,
+                span,
+                ty_deps,
+                Option::None,
+                &trait_ref.substs,
+            )?;
+
+            let trait_id = trait_ref.def_id;
+            ty_deps.insert(trait_id);
+            let trait_name = trait_def_id_to_name(ctx.rustc, trait_id);
+            explore_dependency_item(
+                ctx,
+                stack,
+                decls,
+                trait_id,
+                DeclKind::TraitDecl,
+                &trait_name,
+            )
+        }
+        TyKind::Alias(AliasKind::Opaque, _) => {
+            trace!("Opaque");
+            span_err(ctx.sess, *span, "`impl Trait` types are not supported");
+            Err(())
         }
 
         TyKind::Error(_) => {
@@ -707,11 +959,12 @@ fn explore_mir_ty(
     }
 }
 
-// Extract function information from an operand
+// Extract function information from an operand (returns `None` if the
+// operand is not a function item)
 fn get_fun_from_operand<'tcx>(
     op: &mir::Operand<'tcx>,
 ) -> Option<(DefId, rustc_middle::ty::subst::SubstsRef<'tcx>)> {
-    let fun_ty = op.constant().unwrap().literal.ty();
+    let fun_ty = op.constant()?.literal.ty();
     match fun_ty.kind() {
         TyKind::FnDef(def_id, substs) => Some((*def_id, substs)),
         _ => None,
@@ -751,12 +1004,16 @@ fn visit_globals<'tcx>(
     visit_block(block, ConstantVisitor { f });
 }
 
-/// Return true if the type is exactly `&str`
-fn ty_is_shared_borrow_str(ty: &Ty) -> bool {
+/// Return true if the type is exactly `&str` or `&[u8]`
+fn ty_is_shared_borrow_str_or_bytes(ty: &Ty) -> bool {
     match ty.kind() {
-        TyKind::Ref(_, sty, rustc_middle::mir::Mutability::Not) => {
-            matches!(sty.kind(), TyKind::Str)
-        }
+        TyKind::Ref(_, sty, rustc_middle::mir::Mutability::Not) => match sty.kind() {
+            TyKind::Str => true,
+            TyKind::Slice(elem_ty) => {
+                matches!(elem_ty.kind(), TyKind::Uint(rustc_middle::ty::UintTy::U8))
+            }
+            _ => false,
+        },
         _ => false,
     }
 }
@@ -798,18 +1055,18 @@ fn visit_global_dependencies<'tcx, F: FnMut(DefId)>(
                 mir::interpret::ConstValue::Scalar(_) => {
                     // Nothing to do
                 }
+                mir::interpret::ConstValue::ByRef { .. } if ty.is_fn() => {
+                    // Function items are zero-sized: we can get there through
+                    // the `sym` operands of the inline assembly blocks
+                }
                 mir::interpret::ConstValue::ByRef { .. } => {
                     unimplemented!()
                 }
                 mir::interpret::ConstValue::Slice { .. } => {
                     trace!("ConstValue::Slice: ty: {:?}", ty);
-                    // For now we support slices only if they are `&str` -
-                    // we should encounter them only through the error messages
-                    // raised when panicking (because we use visitors, we explore
-                    // *everything*, including the arguments of `std::core::panic`
-                    // for instance).
-                    // TODO: change that.
-                    assert!(ty_is_shared_borrow_str(&ty));
+                    // The constant slices are string literals (`&str`) or
+                    // byte slices (`&[u8]`)
+                    assert!(ty_is_shared_borrow_str_or_bytes(&ty));
                 }
                 mir::interpret::ConstValue::ZeroSized { .. } => {
                     // Nothing to do
@@ -825,6 +1082,233 @@ fn visit_global_dependencies<'tcx, F: FnMut(DefId)>(
     });
 }
 
+/// Return the operands appearing in an rvalue.
+fn get_rvalue_operands<'a, 'tcx>(rvalue: &'a mir::Rvalue<'tcx>) -> Vec<&'a mir::Operand<'tcx>> {
+    match rvalue {
+        mir::Rvalue::Use(op)
+        | mir::Rvalue::Repeat(op, _)
+        | mir::Rvalue::Cast(_, op, _)
+        | mir::Rvalue::UnaryOp(_, op)
+        | mir::Rvalue::ShallowInitBox(op, _) => vec![op],
+        mir::Rvalue::BinaryOp(_, ops) | mir::Rvalue::CheckedBinaryOp(_, ops) => {
+            vec![&ops.0, &ops.1]
+        }
+        mir::Rvalue::Aggregate(_, ops) => ops.iter().collect(),
+        mir::Rvalue::Ref(_, _, _)
+        | mir::Rvalue::ThreadLocalRef(_)
+        | mir::Rvalue::AddressOf(_, _)
+        | mir::Rvalue::Len(_)
+        | mir::Rvalue::NullaryOp(_, _)
+        | mir::Rvalue::Discriminant(_)
+        | mir::Rvalue::CopyForDeref(_) => vec![],
+    }
+}
+
+/// Register a function called or used as a value in the body of `caller_id`.
+/// Return the name of the function (after resolution, if it is a trait method)
+/// and the information about the arguments which are used (if the function
+/// is primitive, we may need to filter the arguments).
+///
+/// `stack`: see the explanations for [explore_local_hir_item].
+#[allow(clippy::too_many_arguments)]
+fn explore_fun_dependency<'tcx>(
+    ctx: &RegisterContext<'tcx, '_, '_>,
+    stack: Vector<DefId>,
+    decls: &mut DeclarationsRegister,
+    span: &Span,
+    deps: &mut DeclDependencies,
+    caller_id: DefId,
+    fid: DefId,
+    substs: rustc_middle::ty::subst::SubstsRef<'tcx>,
+) -> Result<(Name, Option<Vec<bool>>)> {
+    let name = function_def_id_to_name(ctx.rustc, fid);
+    trace!("called function: name: {:?}", name);
+
+    // The intrinsics don't have declarations: we only need to register
+    // the types given as parameters.
+    if translate_functions_to_ullbc::is_intrinsic_call(ctx.rustc, fid) {
+        explore_mir_substs(ctx, stack, decls, span, deps, Option::None, &substs)?;
+        return Ok((name, Option::None));
+    }
+
+    // We may need to filter the types and arguments, if the type
+    // is considered primitive
+    let is_prim = translate_functions_to_ullbc::is_primitive_function_call(ctx.rustc, fid, substs);
+    let (used_types, used_args) = if is_prim {
+        let used = assumed::function_to_info(&name).unwrap();
+        check_primitive_fun_info(ctx, span, &name, fid, substs, &used)?;
+        (
+            Option::Some(used.used_type_params),
+            Option::Some(used.used_args),
+        )
+    } else {
+        (Option::None, Option::None)
+    };
+
+    // If the function is a trait method, try to resolve the
+    // implementation statically (we need to do the same as
+    // what is done in [crate::translate_functions_to_ullbc]).
+    let (fid, substs, kind) = if is_prim {
+        (fid, substs, DeclKind::Fun)
+    } else {
+        use translate_functions_to_ullbc::ResolvedCall;
+        match translate_functions_to_ullbc::resolve_function_call(
+            ctx.sess, ctx.rustc, *span, caller_id, fid, substs,
+        )? {
+            ResolvedCall::Fun(fid, substs) => {
+                // If we call a closure, only the generic arguments
+                // of the parent function are relevant
+                let substs = if ctx.rustc.is_closure(fid) {
+                    get_closure_parent_substs(ctx.rustc, fid, substs)
+                } else {
+                    substs
+                };
+                (fid, substs, DeclKind::Fun)
+            }
+            ResolvedCall::TraitMethod {
+                trait_id,
+                method: _,
+                impl_source: _,
+            } => (trait_id, substs, DeclKind::TraitDecl),
+        }
+    };
+    let name = get_decl_name(ctx.rustc, kind, fid);
+
+    // Add this function (or trait) to the list of dependencies,
+    // only if it is non-primitive
+    if !is_prim {
+        deps.insert(fid);
+    }
+
+    // Register the types given as parameters.
+    explore_mir_substs(ctx, stack.clone(), decls, span, deps, used_types, &substs)?;
+
+    // Note that we don't need to register the "bare" function
+    // signature: all the types it contains are already covered
+    // by the type arguments and the parameters, at the exception
+    // of the projections which can't be normalized (ex.: the type
+    // `<I as Iterator>::Item` in the signature of `Iterator::next`).
+    // The local functions are explored in full, but we need to
+    // register the traits of those projections for the external
+    // functions.
+    if kind == DeclKind::Fun && !fid.is_local() {
+        explore_fun_sig_projections(ctx, stack.clone(), decls, span, deps, fid)?;
+    }
+
+    // The stack already contains the id of the body owner: no
+    // need to update it.
+    explore_dependency_item(ctx, stack, decls, fid, kind, &name)?;
+
+    Ok((name, used_args))
+}
+
+/// Check that the information about a primitive function is consistent with
+/// its signature. This information may come from the user (see
+/// [assumed::UserAssumedDecls]), and we use it to filter the type arguments
+/// and the arguments of the calls.
+fn check_primitive_fun_info<'tcx>(
+    ctx: &RegisterContext<'tcx, '_, '_>,
+    span: &Span,
+    name: &Name,
+    fid: DefId,
+    substs: rustc_middle::ty::subst::SubstsRef<'tcx>,
+    info: &assumed::FunInfo,
+) -> Result<()> {
+    let num_args = ctx.rustc.fn_sig(fid).skip_binder().inputs().len();
+    if info.used_type_params.len() != substs.len() {
+        span_err(
+            ctx.sess,
+            *span,
+            &format!(
+                "The primitive function {} has {} generic parameter(s), but its type parameters filter has {} element(s)",
+                name,
+                substs.len(),
+                info.used_type_params.len()
+            ),
+        );
+        return Err(());
+    }
+    if info.used_args.len() != num_args {
+        span_err(
+            ctx.sess,
+            *span,
+            &format!(
+                "The primitive function {} has {} argument(s), but its arguments filter has {} element(s)",
+                name,
+                num_args,
+                info.used_args.len()
+            ),
+        );
+        return Err(());
+    }
+    Ok(())
+}
+
+/// Register the projections (and their traits) found in the signature of a
+/// function (see [explore_fun_dependency]).
+///
+/// `stack`: see the explanations for [explore_local_hir_item].
+fn explore_fun_sig_projections(
+    ctx: &RegisterContext<'_, '_, '_>,
+    stack: Vector<DefId>,
+    decls: &mut DeclarationsRegister,
+    span: &Span,
+    deps: &mut DeclDependencies,
+    fid: DefId,
+) -> Result<()> {
+    // The signature may bind regions: we ignore the binder
+    let sig = ctx.rustc.fn_sig(fid);
+    for ty in sig.inputs_and_output().skip_binder().iter() {
+        for arg in ty.walk() {
+            if let rustc_middle::ty::subst::GenericArgKind::Type(ty) = arg.unpack() {
+                if let TyKind::Alias(AliasKind::Projection, _) = ty.kind() {
+                    explore_mir_ty(ctx, stack.clone(), decls, span, deps, &ty)?;
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Register the function item referenced by an operand, if the operand is
+/// a function item used as a value (for instance, given as argument to a
+/// function, or converted to a function pointer).
+///
+/// The constructors of ADTs don't need a special treatment: the ADT itself
+/// is registered when exploring the type of the operand.
+///
+/// `stack`: see the explanations for [explore_local_hir_item].
+fn explore_fun_item_operand<'tcx>(
+    ctx: &RegisterContext<'tcx, '_, '_>,
+    stack: Vector<DefId>,
+    decls: &mut DeclarationsRegister,
+    span: &Span,
+    deps: &mut DeclDependencies,
+    caller_id: DefId,
+    op: &mir::Operand<'tcx>,
+) -> Result<()> {
+    match get_fun_from_operand(op) {
+        Option::None => Ok(()),
+        Option::Some((fid, substs)) => {
+            explore_mir_ty(
+                ctx,
+                stack.clone(),
+                decls,
+                span,
+                deps,
+                &op.constant().unwrap().ty(),
+            )?;
+            if matches!(ctx.rustc.def_kind(fid), DefKind::Ctor(_, _)) {
+                Ok(())
+            } else {
+                let _ =
+                    explore_fun_dependency(ctx, stack, decls, span, deps, caller_id, fid, substs)?;
+                Ok(())
+            }
+        }
+    }
+}
+
 /// `stack`: see the explanations for [explore_local_hir_item].
 fn explore_dependency_item(
     ctx: &RegisterContext,
@@ -838,9 +1322,22 @@ fn explore_dependency_item(
         None => {
             trace!("external expression");
 
-            // Register the external expression as an opaque one.
-            decls.register_opaque_declaration(ctx, &stack, id, kind, name);
-            Ok(())
+            // If the user asked for it, and if the MIR of the function is
+            // available, register the external function as a transparent one.
+            // Otherwise, register the external expression as an opaque one.
+            if kind == DeclKind::Fun
+                && ctx.crate_info.has_transparent_extern_decl(name)
+                && !is_primitive_decl(kind, id, name)
+                && external_mir_is_available(ctx.rustc, id)
+            {
+                if decls.knows(&id) {
+                    return Ok(());
+                }
+                explore_external_item_with_body(ctx, stack, decls, id, kind)
+            } else {
+                decls.register_opaque_declaration(ctx, &stack, id, kind, name);
+                Ok(())
+            }
         }
         Some(node) => {
             trace!("local expression");
@@ -853,6 +1350,24 @@ fn explore_dependency_item(
                     trace!("Impl item");
                     explore_local_hir_impl_item(ctx, stack, decls, impl_item)
                 }
+                rustc_hir::Node::TraitItem(_) => {
+                    trace!("Trait item");
+                    // The trait items are registered when exploring the
+                    // trait declaration itself
+                    let trait_id = ctx.rustc.trait_of_item(id).unwrap();
+                    let trait_item = ctx.rustc.hir().expect_item(trait_id.expect_local());
+                    explore_local_hir_item(ctx, stack, decls, false, trait_item)
+                }
+                rustc_hir::Node::Expr(_) => {
+                    trace!("Closure");
+                    // The closures are the only expressions which can be
+                    // called
+                    assert!(ctx.rustc.is_closure(id));
+                    if decls.knows(&id) {
+                        return Ok(());
+                    }
+                    explore_local_item_with_body(ctx, stack, decls, id.expect_local(), kind)
+                }
                 _ => {
                     unreachable!();
                 }
@@ -868,11 +1383,13 @@ fn explore_body(
     ctx: &RegisterContext,
     stack: Vector<DefId>,
     decls: &mut DeclarationsRegister,
-    def_id: LocalDefId,
+    def_id: DefId,
     deps: &mut DeclDependencies,
 ) -> Result<()> {
-    // Retrieve the MIR code.
-    let body = get_mir_for_def_id_and_level(ctx.rustc, def_id, ctx.mir_level);
+    // Retrieve the MIR code. Note that the level of the MIR of the external
+    // functions is not necessarily the level we extract.
+    let mir_level = get_mir_level_for_def_id(def_id, ctx.mir_level);
+    let body = get_mir_for_def_id(ctx.rustc, def_id, ctx.mir_level);
 
     // Register the file from the span
     decls.register_file_from_span(ctx, body.span);
@@ -880,14 +1397,14 @@ fn explore_body(
     trace!("Body: {:?}", body);
 
     // Visit the global dependencies if the MIR is not optimized.
-    if extract_constants_at_top_level(ctx.mir_level) {
+    if extract_constants_at_top_level(mir_level) {
         // TODO: For now the order of dependencies export depend on the order
         // in which they are discovered. By storing their metadata, we would be
         // able to order them properly, without depending on the visit ordering.
         // Avoid registering globals in optimized MIR (they will be inlined).
         for b in body.basic_blocks.iter() {
             propagate_error(
-                |f| visit_global_dependencies(ctx.mir_level, b, f),
+                |f| visit_global_dependencies(mir_level, b, f),
                 |id| {
                     let name = global_def_id_to_name(ctx.rustc, id);
 
@@ -932,8 +1449,23 @@ fn explore_body(
             // defined).
             decls.register_file_from_span(ctx, statement.source_info.span);
             match &statement.kind {
-                mir::StatementKind::Assign(_)
-                | mir::StatementKind::FakeRead(_)
+                mir::StatementKind::Assign(assign) => {
+                    // The function items used as values (for instance, when
+                    // converted to function pointers) are dependencies
+                    let (_, rvalue) = &**assign;
+                    for op in get_rvalue_operands(rvalue).into_iter() {
+                        explore_fun_item_operand(
+                            ctx,
+                            stack.clone(),
+                            decls,
+                            &statement.source_info.span,
+                            deps,
+                            def_id,
+                            op,
+                        )?;
+                    }
+                }
+                mir::StatementKind::FakeRead(_)
                 | mir::StatementKind::SetDiscriminant {
                     place: _,
                     variant_index: _,
@@ -1008,93 +1540,87 @@ fn explore_body(
                 trace!("terminator: Call\n{:?}", &terminator);
                 trace!("terminator:Call:func: {:?}", func);
 
-                let (fid, substs) = get_fun_from_operand(func).expect("Expected a function call");
-                trace!("terminator:Call:fid {:?}", fid);
-
-                let name = function_def_id_to_name(ctx.rustc, fid);
-                trace!("called function: name: {:?}", name);
-
-                // We may need to filter the types and arguments, if the type
-                // is considered primitive
-                let (used_types, used_args, is_prim) = if fid.is_local() {
-                    // We probably do not need to check if the function is local...
-                    (Option::None, Option::None, false)
-                } else {
-                    match assumed::function_to_info(&name) {
-                        Option::Some(used) => {
-                            // The function is primitive
-                            (
-                                Option::Some(used.used_type_params),
-                                Option::Some(used.used_args),
-                                true,
-                            )
-                        }
-                        Option::None => {
-                            // The function is non-primitive (i.e., external)
-                            (Option::None, Option::None, false)
+                match get_fun_from_operand(func) {
+                    Option::Some((fid, substs)) => {
+                        trace!("terminator:Call:fid {:?}", fid);
+                        let (name, used_args) = explore_fun_dependency(
+                            ctx,
+                            stack.clone(),
+                            decls,
+                            fn_span,
+                            deps,
+                            def_id,
+                            fid,
+                            substs,
+                        )?;
+
+                        // Filter and register the argument types.
+                        // There is something very annoying, which is that MIR is quite
+                        // low level.
+                        // Very specifically, when introducing `box_free`, rustc introduces
+                        // something of the following form:
+                        // ```
+                        // _9 = alloc::alloc::box_free::<T, std::alloc::Global>(
+                        //   move (_4.0: std::ptr::Unique<T>),
+                        //   move (_4.1: std::alloc::Global)) -> bb3;
+                        // ```
+                        // We don't support unique pointers, so we have to ignore the
+                        // arguments in this case (and the `box_free` case has a
+                        // special treatment when translating function bodies).
+                        // Note that the type parameters have already been registered.
+                        if !name.equals_ref_name(&assumed::BOX_FREE_NAME) {
+                            let args: Vec<&mir::Operand<'_>> = match used_args {
+                                Option::None => args.iter().collect(),
+                                Option::Some(used_args) => {
+                                    // Filter
+                                    trace!("args: {:?}, used_args: {:?}", args, used_args);
+                                    assert!(args.len() == used_args.len());
+                                    args.iter()
+                                        .zip(used_args.into_iter())
+                                        .filter_map(
+                                            |(param, used)| if used { Some(param) } else { None },
+                                        )
+                                        .collect()
+                                }
+                            };
+                            for a in args.into_iter() {
+                                trace!("terminator: Call: arg: {:?}", a);
+
+                                let ty = a.ty(&body.local_decls, ctx.rustc);
+                                explore_mir_ty(ctx, stack.clone(), decls, fn_span, deps, &ty)?;
+                                explore_fun_item_operand(
+                                    ctx,
+                                    stack.clone(),
+                                    decls,
+                                    fn_span,
+                                    deps,
+                                    def_id,
+                                    a,
+                                )?;
+                            }
                         }
                     }
-                };
-
-                // Add this function to the list of dependencies, only if
-                // it is non-primitive
-                if !is_prim {
-                    deps.insert(fid);
-                }
-
-                // Register the types given as parameters.
-                explore_mir_substs(
-                    ctx,
-                    stack.clone(),
-                    decls,
-                    fn_span,
-                    deps,
-                    used_types,
-                    &substs,
-                )?;
-
-                // Filter and register the argument types.
-                // There is something very annoying, which is that MIR is quite
-                // low level.
-                // Very specifically, when introducing `box_free`, rustc introduces
-                // something of the following form:
-                // ```
-                // _9 = alloc::alloc::box_free::<T, std::alloc::Global>(
-                //   move (_4.0: std::ptr::Unique<T>),
-                //   move (_4.1: std::alloc::Global)) -> bb3;
-                // ```
-                // We don't support unique pointers, so we have to ignore the
-                // arguments in this case (and the `box_free` case has a
-                // special treatment when translating function bodies).
-                // Note that the type parameters have already been registered.
-                if !name.equals_ref_name(&assumed::BOX_FREE_NAME) {
-                    let args: Vec<&mir::Operand<'_>> = match used_args {
-                        Option::None => args.iter().collect(),
-                        Option::Some(used_args) => {
-                            // Filter
-                            trace!("args: {:?}, used_args: {:?}", args, used_args);
-                            assert!(args.len() == used_args.len());
-                            args.iter()
-                                .zip(used_args.into_iter())
-                                .filter_map(|(param, used)| if used { Some(param) } else { None })
-                                .collect()
+                    Option::None => {
+                        // Indirect call through a function pointer: the type
+                        // of the callee has been registered with the local
+                        // variables, we only need to explore the arguments.
+                        for a in args.iter() {
+                            trace!("terminator: Call: arg: {:?}", a);
+
+                            let ty = a.ty(&body.local_decls, ctx.rustc);
+                            explore_mir_ty(ctx, stack.clone(), decls, fn_span, deps, &ty)?;
+                            explore_fun_item_operand(
+                                ctx,
+                                stack.clone(),
+                                decls,
+                                fn_span,
+                                deps,
+                                def_id,
+                                a,
+                            )?;
                         }
-                    };
-                    for a in args.into_iter() {
-                        trace!("terminator: Call: arg: {:?}", a);
-
-                        let ty = a.ty(&body.local_decls, ctx.rustc);
-                        explore_mir_ty(ctx, stack.clone(), decls, fn_span, deps, &ty)?;
                     }
                 }
-
-                // Note that we don't need to register the "bare" function
-                // signature: all the types it contains are already covered
-                // by the type arguments and the parameters.
-
-                // The stack already contains the id of the body owner: no
-                // need to update it.
-                explore_dependency_item(ctx, stack.clone(), decls, fid, DeclKind::Fun, &name)?;
             }
             mir::TerminatorKind::Yield {
                 value: _,
@@ -1126,11 +1652,9 @@ fn explore_body(
                 cleanup: _,
             } => {
                 trace!("terminator: InlineASM");
-                span_err(
-                    ctx.sess,
-                    terminator.source_info.span,
-                    "Inline ASM is not supported",
-                );
+                // Nothing to do: the inputs and outputs are local variables
+                // (whose types are explored with the body) or integer
+                // constants, and we only keep the names of the `sym` operands
             }
         }
     }
@@ -1154,7 +1678,29 @@ fn explore_local_item_with_body(
 
     decls.register_local_declaration(ctx, &stack, local_id, kind, |decls| {
         let mut deps = DeclDependencies::new();
-        explore_body(ctx, stack.clone(), decls, local_id, &mut deps)?;
+        explore_body(ctx, stack.clone(), decls, local_id.to_def_id(), &mut deps)?;
+        Ok(deps)
+    })
+}
+
+/// Register an external function whose body we extract (see
+/// [CrateInfo::transparent_extern]).
+///
+/// `stack`: see the explanations for [explore_local_hir_item].
+fn explore_external_item_with_body(
+    ctx: &RegisterContext,
+    stack: Vector<DefId>,
+    decls: &mut DeclarationsRegister,
+    id: DefId,
+    kind: DeclKind,
+) -> Result<()> {
+    // Update the stack
+    let mut stack = stack;
+    stack.push_back(id);
+
+    decls.register_external_declaration(ctx, &stack, id, kind, |decls| {
+        let mut deps = DeclDependencies::new();
+        explore_body(ctx, stack.clone(), decls, id, &mut deps)?;
         Ok(deps)
     })
 }
@@ -1216,8 +1762,7 @@ fn explore_local_hir_item(
             Ok(())
         }
         ItemKind::OpaqueTy(_) => unimplemented!(),
-        ItemKind::Union(_, _) => unimplemented!(),
-        ItemKind::Enum(_, _) | ItemKind::Struct(_, _) => {
+        ItemKind::Enum(_, _) | ItemKind::Struct(_, _) | ItemKind::Union(_, _) => {
             explore_local_hir_type_item(ctx, stack, decls, item, def_id)
         }
         ItemKind::Fn(_, _, _) => explore_local_item_with_body(
@@ -1247,6 +1792,11 @@ fn explore_local_hir_item(
             // Sanity checks
             translate_functions_to_ullbc::check_impl_item(impl_block);
 
+            if impl_block.of_trait.is_some() {
+                // Trait implementation: we register the impl block itself
+                return explore_local_trait_impl(ctx, stack, decls, item, impl_block);
+            }
+
             // Update the stack
             let mut stack = stack;
             stack.push_back(def_id);
@@ -1262,6 +1812,10 @@ fn explore_local_hir_item(
             }
             Ok(())
         }
+        ItemKind::Trait(_, _, _, _, trait_items) => {
+            trace!("trait");
+            explore_local_trait_decl(ctx, stack, decls, item, trait_items)
+        }
         ItemKind::Use(_, _) => {
             // Ignore
             trace!("use");
@@ -1307,6 +1861,160 @@ fn explore_local_hir_item(
     }
 }
 
+/// Explore a local trait declaration. We register the trait methods as
+/// function declarations, and list them as dependencies of the trait.
+///
+/// `stack`: see the explanations for [explore_local_hir_item].
+fn explore_local_trait_decl(
+    ctx: &RegisterContext,
+    stack: Vector<DefId>,
+    decls: &mut DeclarationsRegister,
+    item: &Item,
+    trait_items: &[rustc_hir::TraitItemRef],
+) -> Result<()> {
+    let local_id = item.owner_id.def_id;
+
+    // Update the stack
+    let mut nstack = stack.clone();
+    nstack.push_back(local_id.to_def_id());
+
+    decls.register_local_declaration(ctx, &stack, local_id, DeclKind::TraitDecl, |decls| {
+        let hir_map = ctx.rustc.hir();
+        let mut deps = DeclDependencies::new();
+        for trait_item_ref in trait_items {
+            // trait_item_ref only gives the reference of the trait item:
+            // we need to look it up
+            let trait_item = hir_map.trait_item(trait_item_ref.id);
+            if let TraitItemKind::Fn(_, _) = &trait_item.kind {
+                deps.insert(trait_item.owner_id.to_def_id());
+            }
+            explore_local_hir_trait_item(ctx, nstack.clone(), decls, &mut deps, trait_item)?;
+        }
+        Ok(deps)
+    })
+}
+
+/// Explore a trait item (an item defined in a `trait` block).
+///
+/// `deps`: the dependencies of the trait declaration. We add to those
+/// the types appearing in the signatures of the required methods.
+///
+/// `stack`: see the explanations for [explore_local_hir_item].
+fn explore_local_hir_trait_item(
+    ctx: &RegisterContext,
+    stack: Vector<DefId>,
+    decls: &mut DeclarationsRegister,
+    deps: &mut DeclDependencies,
+    trait_item: &TraitItem,
+) -> Result<()> {
+    // Check if the item has already been registered
+    let def_id = trait_item.owner_id.to_def_id();
+    if decls.knows(&def_id) {
+        return Ok(());
+    }
+
+    // Match on the trait item kind
+    match &trait_item.kind {
+        TraitItemKind::Const(_, _) => Ok(()), // patch
+        TraitItemKind::Type(_, _) => {
+            // An associated type: there is nothing to explore (we don't
+            // support default values for the associated types)
+            Ok(())
+        }
+        TraitItemKind::Fn(_, TraitFn::Provided(_)) => {
+            // A method with a default implementation
+            let local_id = trait_item.owner_id.def_id;
+            explore_local_item_with_body(ctx, stack, decls, local_id, DeclKind::Fun)
+        }
+        TraitItemKind::Fn(_, TraitFn::Required(_)) => {
+            // A method without body: we register it as an opaque function.
+            // Because opaque declarations don't have dependencies, we
+            // register the types used in its signature as dependencies
+            // of the trait.
+            let signature = ctx.rustc.fn_sig(def_id);
+            for ty in signature.skip_binder().inputs_and_output.iter() {
+                explore_mir_ty(ctx, stack.clone(), decls, &trait_item.span, deps, &ty)?;
+            }
+            let name = function_def_id_to_name(ctx.rustc, def_id);
+            decls.register_opaque_declaration(ctx, &stack, def_id, DeclKind::Fun, &name);
+            Ok(())
+        }
+    }
+}
+
+/// Explore a local trait implementation.
+/// The dependencies of a trait implementation are: the implemented trait,
+/// the types appearing in the trait reference (including the `Self` type)
+/// and the methods defined in the `impl` block.
+///
+/// `stack`: see the explanations for [explore_local_hir_item].
+fn explore_local_trait_impl(
+    ctx: &RegisterContext,
+    stack: Vector<DefId>,
+    decls: &mut DeclarationsRegister,
+    item: &Item,
+    impl_block: &rustc_hir::Impl,
+) -> Result<()> {
+    let local_id = item.owner_id.def_id;
+    let def_id = local_id.to_def_id();
+
+    // Update the stack
+    let mut nstack = stack.clone();
+    nstack.push_back(def_id);
+
+    decls.register_local_declaration(ctx, &stack, local_id, DeclKind::TraitImpl, |decls| {
+        let mut deps = DeclDependencies::new();
+
+        // Explore the trait reference. Note that its substitution contains
+        // the `Self` type.
+        let trait_ref = ctx.rustc.impl_trait_ref(def_id).unwrap();
+        explore_mir_substs(
+            ctx,
+            nstack.clone(),
+            decls,
+            &item.span,
+            &mut deps,
+            Option::None,
+            &trait_ref.substs,
+        )?;
+
+        // Explore the implemented trait
+        let trait_id = trait_ref.def_id;
+        deps.insert(trait_id);
+        let trait_name = trait_def_id_to_name(ctx.rustc, trait_id);
+        explore_dependency_item(
+            ctx,
+            nstack.clone(),
+            decls,
+            trait_id,
+            DeclKind::TraitDecl,
+            &trait_name,
+        )?;
+
+        // Explore the items
+        let hir_map = ctx.rustc.hir();
+        for impl_item_ref in impl_block.items {
+            // impl_item_ref only gives the reference of the impl item:
+            // we need to look it up
+            let impl_item = hir_map.impl_item(impl_item_ref.id);
+            match &impl_item.kind {
+                ImplItemKind::Fn(_, _) => {
+                    deps.insert(impl_item.owner_id.to_def_id());
+                }
+                ImplItemKind::Type(_) => {
+                    // Explore the definition of the associated type
+                    let ty = ctx.rustc.type_of(impl_item.owner_id.to_def_id());
+                    explore_mir_ty(ctx, nstack.clone(), decls, &impl_item.span, &mut deps, &ty)?;
+                }
+                ImplItemKind::Const(_, _) => (),
+            }
+
+            explore_local_hir_impl_item(ctx, nstack.clone(), decls, impl_item)?;
+        }
+        Ok(deps)
+    })
+}
+
 /// Explore an impl item (an item defined in an `impl` block).
 ///
 /// `stack`: see the explanations for [explore_local_hir_item].
@@ -1329,8 +2037,10 @@ fn explore_local_hir_impl_item(
     match &impl_item.kind {
         ImplItemKind::Const(_, _) => Ok(()), // patch
         ImplItemKind::Type(_) => {
-            // Note sure what to do with associated types yet
-            unimplemented!();
+            // An associated type definition: the type is explored in
+            // [explore_local_trait_impl], because it is a dependency of
+            // the trait implementation
+            Ok(())
         }
         ImplItemKind::Fn(_, _) => {
             let local_id = impl_item.owner_id.to_def_id().as_local().unwrap();
@@ -20,7 +20,7 @@ use crate::ullbc_ast_utils::body_transform_operands;
 use crate::values::VarId;
 
 fn make_aggregate_kind(ty: &ETy, var_index: Option<VariantId::Id>) -> AggregateKind {
-    let (id, _, fields) = ty.as_adt();
+    let (id, _, fields, cgs) = ty.as_adt();
     match id {
         TypeId::Tuple => {
             assert!(var_index.is_none());
@@ -28,7 +28,8 @@ fn make_aggregate_kind(ty: &ETy, var_index: Option<VariantId::Id>) -> AggregateK
         }
         TypeId::Adt(decl_id) => {
             let fields = fields.iter().cloned().collect();
-            AggregateKind::Adt(*decl_id, var_index, vec![], fields)
+            let cgs = cgs.iter().cloned().collect();
+            AggregateKind::Adt(*decl_id, var_index, vec![], fields, cgs)
         }
         TypeId::Assumed(_) => unreachable!(),
     }
@@ -41,6 +42,7 @@ fn make_aggregate_kind(ty: &ETy, var_index: Option<VariantId::Id>) -> AggregateK
 /// Goes fom e.g. `f(T::A(x, y))` to `let a = T::A(x, y); f(a)`.
 /// The function is recursively called on the aggregate fields (e.g. here x and y).
 fn transform_constant_adt<F: FnMut(ETy) -> VarId::Id>(
+    type_defs: &TypeDecls,
     meta: &Meta,
     nst: &mut Vec<Statement>,
     ty: &ETy,
@@ -53,10 +55,24 @@ fn transform_constant_adt<F: FnMut(ETy) -> VarId::Id>(
         _ => return None,
     };
 
+    // Retrieve the types of the fields
+    let field_tys: Vec<ETy> = match ty.as_adt() {
+        (TypeId::Tuple, _, tys, _) => tys.iter().cloned().collect(),
+        (TypeId::Adt(id), _, tys, cgs) => {
+            let def = type_defs.get_type_def(*id).unwrap();
+            def.get_erased_regions_instantiated_field_types(*variant, tys, cgs)
+                .into_iter()
+                .collect()
+        }
+        (TypeId::Assumed(_), _, _, _) => unreachable!(),
+    };
+
     // Translate fields recursively into statements and operands.
-    let ops = zip(ty.as_adt().2, fields)
+    let ops = zip(&field_tys, fields)
         .map(|(f_ty, f_val)| {
-            if let Some(var_id) = transform_constant_adt(meta, nst, f_ty, f_val, make_new_var) {
+            if let Some(var_id) =
+                transform_constant_adt(type_defs, meta, nst, f_ty, f_val, make_new_var)
+            {
                 Operand::Move(Place::new(var_id))
             } else {
                 Operand::Const(f_ty.clone(), f_val.clone())
@@ -75,20 +91,26 @@ fn transform_constant_adt<F: FnMut(ETy) -> VarId::Id>(
 }
 
 fn transform_operand_adt<F: FnMut(ETy) -> VarId::Id>(
+    type_defs: &TypeDecls,
     meta: &Meta,
     nst: &mut Vec<Statement>,
     op: &mut Operand,
     f: &mut F,
 ) {
     if let Operand::Const(ty, val) = op {
-        if let Some(var_id) = transform_constant_adt(meta, nst, ty, val, f) {
+        if let Some(var_id) = transform_constant_adt(type_defs, meta, nst, ty, val, f) {
             // Change the ADT constant operand to a move (of the extracted AST).
             *op = Operand::Move(Place::new(var_id));
         }
     }
 }
 
-pub fn transform(fmt_ctx: &CtxNames<'_>, funs: &mut FunDecls, globals: &mut GlobalDecls) {
+pub fn transform(
+    fmt_ctx: &CtxNames<'_>,
+    type_defs: &TypeDecls,
+    funs: &mut FunDecls,
+    globals: &mut GlobalDecls,
+) {
     for (name, b) in iter_function_bodies(funs).chain(iter_global_bodies(globals)) {
         trace!(
             "# About to regularize constant ADTs in function: {name}:\n{}",
@@ -97,7 +119,7 @@ pub fn transform(fmt_ctx: &CtxNames<'_>, funs: &mut FunDecls, globals: &mut Glob
 
         let mut f = make_locals_generator(&mut b.locals);
         body_transform_operands(&mut b.body, &mut |meta, nst, op| {
-            transform_operand_adt(meta, nst, op, &mut f)
+            transform_operand_adt(type_defs, meta, nst, op, &mut f)
         });
     }
 }
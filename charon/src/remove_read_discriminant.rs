@@ -30,7 +30,9 @@ fn transform_st(st: Statement) -> Statement {
         RawStatement::Drop(p) => RawStatement::Drop(p),
         RawStatement::Assert(assert) => RawStatement::Assert(assert),
         RawStatement::Call(call) => RawStatement::Call(call),
-        RawStatement::Panic => RawStatement::Panic,
+        RawStatement::Intrinsic(id, args) => RawStatement::Intrinsic(id, args),
+        RawStatement::InlineAsm(asm) => RawStatement::InlineAsm(asm),
+        RawStatement::Panic(info) => RawStatement::Panic(info),
         RawStatement::Return => RawStatement::Return,
         RawStatement::Break(i) => RawStatement::Break(i),
         RawStatement::Continue(i) => RawStatement::Continue(i),
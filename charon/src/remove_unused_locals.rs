@@ -1,12 +1,15 @@
 //! Remove the locals (which are not used for the input arguments) which are
 //! never used in the function bodies.  This is useful to remove the locals with
 //! type `Never`. We actually check that there are no such local variables
-//! remaining afterwards.
+//! remaining afterwards, at the exception of the destinations of the calls to
+//! diverging functions (those are never assigned).
 
 use crate::expressions::*;
 use crate::id_vector::ToUsize;
-use crate::llbc_ast::{CtxNames, FunDecls, GlobalDecls, RawStatement, Statement, Switch};
-use crate::ullbc_ast::{iter_function_bodies, iter_global_bodies, Var};
+use crate::llbc_ast::{
+    CtxNames, FunDecls, GlobalDecls, InlineAsmOperand, RawStatement, Statement, Switch,
+};
+use crate::ullbc_ast::{iter_function_bodies, iter_global_bodies, FnOperand, Var};
 use crate::values::*;
 use std::collections::{HashMap, HashSet};
 use std::iter::FromIterator;
@@ -14,6 +17,12 @@ use take_mut::take;
 
 fn compute_used_locals_in_place(locals: &mut HashSet<VarId::Id>, p: &Place) {
     locals.insert(p.var_id);
+    // The index projections also use local variables
+    for pe in p.projection.iter() {
+        if let ProjectionElem::Index(var_id) = pe {
+            locals.insert(*var_id);
+        }
+    }
 }
 
 fn compute_used_locals_in_operand(locals: &mut HashSet<VarId::Id>, op: &Operand) {
@@ -38,7 +47,10 @@ fn compute_used_locals_in_rvalue(locals: &mut HashSet<VarId::Id>, rv: &Rvalue) {
             compute_used_locals_in_operand(locals, op1);
             compute_used_locals_in_operand(locals, op2);
         }
-        Rvalue::Discriminant(p) => compute_used_locals_in_place(locals, p),
+        Rvalue::Discriminant(p) | Rvalue::Len(p) | Rvalue::RawPtr(p, _) => {
+            compute_used_locals_in_place(locals, p)
+        }
+        Rvalue::Repeat(op, _, _) => compute_used_locals_in_operand(locals, op),
         Rvalue::Global(_) => (),
         Rvalue::Aggregate(_, ops) => {
             compute_used_locals_in_operands(locals, ops);
@@ -58,10 +70,45 @@ fn compute_used_locals_in_statement(locals: &mut HashSet<VarId::Id>, st: &Statem
         RawStatement::Drop(p) => compute_used_locals_in_place(locals, p),
         RawStatement::Assert(assert) => compute_used_locals_in_operand(locals, &assert.cond),
         RawStatement::Call(call) => {
+            if let FnOperand::Move(p) = &call.func {
+                compute_used_locals_in_place(locals, p);
+            }
             compute_used_locals_in_operands(locals, &call.args);
             compute_used_locals_in_place(locals, &call.dest);
         }
-        RawStatement::Panic => (),
+        RawStatement::Intrinsic(_, args) => compute_used_locals_in_operands(locals, args),
+        RawStatement::InlineAsm(asm) => {
+            for op in &asm.operands {
+                match op {
+                    InlineAsmOperand::In { reg: _, value } => {
+                        compute_used_locals_in_operand(locals, value)
+                    }
+                    InlineAsmOperand::Out {
+                        reg: _,
+                        late: _,
+                        place,
+                    } => {
+                        if let Some(p) = place {
+                            compute_used_locals_in_place(locals, p)
+                        }
+                    }
+                    InlineAsmOperand::InOut {
+                        reg: _,
+                        late: _,
+                        in_value,
+                        out_place,
+                    } => {
+                        compute_used_locals_in_operand(locals, in_value);
+                        if let Some(p) = out_place {
+                            compute_used_locals_in_place(locals, p)
+                        }
+                    }
+                    InlineAsmOperand::Const(op) => compute_used_locals_in_operand(locals, op),
+                    InlineAsmOperand::Sym(_) => (),
+                }
+            }
+        }
+        RawStatement::Panic(_) => (),
         RawStatement::Break(_) => (),
         RawStatement::Continue(_) => (),
         RawStatement::Nop => (),
@@ -97,6 +144,11 @@ fn compute_used_locals_in_statement(locals: &mut HashSet<VarId::Id>, st: &Statem
 fn transform_place(vids_map: &HashMap<VarId::Id, VarId::Id>, mut p: Place) -> Place {
     let nvid = vids_map.get(&p.var_id).unwrap();
     p.var_id = *nvid;
+    for pe in p.projection.iter_mut() {
+        if let ProjectionElem::Index(var_id) = pe {
+            *var_id = *vids_map.get(var_id).unwrap();
+        }
+    }
     p
 }
 
@@ -126,6 +178,9 @@ fn transform_rvalue(vids_map: &HashMap<VarId::Id, VarId::Id>, rv: Rvalue) -> Rva
         }
         Rvalue::Global(gid) => Rvalue::Global(gid),
         Rvalue::Discriminant(p) => Rvalue::Discriminant(transform_place(vids_map, p)),
+        Rvalue::Len(p) => Rvalue::Len(transform_place(vids_map, p)),
+        Rvalue::Repeat(op, ty, len) => Rvalue::Repeat(transform_operand(vids_map, op), ty, len),
+        Rvalue::RawPtr(p, kind) => Rvalue::RawPtr(transform_place(vids_map, p), kind),
         Rvalue::Aggregate(kind, ops) => {
             let ops = transform_operands(vids_map, ops);
             Rvalue::Aggregate(kind, ops)
@@ -149,11 +204,46 @@ fn transform_st(vids_map: &HashMap<VarId::Id, VarId::Id>, st: Statement) -> Stat
             RawStatement::Assert(assert)
         }
         RawStatement::Call(mut call) => {
+            if let FnOperand::Move(p) = call.func {
+                call.func = FnOperand::Move(transform_place(vids_map, p));
+            }
             call.args = transform_operands(vids_map, call.args);
             call.dest = transform_place(vids_map, call.dest);
             RawStatement::Call(call)
         }
-        RawStatement::Panic => RawStatement::Panic,
+        RawStatement::Intrinsic(id, args) => {
+            RawStatement::Intrinsic(id, transform_operands(vids_map, args))
+        }
+        RawStatement::InlineAsm(mut asm) => {
+            asm.operands = Vec::from_iter(asm.operands.into_iter().map(|op| match op {
+                InlineAsmOperand::In { reg, value } => InlineAsmOperand::In {
+                    reg,
+                    value: transform_operand(vids_map, value),
+                },
+                InlineAsmOperand::Out { reg, late, place } => InlineAsmOperand::Out {
+                    reg,
+                    late,
+                    place: place.map(|p| transform_place(vids_map, p)),
+                },
+                InlineAsmOperand::InOut {
+                    reg,
+                    late,
+                    in_value,
+                    out_place,
+                } => InlineAsmOperand::InOut {
+                    reg,
+                    late,
+                    in_value: transform_operand(vids_map, in_value),
+                    out_place: out_place.map(|p| transform_place(vids_map, p)),
+                },
+                InlineAsmOperand::Const(op) => {
+                    InlineAsmOperand::Const(transform_operand(vids_map, op))
+                }
+                InlineAsmOperand::Sym(name) => InlineAsmOperand::Sym(name),
+            }));
+            RawStatement::InlineAsm(asm)
+        }
+        RawStatement::Panic(info) => RawStatement::Panic(info),
         RawStatement::Break(i) => RawStatement::Break(i),
         RawStatement::Continue(i) => RawStatement::Continue(i),
         RawStatement::Nop => RawStatement::Nop,
@@ -200,6 +290,26 @@ fn transform_st(vids_map: &HashMap<VarId::Id, VarId::Id>, st: Statement) -> Stat
     Statement::new(st.meta, st_raw)
 }
 
+/// Compute the set of locals used as destinations of function calls
+fn compute_call_dests_in_statement(dests: &mut HashSet<VarId::Id>, st: &Statement) {
+    match &st.content {
+        RawStatement::Call(call) => {
+            dests.insert(call.dest.var_id);
+        }
+        RawStatement::Switch(switch) => {
+            for st in switch.get_targets() {
+                compute_call_dests_in_statement(dests, st);
+            }
+        }
+        RawStatement::Loop(loop_body) => compute_call_dests_in_statement(dests, loop_body),
+        RawStatement::Sequence(st1, st2) => {
+            compute_call_dests_in_statement(dests, st1);
+            compute_call_dests_in_statement(dests, st2);
+        }
+        _ => (),
+    }
+}
+
 fn update_locals(
     num_inputs: usize,
     old_locals: VarId::Vector<Var>,
@@ -230,9 +340,14 @@ fn update_locals(
         }
     }
 
-    // Check there are no remaining variables with type `Never`
+    // Check there are no remaining variables with type `Never`, at the
+    // exception of the destinations of the diverging calls
+    let mut call_dests: HashSet<VarId::Id> = HashSet::new();
+    compute_call_dests_in_statement(&mut call_dests, st);
+    let call_dests: HashSet<VarId::Id> =
+        HashSet::from_iter(call_dests.iter().map(|vid| *vids_map.get(vid).unwrap()));
     for v in &locals {
-        assert!(!v.ty.contains_never());
+        assert!(!v.ty.contains_never() || call_dests.contains(&v.index));
     }
     (locals, vids_map)
 }
@@ -26,20 +26,33 @@ pub enum GDeclarationGroup<Id: Copy> {
 
 /// A (group of) top-level declaration(s), properly reordered.
 #[derive(Debug, VariantIndexArity, VariantName)]
-pub enum DeclarationGroup<TypeId: Copy, FunId: Copy, GlobalId: Copy> {
+pub enum DeclarationGroup<
+    TypeId: Copy,
+    FunId: Copy,
+    GlobalId: Copy,
+    TraitDeclId: Copy,
+    TraitImplId: Copy,
+> {
     /// A type declaration group
     Type(GDeclarationGroup<TypeId>),
     /// A function declaration group
     Fun(GDeclarationGroup<FunId>),
     /// A global declaration group
     Global(GDeclarationGroup<GlobalId>),
+    /// A trait declaration group
+    TraitDecl(GDeclarationGroup<TraitDeclId>),
+    /// A trait implementation group
+    TraitImpl(GDeclarationGroup<TraitImplId>),
 }
 
 #[derive(PartialEq, Eq, Hash, EnumIsA, EnumAsGetters, VariantName)]
-pub enum AnyDeclId<TypeId: Copy, FunId: Copy, GlobalId: Copy> {
+pub enum AnyDeclId<TypeId: Copy, FunId: Copy, GlobalId: Copy, TraitDeclId: Copy, TraitImplId: Copy>
+{
     Type(TypeId),
     Fun(FunId),
     Global(GlobalId),
+    TraitDecl(TraitDeclId),
+    TraitImpl(TraitImplId),
 }
 
 #[derive(Clone, Copy)]
@@ -50,17 +63,27 @@ pub struct DeclInfo {
 /// The top-level declarations in a module and their external dependencies.
 /// External declarations are recognizable with `DefId::is_local()`:
 /// See [rust_to_local_ids.rs].
-pub struct DeclarationsGroups<TypeId: Copy, FunId: Copy, GlobalId: Copy> {
+pub struct DeclarationsGroups<
+    TypeId: Copy,
+    FunId: Copy,
+    GlobalId: Copy,
+    TraitDeclId: Copy,
+    TraitImplId: Copy,
+> {
     /// The properly grouped and ordered declarations
-    pub decls: Vec<DeclarationGroup<TypeId, FunId, GlobalId>>,
+    pub decls: Vec<DeclarationGroup<TypeId, FunId, GlobalId, TraitDeclId, TraitImplId>>,
     /// All the type ids
     pub type_ids: Vec<TypeId>,
     /// All the function ids
     pub fun_ids: Vec<FunId>,
     /// All the global ids
     pub global_ids: Vec<GlobalId>,
+    /// All the trait declaration ids
+    pub trait_decl_ids: Vec<TraitDeclId>,
+    /// All the trait implementation ids
+    pub trait_impl_ids: Vec<TraitImplId>,
     /// Additional information on declarations
-    pub decls_info: HashMap<AnyDeclId<TypeId, FunId, GlobalId>, DeclInfo>,
+    pub decls_info: HashMap<AnyDeclId<TypeId, FunId, GlobalId, TraitDeclId, TraitImplId>, DeclInfo>,
 }
 
 /// We use the [Debug] trait instead of [Display] for the identifiers, because
@@ -110,22 +133,34 @@ impl<Id: Copy + Serialize> Serialize for GDeclarationGroup<Id> {
 
 /// We use the [Debug] trait instead of [Display] for the identifiers, because
 /// the rustc [DefId] doesn't implement [Display]...
-impl<TypeId: Copy + Debug, FunId: Copy + Debug, GlobalId: Copy + Debug> Display
-    for DeclarationGroup<TypeId, FunId, GlobalId>
+impl<
+        TypeId: Copy + Debug,
+        FunId: Copy + Debug,
+        GlobalId: Copy + Debug,
+        TraitDeclId: Copy + Debug,
+        TraitImplId: Copy + Debug,
+    > Display for DeclarationGroup<TypeId, FunId, GlobalId, TraitDeclId, TraitImplId>
 {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::result::Result<(), Error> {
         match self {
             DeclarationGroup::Type(decl) => write!(f, "{{ Type(s): {decl} }}"),
             DeclarationGroup::Fun(decl) => write!(f, "{{ Fun(s): {decl} }}"),
             DeclarationGroup::Global(decl) => write!(f, "{{ Global(s): {decl} }}"),
+            DeclarationGroup::TraitDecl(decl) => write!(f, "{{ Trait(s): {decl} }}"),
+            DeclarationGroup::TraitImpl(decl) => write!(f, "{{ Trait impl(s): {decl} }}"),
         }
     }
 }
 
 /// This is a bit annoying: because [DefId] and [Vec] doe't implement the
 /// [Serialize] trait, we can't automatically derive the serializing trait...
-impl<TypeId: Copy + Serialize, FunId: Copy + Serialize, GlobalId: Copy + Serialize> Serialize
-    for DeclarationGroup<TypeId, FunId, GlobalId>
+impl<
+        TypeId: Copy + Serialize,
+        FunId: Copy + Serialize,
+        GlobalId: Copy + Serialize,
+        TraitDeclId: Copy + Serialize,
+        TraitImplId: Copy + Serialize,
+    > Serialize for DeclarationGroup<TypeId, FunId, GlobalId, TraitDeclId, TraitImplId>
 {
     fn serialize<S>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error>
     where
@@ -151,24 +186,34 @@ impl<TypeId: Copy + Serialize, FunId: Copy + Serialize, GlobalId: Copy + Seriali
             DeclarationGroup::Global(decl) => {
                 vs.serialize_field(decl)?;
             }
+            DeclarationGroup::TraitDecl(decl) => {
+                vs.serialize_field(decl)?;
+            }
+            DeclarationGroup::TraitImpl(decl) => {
+                vs.serialize_field(decl)?;
+            }
         }
         vs.end()
     }
 }
 
-impl<TypeId: Copy, FunId: Copy, GlobalId: Copy> DeclarationsGroups<TypeId, FunId, GlobalId> {
+impl<TypeId: Copy, FunId: Copy, GlobalId: Copy, TraitDeclId: Copy, TraitImplId: Copy>
+    DeclarationsGroups<TypeId, FunId, GlobalId, TraitDeclId, TraitImplId>
+{
     #[allow(clippy::new_without_default)]
-    pub fn new() -> DeclarationsGroups<TypeId, FunId, GlobalId> {
+    pub fn new() -> DeclarationsGroups<TypeId, FunId, GlobalId, TraitDeclId, TraitImplId> {
         DeclarationsGroups {
             decls: vec![],
             type_ids: vec![],
             fun_ids: vec![],
             global_ids: vec![],
+            trait_decl_ids: vec![],
+            trait_impl_ids: vec![],
             decls_info: HashMap::new(),
         }
     }
 
-    fn push(&mut self, decl: DeclarationGroup<TypeId, FunId, GlobalId>) {
+    fn push(&mut self, decl: DeclarationGroup<TypeId, FunId, GlobalId, TraitDeclId, TraitImplId>) {
         match &decl {
             DeclarationGroup::Type(GDeclarationGroup::NonRec(id)) => {
                 self.type_ids.push(*id);
@@ -194,6 +239,22 @@ impl<TypeId: Copy, FunId: Copy, GlobalId: Copy> DeclarationsGroups<TypeId, FunId
                     self.global_ids.push(*id);
                 }
             }
+            DeclarationGroup::TraitDecl(GDeclarationGroup::NonRec(id)) => {
+                self.trait_decl_ids.push(*id);
+            }
+            DeclarationGroup::TraitDecl(GDeclarationGroup::Rec(ids)) => {
+                for id in ids {
+                    self.trait_decl_ids.push(*id);
+                }
+            }
+            DeclarationGroup::TraitImpl(GDeclarationGroup::NonRec(id)) => {
+                self.trait_impl_ids.push(*id);
+            }
+            DeclarationGroup::TraitImpl(GDeclarationGroup::Rec(ids)) => {
+                for id in ids {
+                    self.trait_impl_ids.push(*id);
+                }
+            }
         }
         self.decls.push(decl);
     }
@@ -201,26 +262,34 @@ impl<TypeId: Copy, FunId: Copy, GlobalId: Copy> DeclarationsGroups<TypeId, FunId
 
 /// We use the [Debug] trait instead of [Display] for the identifiers, because
 /// the rustc [DefId] doesn't implement [Display]...
-impl<TypeId: Copy + Debug, FunId: Copy + Debug, GlobalId: Copy + Debug> Display
-    for DeclarationsGroups<TypeId, FunId, GlobalId>
+impl<
+        TypeId: Copy + Debug,
+        FunId: Copy + Debug,
+        GlobalId: Copy + Debug,
+        TraitDeclId: Copy + Debug,
+        TraitImplId: Copy + Debug,
+    > Display for DeclarationsGroups<TypeId, FunId, GlobalId, TraitDeclId, TraitImplId>
 {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::result::Result<(), Error> {
         write!(
             f,
             "{}",
             vec_to_string(
-                &|d: &DeclarationGroup<TypeId, FunId, GlobalId>| d.to_string(),
+                &|d: &DeclarationGroup<TypeId, FunId, GlobalId, TraitDeclId, TraitImplId>| d
+                    .to_string(),
                 &self.decls,
             )
         )
     }
 }
 
-impl<'a, TypeId: Copy, FunId: Copy, GlobalId: Copy> std::iter::IntoIterator
-    for &'a DeclarationsGroups<TypeId, FunId, GlobalId>
+impl<'a, TypeId: Copy, FunId: Copy, GlobalId: Copy, TraitDeclId: Copy, TraitImplId: Copy>
+    std::iter::IntoIterator
+    for &'a DeclarationsGroups<TypeId, FunId, GlobalId, TraitDeclId, TraitImplId>
 {
-    type Item = &'a DeclarationGroup<TypeId, FunId, GlobalId>;
-    type IntoIter = std::slice::Iter<'a, DeclarationGroup<TypeId, FunId, GlobalId>>;
+    type Item = &'a DeclarationGroup<TypeId, FunId, GlobalId, TraitDeclId, TraitImplId>;
+    type IntoIter =
+        std::slice::Iter<'a, DeclarationGroup<TypeId, FunId, GlobalId, TraitDeclId, TraitImplId>>;
 
     #[inline]
     fn into_iter(self) -> Self::IntoIter {
@@ -230,7 +299,7 @@ impl<'a, TypeId: Copy, FunId: Copy, GlobalId: Copy> std::iter::IntoIterator
 
 pub fn reorder_declarations(
     decls: &RegisteredDeclarations,
-) -> Result<DeclarationsGroups<DefId, DefId, DefId>> {
+) -> Result<DeclarationsGroups<DefId, DefId, DefId, DefId, DefId>> {
     trace!();
 
     // Step 1: Start by building the graph
@@ -270,47 +339,47 @@ pub fn reorder_declarations(
     // Finally, generate the list of declarations
     let mut reordered_decls = DeclarationsGroups::new();
 
-    format!("PATCH: unsafe code comment");
     // Iterate over the SCC ids in the proper order
-    // for scc in reordered_sccs.iter() {
-    //     // Retrieve the SCC
-    //     
-    //     assert!(!scc.is_empty());
-
-    //     // Note that the length of an SCC should be at least 1.
-    //     let mut it = scc.iter();
-    //     let id0 = *it.next().unwrap();
-    //     let decl = &decls[&id0];
-
-    //     // The group should consist of only functions, only types or only one global.
-    //     for id in scc {
-    //         assert!(decls[id].kind == decl.kind);
-    //     }
-    //     if let DeclKind::Global = decl.kind {
-    //         assert!(scc.len() == 1);
-    //     }
-
-    //     // If an SCC has length one, the declaration may be simply recursive:
-    //     // we determine whether it is the case by checking if the def id is in
-    //     // its own set of dependencies.
-    //     let is_mutually_recursive = scc.len() > 1;
-    //     let is_simply_recursive =
-    //         !is_mutually_recursive && decl.deps.as_ref().is_some_and(|deps| deps.contains(&id0));
-
-    //     // Add the declaration.
-    //     // Note that we clone the vectors: it is not optimal, but they should
-    //     // be pretty small.
-    //     let group = if is_mutually_recursive || is_simply_recursive {
-    //         GDeclarationGroup::Rec(scc.clone())
-    //     } else {
-    //         GDeclarationGroup::NonRec(id0)
-    //     };
-    //     reordered_decls.push(match decl.kind {
-    //         DeclKind::Type => DeclarationGroup::Type(group),
-    //         DeclKind::Fun => DeclarationGroup::Fun(group),
-    //         DeclKind::Global => DeclarationGroup::Global(group),
-    //     });
-    // }
+    for scc in reordered_sccs.iter() {
+        assert!(!scc.is_empty());
+
+        // Note that the length of an SCC should be at least 1.
+        let mut it = scc.iter();
+        let id0 = *it.next().unwrap();
+        let decl = &decls[&id0];
+
+        // The group should consist of only functions, only types or only one
+        // global/trait declaration/trait implementation.
+        for id in scc {
+            assert!(decls[id].kind == decl.kind);
+        }
+        if let DeclKind::Global | DeclKind::TraitDecl | DeclKind::TraitImpl = decl.kind {
+            assert!(scc.len() == 1);
+        }
+
+        // If an SCC has length one, the declaration may be simply recursive:
+        // we determine whether it is the case by checking if the def id is in
+        // its own set of dependencies.
+        let is_mutually_recursive = scc.len() > 1;
+        let is_simply_recursive =
+            !is_mutually_recursive && decl.deps.as_ref().is_some_and(|deps| deps.contains(&id0));
+
+        // Add the declaration.
+        // Note that we clone the vectors: it is not optimal, but they should
+        // be pretty small.
+        let group = if is_mutually_recursive || is_simply_recursive {
+            GDeclarationGroup::Rec(scc.clone())
+        } else {
+            GDeclarationGroup::NonRec(id0)
+        };
+        reordered_decls.push(match decl.kind {
+            DeclKind::Type => DeclarationGroup::Type(group),
+            DeclKind::Fun => DeclarationGroup::Fun(group),
+            DeclKind::Global => DeclarationGroup::Global(group),
+            DeclKind::TraitDecl => DeclarationGroup::TraitDecl(group),
+            DeclKind::TraitImpl => DeclarationGroup::TraitImpl(group),
+        });
+    }
 
     trace!("{}", reordered_decls.to_string());
 
@@ -323,6 +392,8 @@ pub fn reorder_declarations(
                     DeclKind::Type => AnyDeclId::Type(*id),
                     DeclKind::Fun => AnyDeclId::Fun(*id),
                     DeclKind::Global => AnyDeclId::Global(*id),
+                    DeclKind::TraitDecl => AnyDeclId::TraitDecl(*id),
+                    DeclKind::TraitImpl => AnyDeclId::TraitImpl(*id),
                 },
                 DeclInfo {
                     is_transparent: decl.is_transparent(),
@@ -6,18 +6,31 @@ use crate::types::TypeDeclId;
 use crate::ullbc_ast as ast;
 use crate::ullbc_ast::FunDeclId;
 use crate::ullbc_ast::GlobalDeclId;
+use crate::ullbc_ast::{TraitDeclId, TraitImplId};
 use rustc_hir::def_id::DefId;
+use rustc_middle::ty::TyCtxt;
 use std::collections::HashMap;
 use std::vec::Vec;
 
 pub type GDeclarationGroup<Id> = rd::GDeclarationGroup<Id>;
 pub type TypeDeclarationGroup = rd::GDeclarationGroup<ty::TypeDeclId::Id>;
 pub type FunDeclarationGroup = rd::GDeclarationGroup<ast::FunDeclId::Id>;
-pub type DeclarationGroup =
-    rd::DeclarationGroup<ty::TypeDeclId::Id, ast::FunDeclId::Id, ast::GlobalDeclId::Id>;
+pub type DeclarationGroup = rd::DeclarationGroup<
+    ty::TypeDeclId::Id,
+    ast::FunDeclId::Id,
+    ast::GlobalDeclId::Id,
+    ast::TraitDeclId::Id,
+    ast::TraitImplId::Id,
+>;
 
-pub type AnyDeclRid = rd::AnyDeclId<DefId, DefId, DefId>;
-pub type AnyDeclId = rd::AnyDeclId<ty::TypeDeclId::Id, ast::FunDeclId::Id, ast::GlobalDeclId::Id>;
+pub type AnyDeclRid = rd::AnyDeclId<DefId, DefId, DefId, DefId, DefId>;
+pub type AnyDeclId = rd::AnyDeclId<
+    ty::TypeDeclId::Id,
+    ast::FunDeclId::Id,
+    ast::GlobalDeclId::Id,
+    ast::TraitDeclId::Id,
+    ast::TraitImplId::Id,
+>;
 
 #[derive(Clone, Copy)]
 /// Information common to any top-level declaration.
@@ -58,6 +71,28 @@ fn add_function_info(
     let info = *src.get(&AnyDeclRid::Fun(rid)).unwrap();
     dst.insert(AnyDeclId::Fun(id), DeclInfo::new(rid, info));
 }
+/// Closures have an additional declaration, which doesn't appear in the
+/// registered declarations: the type of their state (i.e., of the captured
+/// variables). We insert it just before the group containing the closure.
+fn add_closure_state_types(
+    tcx: TyCtxt,
+    type_counter: &mut ty::TypeDeclId::Generator,
+    type_rid_to_id: &mut HashMap<DefId, ty::TypeDeclId::Id>,
+    src: &HashMap<AnyDeclRid, rd::DeclInfo>,
+    dst: &mut HashMap<AnyDeclId, DeclInfo>,
+    decls: &mut Vec<DeclarationGroup>,
+    rids: &[DefId],
+) {
+    for rid in rids {
+        if tcx.is_closure(*rid) {
+            let id = type_counter.fresh_id();
+            type_rid_to_id.insert(*rid, id);
+            let info = *src.get(&AnyDeclRid::Fun(*rid)).unwrap();
+            dst.insert(AnyDeclId::Type(id), DeclInfo::new(*rid, info));
+            decls.push(DeclarationGroup::Type(GDeclarationGroup::NonRec(id)));
+        }
+    }
+}
 fn add_global_info(
     src: &HashMap<AnyDeclRid, rd::DeclInfo>,
     dst: &mut HashMap<AnyDeclId, DeclInfo>,
@@ -67,6 +102,24 @@ fn add_global_info(
     let info = *src.get(&AnyDeclRid::Global(rid)).unwrap();
     dst.insert(AnyDeclId::Global(id), DeclInfo::new(rid, info));
 }
+fn add_trait_decl_info(
+    src: &HashMap<AnyDeclRid, rd::DeclInfo>,
+    dst: &mut HashMap<AnyDeclId, DeclInfo>,
+    rid: DefId,
+    id: TraitDeclId::Id,
+) {
+    let info = *src.get(&AnyDeclRid::TraitDecl(rid)).unwrap();
+    dst.insert(AnyDeclId::TraitDecl(id), DeclInfo::new(rid, info));
+}
+fn add_trait_impl_info(
+    src: &HashMap<AnyDeclRid, rd::DeclInfo>,
+    dst: &mut HashMap<AnyDeclId, DeclInfo>,
+    rid: DefId,
+    id: TraitImplId::Id,
+) {
+    let info = *src.get(&AnyDeclRid::TraitImpl(rid)).unwrap();
+    dst.insert(AnyDeclId::TraitImpl(id), DeclInfo::new(rid, info));
+}
 
 pub struct OrderedDecls {
     /// The ordered files
@@ -82,22 +135,29 @@ pub struct OrderedDecls {
     pub type_rid_to_id: HashMap<DefId, ty::TypeDeclId::Id>,
     pub fun_rid_to_id: HashMap<DefId, ast::FunDeclId::Id>,
     pub global_rid_to_id: HashMap<DefId, ast::GlobalDeclId::Id>,
+    pub trait_decl_rid_to_id: HashMap<DefId, ast::TraitDeclId::Id>,
+    pub trait_impl_rid_to_id: HashMap<DefId, ast::TraitImplId::Id>,
 }
 
 /// Convert the definition ids used by the rust compiler to our own definition ids.
 pub fn rust_to_local_ids(
+    tcx: TyCtxt,
     files_info: &HashMap<FileName, FileInfo>,
-    reordered: &rd::DeclarationsGroups<DefId, DefId, DefId>,
+    reordered: &rd::DeclarationsGroups<DefId, DefId, DefId, DefId, DefId>,
 ) -> OrderedDecls {
     let mut decls_info = HashMap::new();
 
     let mut type_rid_to_id: HashMap<DefId, ty::TypeDeclId::Id> = HashMap::new();
     let mut fun_rid_to_id: HashMap<DefId, ast::FunDeclId::Id> = HashMap::new();
     let mut global_rid_to_id: HashMap<DefId, ast::GlobalDeclId::Id> = HashMap::new();
+    let mut trait_decl_rid_to_id: HashMap<DefId, ast::TraitDeclId::Id> = HashMap::new();
+    let mut trait_impl_rid_to_id: HashMap<DefId, ast::TraitImplId::Id> = HashMap::new();
 
     let mut type_counter = ty::TypeDeclId::Generator::new();
     let mut fun_counter = ast::FunDeclId::Generator::new();
     let mut global_counter = ast::GlobalDeclId::Generator::new();
+    let mut trait_decl_counter = ast::TraitDeclId::Generator::new();
+    let mut trait_impl_counter = ast::TraitImplId::Generator::new();
 
     let mut decls: Vec<DeclarationGroup> = Vec::new();
 
@@ -122,12 +182,30 @@ pub fn rust_to_local_ids(
                 decls.push(DeclarationGroup::Type(GDeclarationGroup::Rec(ids)));
             }
             rd::DeclarationGroup::Fun(rd::GDeclarationGroup::NonRec(rid)) => {
+                add_closure_state_types(
+                    tcx,
+                    &mut type_counter,
+                    &mut type_rid_to_id,
+                    &reordered.decls_info,
+                    &mut decls_info,
+                    &mut decls,
+                    &[*rid],
+                );
                 let id = fun_counter.fresh_id();
                 fun_rid_to_id.insert(*rid, id);
                 add_function_info(&reordered.decls_info, &mut decls_info, *rid, id);
                 decls.push(DeclarationGroup::Fun(GDeclarationGroup::NonRec(id)));
             }
             rd::DeclarationGroup::Fun(rd::GDeclarationGroup::Rec(rids)) => {
+                add_closure_state_types(
+                    tcx,
+                    &mut type_counter,
+                    &mut type_rid_to_id,
+                    &reordered.decls_info,
+                    &mut decls_info,
+                    &mut decls,
+                    rids,
+                );
                 let mut ids: Vec<ast::FunDeclId::Id> = Vec::new();
                 for rid in rids {
                     let id = fun_counter.fresh_id();
@@ -153,6 +231,38 @@ pub fn rust_to_local_ids(
                 }
                 decls.push(DeclarationGroup::Global(GDeclarationGroup::Rec(ids)));
             }
+            rd::DeclarationGroup::TraitDecl(rd::GDeclarationGroup::NonRec(rid)) => {
+                let id = trait_decl_counter.fresh_id();
+                trait_decl_rid_to_id.insert(*rid, id);
+                add_trait_decl_info(&reordered.decls_info, &mut decls_info, *rid, id);
+                decls.push(DeclarationGroup::TraitDecl(GDeclarationGroup::NonRec(id)));
+            }
+            rd::DeclarationGroup::TraitDecl(rd::GDeclarationGroup::Rec(rids)) => {
+                let mut ids: Vec<ast::TraitDeclId::Id> = Vec::new();
+                for rid in rids {
+                    let id = trait_decl_counter.fresh_id();
+                    trait_decl_rid_to_id.insert(*rid, id);
+                    add_trait_decl_info(&reordered.decls_info, &mut decls_info, *rid, id);
+                    ids.push(id);
+                }
+                decls.push(DeclarationGroup::TraitDecl(GDeclarationGroup::Rec(ids)));
+            }
+            rd::DeclarationGroup::TraitImpl(rd::GDeclarationGroup::NonRec(rid)) => {
+                let id = trait_impl_counter.fresh_id();
+                trait_impl_rid_to_id.insert(*rid, id);
+                add_trait_impl_info(&reordered.decls_info, &mut decls_info, *rid, id);
+                decls.push(DeclarationGroup::TraitImpl(GDeclarationGroup::NonRec(id)));
+            }
+            rd::DeclarationGroup::TraitImpl(rd::GDeclarationGroup::Rec(rids)) => {
+                let mut ids: Vec<ast::TraitImplId::Id> = Vec::new();
+                for rid in rids {
+                    let id = trait_impl_counter.fresh_id();
+                    trait_impl_rid_to_id.insert(*rid, id);
+                    add_trait_impl_info(&reordered.decls_info, &mut decls_info, *rid, id);
+                    ids.push(id);
+                }
+                decls.push(DeclarationGroup::TraitImpl(GDeclarationGroup::Rec(ids)));
+            }
         }
     }
 
@@ -184,5 +294,7 @@ pub fn rust_to_local_ids(
         type_rid_to_id,
         fun_rid_to_id,
         global_rid_to_id,
+        trait_decl_rid_to_id,
+        trait_impl_rid_to_id,
     }
 }
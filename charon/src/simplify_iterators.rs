@@ -0,0 +1,114 @@
+//! The `for` loops are desugared to a call to `IntoIterator::into_iter`, which
+//! builds an iterator, followed by a loop which calls `Iterator::next` until
+//! it returns `None`. For instance, `for i in 0..n { ... }` gives:
+//! ```text
+//! range := core::ops::range::Range { start: 0, end: copy n };
+//! iter := core::iter::traits::collect::IntoIterator::into_iter<Range<u32>>(move range);
+//! loop {
+//!   next := core::iter::traits::iterator::Iterator::next<Range<u32>>(&mut iter);
+//!   match next {
+//!     None => break,
+//!     Some => {
+//!       i := copy ((next as Some).0);
+//!       ...
+//!     }
+//!   }
+//! }
+//! ```
+//! When iterating over ranges and slices, we translate those calls to
+//! primitive functions (see [crate::assumed]). The calls to `into_iter` are
+//! however not interesting: applied to an iterator (a range or a slice iterator)
+//! `into_iter` is the identity, and applied to a borrowed slice it is the same
+//! as `[T]::iter` (or `[T]::iter_mut`). This pass simplifies them: in the
+//! example above, we get:
+//! ```text
+//! iter := move range;
+//! ```
+
+use take_mut::take;
+
+use crate::expressions::*;
+use crate::llbc_ast::{CtxNames, FunDecls, GlobalDecls, RawStatement, Statement, Switch};
+use crate::types::*;
+use crate::ullbc_ast::{iter_function_bodies, iter_global_bodies, AssumedFunId, FnOperand, FunId};
+use std::iter::FromIterator;
+
+fn transform_st(mut st: Statement) -> Statement {
+    st.content = match st.content {
+        RawStatement::Assign(p, rv) => RawStatement::Assign(p, rv),
+        RawStatement::FakeRead(p) => RawStatement::FakeRead(p),
+        RawStatement::SetDiscriminant(p, vid) => RawStatement::SetDiscriminant(p, vid),
+        RawStatement::Drop(p) => RawStatement::Drop(p),
+        RawStatement::Assert(assert) => RawStatement::Assert(assert),
+        RawStatement::Call(mut call) => {
+            if let FnOperand::Regular(FunId::Assumed(AssumedFunId::IntoIter)) = &call.func {
+                assert!(call.type_args.len() == 1);
+                assert!(call.args.len() == 1);
+                match &call.type_args[0] {
+                    Ty::Ref(_, ty, kind) => {
+                        // A borrowed slice
+                        let aid = match kind {
+                            RefKind::Shared => AssumedFunId::SliceIter,
+                            RefKind::Mut => AssumedFunId::SliceIterMut,
+                        };
+                        let elem_ty = ty.as_slice().as_ref().clone();
+                        call.func = FnOperand::Regular(FunId::Assumed(aid));
+                        call.type_args = vec![elem_ty];
+                        RawStatement::Call(call)
+                    }
+                    _ => {
+                        // An iterator
+                        let rv = Rvalue::Use(call.args.pop().unwrap());
+                        RawStatement::Assign(call.dest, rv)
+                    }
+                }
+            } else {
+                RawStatement::Call(call)
+            }
+        }
+        RawStatement::Intrinsic(id, args) => RawStatement::Intrinsic(id, args),
+        RawStatement::InlineAsm(asm) => RawStatement::InlineAsm(asm),
+        RawStatement::Panic(info) => RawStatement::Panic(info),
+        RawStatement::Return => RawStatement::Return,
+        RawStatement::Break(i) => RawStatement::Break(i),
+        RawStatement::Continue(i) => RawStatement::Continue(i),
+        RawStatement::Nop => RawStatement::Nop,
+        RawStatement::Switch(switch) => {
+            let switch = match switch {
+                Switch::If(op, st1, st2) => Switch::If(
+                    op,
+                    Box::new(transform_st(*st1)),
+                    Box::new(transform_st(*st2)),
+                ),
+                Switch::SwitchInt(op, int_ty, targets, mut otherwise) => {
+                    let targets =
+                        Vec::from_iter(targets.into_iter().map(|(v, e)| (v, transform_st(e))));
+                    *otherwise = transform_st(*otherwise);
+                    Switch::SwitchInt(op, int_ty, targets, otherwise)
+                }
+                Switch::Match(_, _, _) => {
+                    // This variant is introduced in a subsequent pass
+                    unreachable!();
+                }
+            };
+            RawStatement::Switch(switch)
+        }
+        RawStatement::Loop(loop_body) => RawStatement::Loop(Box::new(transform_st(*loop_body))),
+        RawStatement::Sequence(st1, st2) => {
+            RawStatement::Sequence(Box::new(transform_st(*st1)), Box::new(transform_st(*st2)))
+        }
+    };
+
+    st
+}
+
+/// `fmt_ctx` is used for pretty-printing purposes.
+pub fn transform(fmt_ctx: &CtxNames<'_>, funs: &mut FunDecls, globals: &mut GlobalDecls) {
+    for (name, b) in iter_function_bodies(funs).chain(iter_global_bodies(globals)) {
+        trace!(
+            "# About to simplify the iterators in decl: {name}\n{}",
+            b.fmt_with_ctx_names(fmt_ctx)
+        );
+        take(&mut b.body, transform_st);
+    }
+}
@@ -17,7 +17,7 @@ use take_mut::take;
 
 use crate::expressions::*;
 use crate::llbc_ast::{
-    new_sequence, Assert, CtxNames, FunDecls, GlobalDecls, RawStatement, Statement, Switch,
+    new_sequence, Assert, CtxNames, FunDecls, GlobalDecls, RawStatement, Statement, Switch, Var,
 };
 use crate::meta::combine_meta;
 use crate::types::*;
@@ -81,11 +81,11 @@ fn binop_requires_assert_after(binop: BinOp) -> bool {
 
 /// Return true if the unary operation has a precondition (negating the number
 /// won't lead to an overflow, for instance).
-fn unop_requires_assert_before(unop: UnOp) -> bool {
+fn unop_requires_assert_before(unop: &UnOp) -> bool {
     match unop {
         UnOp::Not => false,
         UnOp::Neg => true,
-        UnOp::Cast(_, _) => {
+        UnOp::Cast(_) => {
             // This case is peculiar, because rustc doesn't insert assertions
             // while it can actually fail
             false
@@ -93,11 +93,11 @@ fn unop_requires_assert_before(unop: UnOp) -> bool {
     }
 }
 
-fn unop_can_fail(unop: UnOp) -> bool {
+fn unop_can_fail(unop: &UnOp) -> bool {
     match unop {
         UnOp::Not => false,
         UnOp::Neg => true,
-        UnOp::Cast(_, _) => {
+        UnOp::Cast(_) => {
             // See [unop_requires_assert_before]
             false
         }
@@ -130,18 +130,36 @@ fn binop_can_fail(binop: BinOp) -> bool {
     binop_requires_assert_after(binop) || binop_requires_assert_before(binop)
 }
 
+/// Return true if the operand is a floating-point number.
+///
+/// Operations on floating-point numbers can't fail (they return NaN or
+/// infinities instead), and rustc doesn't introduce dynamic checks for them:
+/// there is nothing to simplify, and we must leave those operations unchanged.
+///
+/// Note that MIR always stores the operands of the unops and binops in
+/// temporaries, unless they are constants: we don't need to type projections.
+fn operand_is_float(locals: &VarId::Vector<Var>, op: &Operand) -> bool {
+    match op {
+        Operand::Copy(p) | Operand::Move(p) => {
+            p.projection.is_empty() && locals.get(p.var_id).unwrap().ty.is_float()
+        }
+        Operand::Const(ty, _) => ty.is_float(),
+    }
+}
+
 /// Check if this is a group of statements of the form: "check that we can do
 /// a unary operation, then do this operation (ex.: check that negating a number
 /// won't lead to an overflow)", unless we compile for release mode.
 fn check_if_assert_then_unop<R>(
     release: bool,
+    locals: &VarId::Vector<Var>,
     st1: &Statement<R>,
     st2: &Statement<R>,
     st3: &Statement<R>,
 ) -> bool {
     match &st3.content {
-        RawStatement::Assign(_, Rvalue::UnaryOp(unop, _)) => {
-            if unop_requires_assert_before(*unop) {
+        RawStatement::Assign(_, Rvalue::UnaryOp(unop, op)) => {
+            if unop_requires_assert_before(unop) && !operand_is_float(locals, op) {
                 // We found a unary op with a precondition
                 //
                 // This group of statements should exactly match the following pattern:
@@ -193,6 +211,7 @@ fn check_if_simplifiable_assert_then_unop<R>(
             RawStatement::Assert(Assert {
                 cond: Operand::Move(cond_op),
                 expected,
+                kind: _,
             }),
             RawStatement::Assign(_mp, Rvalue::UnaryOp(unop, op1)),
         ) => {
@@ -272,13 +291,14 @@ fn simplify_assert_then_unop<R>(_st1: Statement<R>, _st2: Statement<R>, st3: Sta
 /// Simply check if the first statements is a checked binop.
 fn check_if_binop_then_assert<R>(
     release: bool,
+    locals: &VarId::Vector<Var>,
     st1: &Statement<R>,
     st2: &Statement<R>,
     st3: &Statement<R>,
 ) -> bool {
     match &st1.content {
-        RawStatement::Assign(_, Rvalue::BinaryOp(binop, _, _)) => {
-            if binop_requires_assert_after(*binop) {
+        RawStatement::Assign(_, Rvalue::BinaryOp(binop, op, _)) => {
+            if binop_requires_assert_after(*binop) && !operand_is_float(locals, op) {
                 // We found a checked binary op.
                 //
                 // This group of statements should exactly match the following pattern:
@@ -318,6 +338,7 @@ fn check_if_simplifiable_binop_then_assert<R>(
             RawStatement::Assert(Assert {
                 cond: Operand::Move(cond_op),
                 expected,
+                kind: _,
             }),
             RawStatement::Assign(_mp, Rvalue::Use(Operand::Move(mr))),
         ) => {
@@ -381,13 +402,14 @@ fn simplify_binop_then_assert<R>(st1: Statement<R>, st2: Statement<R>, st3: Stat
 /// non zero before doing a division, panic otherwise)"
 fn check_if_assert_then_binop<R>(
     release: bool,
+    locals: &VarId::Vector<Var>,
     st1: &Statement<R>,
     st2: &Statement<R>,
     st3: &Statement<R>,
 ) -> bool {
     match &st3.content {
-        RawStatement::Assign(_, Rvalue::BinaryOp(binop, _, _)) => {
-            if binop_requires_assert_before(*binop) {
+        RawStatement::Assign(_, Rvalue::BinaryOp(binop, op, _)) => {
+            if binop_requires_assert_before(*binop) && !operand_is_float(locals, op) {
                 // We found an unchecked binop which should be simplified (division
                 // or remainder computation).
                 //
@@ -449,6 +471,7 @@ fn check_if_simplifiable_assert_then_binop<R>(
             RawStatement::Assert(Assert {
                 cond: Operand::Move(cond_op),
                 expected,
+                kind: _,
             }),
             RawStatement::Assign(_mp, Rvalue::BinaryOp(binop, _dividend, Operand::Move(divisor))),
         ) => {
@@ -479,6 +502,7 @@ fn check_if_simplifiable_assert_then_binop<R>(
             RawStatement::Assert(Assert {
                 cond: Operand::Move(cond_op),
                 expected,
+                kind: _,
             }),
             RawStatement::Assign(_mp, Rvalue::BinaryOp(binop, _dividend, divisor1)),
         ) => {
@@ -541,26 +565,138 @@ fn simplify_assert_then_binop<R>(_st1: Statement<R>, _st2: Statement<R>, st3: St
     st3
 }
 
+/// Return true if the statement is a copy of a reference which we can move
+/// before the checked binop `tmp := op1 + op2` (see [find_ref_copy_in_binop_then_assert]).
+fn is_movable_ref_copy<R>(
+    locals: &VarId::Vector<Var>,
+    tmp: VarId::Id,
+    op1: &Operand,
+    op2: &Operand,
+    st: &Statement<R>,
+) -> bool {
+    match &st.content {
+        RawStatement::Assign(p, Rvalue::Use(Operand::Copy(q))) => {
+            // The copy must not read the result of the binop, and the
+            // binop must not read the variable we assign
+            let reads = |op: &Operand| match op {
+                Operand::Copy(op_p) | Operand::Move(op_p) => op_p.var_id == p.var_id,
+                Operand::Const(_, _) => false,
+            };
+            p.projection.is_empty()
+                && locals.get(p.var_id).unwrap().ty.is_ref()
+                && p.var_id != tmp
+                && q.var_id != tmp
+                && !reads(op1)
+                && !reads(op2)
+        }
+        _ => false,
+    }
+}
+
+/// In optimized MIR, the statements of a checked binop are sometimes
+/// interleaved with copies of references, which rustc introduces to
+/// dereference places (see `CopyForDeref`). This happens when the
+/// destination of the binop, or the operands of the assertion message,
+/// are dereferences: typically, in the closures which mutably borrow their
+/// state. For instance:
+///   ```text
+///   tmp := copy x + copy y;
+///   ref1 := copy ((*(state)).count); // For the assertion message
+///   assert(move (tmp.1) == false);
+///   ref2 := copy ((*(state)).count);
+///   *(ref2) := move (tmp.0);
+///   ```
+/// Those copies have no side effects and don't depend on the binop: we can
+/// move them before the binop, so that we can then collapse the binop and the
+/// assertion (see [check_if_binop_then_assert]).
+///
+/// Return the index of the statement to move (`2` for `st2` or `3` for `st3`),
+/// if there is.
+fn find_ref_copy_in_binop_then_assert<R>(
+    release: bool,
+    locals: &VarId::Vector<Var>,
+    st1: &Statement<R>,
+    st2: &Statement<R>,
+    st3: &Statement<R>,
+) -> Option<usize> {
+    match &st1.content {
+        RawStatement::Assign(bp, Rvalue::BinaryOp(binop, op1, op2))
+            if !release
+                && binop_requires_assert_after(*binop)
+                && !operand_is_float(locals, op1) =>
+        {
+            if is_movable_ref_copy(locals, bp.var_id, op1, op2, st2) {
+                return Option::Some(2);
+            }
+            match &st2.content {
+                RawStatement::Assert(Assert {
+                    cond: Operand::Move(cond_op),
+                    ..
+                }) if cond_op.var_id == bp.var_id
+                    && is_movable_ref_copy(locals, bp.var_id, op1, op2, st3) =>
+                {
+                    Option::Some(3)
+                }
+                _ => Option::None,
+            }
+        }
+        _ => Option::None,
+    }
+}
+
+/// Move the copy of reference identified by [find_ref_copy_in_binop_then_assert]
+/// (`i` is the index of the statement to move) before the binop, then simplify
+/// the resulting sequence.
+///
+/// We do this in a separate function to limit the size of the stack frame
+/// of [simplify_st_seq] (see the comments for [simplify_non_seq_st]).
+fn move_ref_copy_then_simplify<R>(
+    release: bool,
+    locals: &VarId::Vector<Var>,
+    i: usize,
+    st1: Statement<R>,
+    st2: Statement<R>,
+    st3: Statement<R>,
+    st4: Option<Statement<R>>,
+) -> Statement<R> {
+    let (moved_st, st2) = if i == 2 { (st2, st3) } else { (st3, st2) };
+    let next_st = match st4 {
+        Option::Some(st4) => new_sequence(st2, st4),
+        Option::None => st2,
+    };
+    let next_st = new_sequence(st1, next_st);
+    new_sequence(
+        simplify_st(release, locals, moved_st),
+        simplify_st(release, locals, next_st),
+    )
+}
+
 /// Attempt to simplify a sequence of statemnets
 fn simplify_st_seq<R>(
     release: bool,
+    locals: &VarId::Vector<Var>,
     st1: Statement<R>,
     st2: Statement<R>,
     st3: Statement<R>,
     st4: Option<Statement<R>>,
 ) -> Statement<R> {
+    // Move the copies of references interleaved with the checked binops
+    if let Option::Some(i) = find_ref_copy_in_binop_then_assert(release, locals, &st1, &st2, &st3) {
+        return move_ref_copy_then_simplify(release, locals, i, st1, st2, st3, st4);
+    }
+
     // Try to simplify
     let simpl_st = {
         // Simplify checked unops (negation)
-        if check_if_assert_then_unop(release, &st1, &st2, &st3) {
+        if check_if_assert_then_unop(release, locals, &st1, &st2, &st3) {
             simplify_assert_then_unop(st1, st2, st3)
         }
         // Simplify checked binops
-        else if check_if_binop_then_assert(release, &st1, &st2, &st3) {
+        else if check_if_binop_then_assert(release, locals, &st1, &st2, &st3) {
             simplify_binop_then_assert(st1, st2, st3)
         }
         // Simplify unchecked binops (division, modulo)
-        else if check_if_assert_then_binop(release, &st1, &st2, &st3) {
+        else if check_if_assert_then_binop(release, locals, &st1, &st2, &st3) {
             simplify_assert_then_binop(st1, st2, st3)
         } else {
             // Not simplifyable
@@ -569,31 +705,45 @@ fn simplify_st_seq<R>(
                 Option::None => st3,
             };
             let next_st = new_sequence(st2, next_st);
-            return new_sequence(simplify_st(release, st1), simplify_st(release, next_st));
+            return new_sequence(
+                simplify_st(release, locals, st1),
+                simplify_st(release, locals, next_st),
+            );
         }
     };
 
     // Combine the simplified statements with the statement after, if there is
     match st4 {
         Option::Some(st4) => {
-            let st4 = simplify_st(release, st4);
+            let st4 = simplify_st(release, locals, st4);
             new_sequence(simpl_st, st4)
         }
         Option::None => simpl_st,
     }
 }
 
-// TODO: don't consume `st`, use mutable borrows
-fn simplify_st(release: bool, st: Statement) -> Statement {
-    let content = match st.content {
+/// Simplify a statement which is not a sequence.
+///
+/// We explore the sequences by recursively calling [simplify_st] and
+/// [simplify_st_seq], so the stack depth is proportional to the length of
+/// the sequences. We isolate the other cases in this function to keep the
+/// stack frame of [simplify_st] small: otherwise, we overflow the stack on
+/// long function bodies in debug mode.
+fn simplify_non_seq_st(
+    release: bool,
+    locals: &VarId::Vector<Var>,
+    st: RawStatement,
+) -> RawStatement {
+    match st {
         RawStatement::Assign(p, rv) => {
             // Check that we never failed to simplify a binop
             match &rv {
-                Rvalue::BinaryOp(binop, _, divisor) => {
+                Rvalue::BinaryOp(binop, op, divisor) => {
                     // If it is an unsimplified binop, it must be / or %
                     // and the divisor must be a non-zero constant integer,
-                    // unless we compile for release
-                    if binop_can_fail(*binop) {
+                    // unless we compile for release or the operands are
+                    // floating-point numbers
+                    if binop_can_fail(*binop) && !operand_is_float(locals, op) {
                         match binop {
                             BinOp::Div | BinOp::Rem => {
                                 let (_, cv) = divisor.as_const();
@@ -617,7 +767,8 @@ fn simplify_st(release: bool, st: Statement) -> Statement {
                     //   - either we compile for release
                     //   - or the value must be a constant integer which won't
                     //     lead to overflow.
-                    if unop_can_fail(*unop) {
+                    // Note that negating a floating-point number can't fail.
+                    if unop_can_fail(unop) && !operand_is_float(locals, v) {
                         match unop {
                             UnOp::Neg => {
                                 if release {
@@ -645,7 +796,9 @@ fn simplify_st(release: bool, st: Statement) -> Statement {
         RawStatement::Drop(p) => RawStatement::Drop(p),
         RawStatement::Assert(assert) => RawStatement::Assert(assert),
         RawStatement::Call(call) => RawStatement::Call(call),
-        RawStatement::Panic => RawStatement::Panic,
+        RawStatement::Intrinsic(id, args) => RawStatement::Intrinsic(id, args),
+        RawStatement::InlineAsm(asm) => RawStatement::InlineAsm(asm),
+        RawStatement::Panic(info) => RawStatement::Panic(info),
         RawStatement::Return => RawStatement::Return,
         RawStatement::Break(i) => RawStatement::Break(i),
         RawStatement::Continue(i) => RawStatement::Continue(i),
@@ -654,16 +807,16 @@ fn simplify_st(release: bool, st: Statement) -> Statement {
             let switch = match switch {
                 Switch::If(op, st1, st2) => Switch::If(
                     op,
-                    Box::new(simplify_st(release, *st1)),
-                    Box::new(simplify_st(release, *st2)),
+                    Box::new(simplify_st(release, locals, *st1)),
+                    Box::new(simplify_st(release, locals, *st2)),
                 ),
                 Switch::SwitchInt(op, int_ty, targets, mut otherwise) => {
                     let targets = Vec::from_iter(
                         targets
                             .into_iter()
-                            .map(|(v, e)| (v, simplify_st(release, e))),
+                            .map(|(v, e)| (v, simplify_st(release, locals, e))),
                     );
-                    *otherwise = simplify_st(release, *otherwise);
+                    *otherwise = simplify_st(release, locals, *otherwise);
                     Switch::SwitchInt(op, int_ty, targets, otherwise)
                 }
                 Switch::Match(_, _, _) => {
@@ -674,17 +827,25 @@ fn simplify_st(release: bool, st: Statement) -> Statement {
             RawStatement::Switch(switch)
         }
         RawStatement::Loop(loop_body) => {
-            RawStatement::Loop(Box::new(simplify_st(release, *loop_body)))
+            RawStatement::Loop(Box::new(simplify_st(release, locals, *loop_body)))
         }
+        RawStatement::Sequence(_, _) => unreachable!(),
+    }
+}
+
+// TODO: don't consume `st`, use mutable borrows
+fn simplify_st(release: bool, locals: &VarId::Vector<Var>, st: Statement) -> Statement {
+    let content = match st.content {
         RawStatement::Sequence(st1, st2) => match st2.content {
             RawStatement::Sequence(st2, st3) => match st3.content {
                 RawStatement::Sequence(st3, st4) => {
-                    simplify_st_seq(release, *st1, *st2, *st3, Option::Some(*st4)).content
+                    simplify_st_seq(release, locals, *st1, *st2, *st3, Option::Some(*st4)).content
                 }
                 st3_raw => {
                     // Below: the fact that we moved the value is very annoying
                     simplify_st_seq(
                         release,
+                        locals,
                         *st1,
                         *st2,
                         Statement::new(st3.meta, st3_raw),
@@ -694,11 +855,12 @@ fn simplify_st(release: bool, st: Statement) -> Statement {
                 }
             },
             st2_raw => RawStatement::Sequence(
-                Box::new(simplify_st(release, *st1)),
+                Box::new(simplify_st(release, locals, *st1)),
                 // Below: the fact that we moved the value is very annoying
-                Box::new(simplify_st(release, Statement::new(st2.meta, st2_raw))),
+                Box::new(simplify_st(release, locals, Statement::new(st2.meta, st2_raw))),
             ),
         },
+        content => simplify_non_seq_st(release, locals, content),
     };
 
     Statement::new(st.meta, content)
@@ -716,6 +878,7 @@ pub fn simplify(
             "# About to simplify operands in decl: {name}:\n{}",
             b.fmt_with_ctx_names(fmt_ctx)
         );
-        take(&mut b.body, |b| simplify_st(release, b));
+        let locals = &b.locals;
+        take(&mut b.body, |b| simplify_st(release, locals, b));
     }
 }
@@ -10,7 +10,8 @@ use crate::expressions as e;
 use crate::formatter::Formatter;
 use crate::generics;
 use crate::get_mir::{
-    boxes_are_desugared, extract_constants_at_top_level, get_mir_for_def_id_and_level, MirLevel,
+    boxes_are_desugared, extract_constants_at_top_level, get_mir_for_def_id,
+    get_mir_level_for_def_id, MirLevel,
 };
 use crate::id_vector;
 use crate::meta;
@@ -28,14 +29,17 @@ use crate::values as v;
 use hashlink::linked_hash_map::LinkedHashMap;
 use im::Vector;
 use log::warn;
-use rustc_hir::def_id::{DefId, LocalDefId};
+use rustc_ast::{InlineAsmOptions, InlineAsmTemplatePiece};
+use rustc_hir::def::{CtorOf, DefKind};
+use rustc_hir::def_id::DefId;
 use rustc_middle::mir;
 use rustc_middle::mir::{
     BasicBlock, Body, Operand, Place, PlaceElem, Statement, StatementKind, Terminator,
     TerminatorKind, START_BLOCK,
 };
 use rustc_middle::ty as mir_ty;
-use rustc_middle::ty::{ConstKind, Ty, TyCtxt, TyKind};
+use rustc_middle::ty::subst::SubstsRef;
+use rustc_middle::ty::{AdtDef, ConstKind, Ty, TyCtxt, TyKind};
 use rustc_session::Session;
 use rustc_span::Span;
 use std::collections::HashMap;
@@ -60,6 +64,10 @@ pub struct DeclTransContext<'tcx, 'ctx> {
     pub global_defs: &'ctx ast::GlobalDecls,
     /// The level at which to extract the MIR
     pub mir_level: MirLevel,
+    /// If `true`, translate the cleanup blocks and the unwind edges leading
+    /// to them (`--unwind`). Otherwise, we ignore them: the state gets stuck
+    /// upon panicking.
+    pub unwind: bool,
 }
 
 /// A translation context for function and global bodies.
@@ -95,6 +103,12 @@ struct BodyTransContext<'tcx, 'ctx, 'ctx1> {
     /// Redundant with `rtype_vars_to_ids`. We need this for [translate_types::translate_ety].
     /// This maps type variables to types with erased regions.
     rtype_vars_to_etypes: im::OrdMap<u32, ty::ETy>,
+    /// Id counter for the const generic variables
+    const_generic_vars_counter: ty::ConstGenericVarId::Generator,
+    /// The const generic variables
+    const_generic_vars: ty::ConstGenericVarId::Vector<ty::ConstGenericVar>,
+    /// The map from rust const generic variables to translate const generics.
+    rconst_generic_vars_to_cgs: im::OrdMap<u32, ty::ConstGeneric>,
     /// Id counter for the variables
     vars_counter: v::VarId::Generator,
     /// The "regular" variables
@@ -111,6 +125,21 @@ struct BodyTransContext<'tcx, 'ctx, 'ctx1> {
     /// Note that when translating terminators like DropAndReplace, we might have
     /// to introduce new blocks which don't appear in the original MIR.
     rblocks_to_ids: im::OrdMap<BasicBlock, ast::BlockId::Id>,
+    /// The locals containing the discriminant of a fieldless enumeration
+    /// which is read only to cast the enumeration to an integer, mapped to
+    /// the place of the enumeration. MIR decomposes the cast `e as i32` into
+    /// a discriminant read followed by a cast of the discriminant: we merge
+    /// them back (see [e::CastKind::EnumDiscriminant]).
+    discriminant_casts: im::OrdMap<mir::Local, Place<'tcx>>,
+    /// All the locals containing the discriminant of an enumeration, mapped
+    /// to the place of the enumeration. We use it to convert the values
+    /// of the switches over those discriminants to variant indices (see
+    /// [translate_switch_targets]).
+    discriminant_reads: im::OrdMap<mir::Local, Place<'tcx>>,
+    /// The boolean locals used by the `assume` hints that rustc introduces
+    /// when casting enumerations. We ignore those hints (the other `assume`
+    /// are translated to [ast::RawStatement::Intrinsic]).
+    assume_locals: im::OrdSet<mir::Local>,
 }
 
 impl<'tcx, 'ctx> DeclTransContext<'tcx, 'ctx> {
@@ -140,12 +169,18 @@ impl<'tcx, 'ctx, 'ctx1> BodyTransContext<'tcx, 'ctx, 'ctx1> {
             rtype_vars_to_ids: im::OrdMap::new(),
             rtype_vars_to_rtypes: im::OrdMap::new(),
             rtype_vars_to_etypes: im::OrdMap::new(),
+            const_generic_vars_counter: ty::ConstGenericVarId::Generator::new(),
+            const_generic_vars: ty::ConstGenericVarId::Vector::new(),
+            rconst_generic_vars_to_cgs: im::OrdMap::new(),
             vars_counter: v::VarId::Generator::new(),
             vars: v::VarId::Vector::new(),
             rvars_to_ids: im::OrdMap::new(),
             blocks_counter: ast::BlockId::Generator::new(),
             blocks: im::OrdMap::new(),
             rblocks_to_ids: im::OrdMap::new(),
+            discriminant_casts: im::OrdMap::new(),
+            discriminant_reads: im::OrdMap::new(),
+            assume_locals: im::OrdSet::new(),
         }
     }
 
@@ -199,6 +234,26 @@ impl<'tcx, 'ctx, 'ctx1> BodyTransContext<'tcx, 'ctx, 'ctx1> {
         var_id
     }
 
+    fn push_const_generic_var(
+        &mut self,
+        rindex: u32,
+        ty: ty::IntegerTy,
+        name: String,
+    ) -> ty::ConstGenericVarId::Id {
+        use crate::id_vector::ToUsize;
+        let var_id = self.const_generic_vars_counter.fresh_id();
+        assert!(var_id.to_usize() == self.const_generic_vars.len());
+        let var = ty::ConstGenericVar {
+            index: var_id,
+            name,
+            ty,
+        };
+        self.const_generic_vars.insert(var_id, var);
+        self.rconst_generic_vars_to_cgs
+            .insert(rindex, ty::ConstGeneric::Var(var_id));
+        var_id
+    }
+
     fn push_var(&mut self, rid: u32, ty: ty::ETy, name: Option<String>) {
         use crate::id_vector::ToUsize;
         let var_id = self.vars_counter.fresh_id();
@@ -290,20 +345,30 @@ impl<'tcx, 'ctx, 'ctx1> Formatter<&ty::Ty<ty::ErasedRegion>>
     }
 }
 
-fn translate_ety(bt_ctx: &BodyTransContext<'_, '_, '_>, ty: &mir_ty::Ty) -> Result<ty::ETy> {
+fn translate_ety<'tcx>(
+    bt_ctx: &BodyTransContext<'tcx, '_, '_>,
+    ty: &mir_ty::Ty<'tcx>,
+) -> Result<ty::ETy> {
     let ty_ctx = TypeTransContext::new(bt_ctx.ft_ctx.type_defs, bt_ctx.ft_ctx.ordered);
-    translate_types::translate_ety(bt_ctx.ft_ctx.tcx, &ty_ctx, &bt_ctx.rtype_vars_to_etypes, ty)
+    translate_types::translate_ety(
+        bt_ctx.ft_ctx.tcx,
+        &ty_ctx,
+        &bt_ctx.rtype_vars_to_etypes,
+        &bt_ctx.rconst_generic_vars_to_cgs,
+        ty,
+    )
 }
 
-fn translate_ety_kind(
-    bt_ctx: &BodyTransContext<'_, '_, '_>,
-    ty: &mir_ty::TyKind,
+fn translate_ety_kind<'tcx>(
+    bt_ctx: &BodyTransContext<'tcx, '_, '_>,
+    ty: &mir_ty::TyKind<'tcx>,
 ) -> Result<ty::ETy> {
     let ty_ctx = TypeTransContext::new(bt_ctx.ft_ctx.type_defs, bt_ctx.ft_ctx.ordered);
     translate_types::translate_ety_kind(
         bt_ctx.ft_ctx.tcx,
         &ty_ctx,
         &bt_ctx.rtype_vars_to_etypes,
+        &bt_ctx.rconst_generic_vars_to_cgs,
         ty,
     )
 }
@@ -318,10 +383,22 @@ fn translate_sig_ty<'tcx>(
         &ty_ctx,
         &bt_ctx.rregions_to_ids,
         &bt_ctx.rtype_vars_to_rtypes,
+        &bt_ctx.rconst_generic_vars_to_cgs,
         ty,
     )
 }
 
+fn translate_const_generic<'tcx>(
+    bt_ctx: &BodyTransContext<'tcx, '_, '_>,
+    c: &mir_ty::Const<'tcx>,
+) -> Result<ty::ConstGeneric> {
+    translate_types::translate_const_generic(
+        bt_ctx.ft_ctx.tcx,
+        &bt_ctx.rconst_generic_vars_to_cgs,
+        c,
+    )
+}
+
 /// Translate a function's local variables by adding them in the environment.
 fn translate_body_locals<'tcx>(
     bt_ctx: &mut BodyTransContext<'tcx, '_, '_>,
@@ -424,16 +501,19 @@ fn translate_place_with_type<'tcx, 'ctx>(
 ) -> (e::Place, ty::ETy) {
     let var_id = bt_ctx.get_local(&place.local).unwrap();
     let var = bt_ctx.get_var_from_id(var_id).unwrap();
-    let (projection, ty) = translate_projection(
-        bt_ctx.ft_ctx.mir_level,
-        bt_ctx.ft_ctx.type_defs,
-        var.ty.clone(),
-        place.projection,
-    );
+    let (projection, ty) = translate_projection(bt_ctx, var.ty.clone(), place.projection);
 
     (e::Place { var_id, projection }, ty)
 }
 
+/// Return the type of the elements of an array or a slice type.
+fn get_array_or_slice_elem_ty(ty: &ty::ETy) -> ty::ETy {
+    match ty {
+        ty::Ty::Array(elem_ty, _) | ty::Ty::Slice(elem_ty) => (**elem_ty).clone(),
+        _ => unreachable!("Expected an array or a slice, got: {:?}", ty),
+    }
+}
+
 /// Translate a place
 fn translate_place<'tcx, 'ctx>(
     bt_ctx: &'ctx BodyTransContext<'tcx, 'ctx, '_>,
@@ -450,15 +530,15 @@ fn translate_place<'tcx, 'ctx>(
 /// the two kinds of dereferences.
 ///
 /// We return the translated projection, and its type.
-///
-/// - `mir_level`: used for sanity checks
 fn translate_projection(
-    mir_level: MirLevel,
-    type_defs: &ty::TypeDecls,
+    bt_ctx: &BodyTransContext<'_, '_, '_>,
     var_ty: ty::ETy,
     rprojection: &rustc_middle::ty::List<PlaceElem<'_>>,
 ) -> (e::Projection, ty::ETy) {
     trace!("- projection: {:?}\n- var_ty: {:?}", rprojection, var_ty);
+    // The MIR level is used for sanity checks
+    let mir_level = bt_ctx.ft_ctx.mir_level;
+    let type_defs = bt_ctx.ft_ctx.type_defs;
 
     // We need to track the type of the value we look at, while exploring the path.
     // This is important to disambiguate, for instance, dereferencement operations.
@@ -481,7 +561,7 @@ fn translate_projection(
                         path_type = ty.deref().clone();
                         projection.push_back(e::ProjectionElem::Deref);
                     }
-                    ty::Ty::Adt(ty::TypeId::Assumed(ty::AssumedTy::Box), regions, tys) => {
+                    ty::Ty::Adt(ty::TypeId::Assumed(ty::AssumedTy::Box), regions, tys, _) => {
                         // This case only happens in some MIR levels
                         assert!(!boxes_are_desugared(mir_level));
                         assert!(regions.is_empty());
@@ -503,7 +583,7 @@ fn translate_projection(
                 // Update the path type and generate the proj kind at the
                 // same time.
                 let proj_elem = match path_type {
-                    ty::Ty::Adt(ty::TypeId::Adt(type_id), _regions, tys) => {
+                    ty::Ty::Adt(ty::TypeId::Adt(type_id), _regions, tys, cgs) => {
                         let type_def = type_defs.get_type_def(type_id).unwrap();
 
                         // If (and only if) the ADT is an enumeration, we should
@@ -514,20 +594,25 @@ fn translate_projection(
                         path_type = type_def.get_erased_regions_instantiated_field_type(
                             downcast_id,
                             &tys,
+                            &cgs,
                             field_id,
                         );
 
-                        let proj_kind = e::FieldProjKind::Adt(type_id, downcast_id);
+                        let proj_kind = if type_def.kind.is_union() {
+                            e::FieldProjKind::Union(type_id)
+                        } else {
+                            e::FieldProjKind::Adt(type_id, downcast_id)
+                        };
                         e::ProjectionElem::Field(proj_kind, field_id)
                     }
-                    ty::Ty::Adt(ty::TypeId::Tuple, regions, tys) => {
+                    ty::Ty::Adt(ty::TypeId::Tuple, regions, tys, _) => {
                         assert!(regions.is_empty());
                         assert!(downcast_id.is_none());
                         path_type = tys.get(field.as_usize()).unwrap().clone();
                         let proj_kind = e::FieldProjKind::Tuple(tys.len());
                         e::ProjectionElem::Field(proj_kind, field_id)
                     }
-                    ty::Ty::Adt(ty::TypeId::Assumed(ty::AssumedTy::Option), regions, tys) => {
+                    ty::Ty::Adt(ty::TypeId::Assumed(ty::AssumedTy::Option), regions, tys, _) => {
                         assert!(regions.is_empty());
                         assert!(tys.len() == 1);
                         assert!(downcast_id.is_some());
@@ -539,7 +624,7 @@ fn translate_projection(
                         let proj_kind = e::FieldProjKind::Option(variant_id);
                         e::ProjectionElem::Field(proj_kind, field_id)
                     }
-                    ty::Ty::Adt(ty::TypeId::Assumed(aty), regions, tys)
+                    ty::Ty::Adt(ty::TypeId::Assumed(aty), regions, tys, _)
                         if aty == ty::AssumedTy::Box
                             || aty == ty::AssumedTy::PtrUnique
                             || aty == ty::AssumedTy::PtrNonNull =>
@@ -571,6 +656,7 @@ fn translate_projection(
                                     ty::TypeId::Assumed(ty::AssumedTy::PtrUnique),
                                     im::vector![],
                                     im::vector![type_param],
+                                    Box::new(im::vector![]),
                                 )
                             }
                             ty::AssumedTy::PtrUnique => {
@@ -579,6 +665,7 @@ fn translate_projection(
                                     ty::TypeId::Assumed(ty::AssumedTy::PtrNonNull),
                                     im::vector![],
                                     im::vector![type_param],
+                                    Box::new(im::vector![]),
                                 )
                             }
                             ty::AssumedTy::PtrNonNull => {
@@ -599,22 +686,53 @@ fn translate_projection(
                 projection.push_back(proj_elem);
                 downcast_id = None;
             }
-            mir::ProjectionElem::Index(_local) => {
-                unimplemented!();
+            mir::ProjectionElem::Index(local) => {
+                // Indexing with a local variable: `a[i]`
+                assert!(downcast_id.is_none());
+                let var_id = bt_ctx.get_local(&local).unwrap();
+                path_type = get_array_or_slice_elem_ty(&path_type);
+                projection.push_back(e::ProjectionElem::Index(var_id));
             }
             mir::ProjectionElem::ConstantIndex {
-                offset: _,
-                min_length: _,
-                from_end: _,
+                offset,
+                min_length,
+                from_end,
             } => {
-                unimplemented!();
+                // Indexing with a constant. This comes from the slice patterns,
+                // for instance `first` in `[first, .., last]`.
+                assert!(downcast_id.is_none());
+                path_type = get_array_or_slice_elem_ty(&path_type);
+                projection.push_back(e::ProjectionElem::ConstantIndex {
+                    offset,
+                    min_length,
+                    from_end,
+                });
             }
-            mir::ProjectionElem::Subslice {
-                from: _,
-                to: _,
-                from_end: _,
-            } => {
-                unimplemented!();
+            mir::ProjectionElem::Subslice { from, to, from_end } => {
+                // A sub-slice or sub-array. This comes from the slice patterns,
+                // for instance `rest` in `[first, rest @ ..]`.
+                assert!(downcast_id.is_none());
+                path_type = match path_type {
+                    ty::Ty::Slice(_) => {
+                        assert!(from_end);
+                        path_type
+                    }
+                    ty::Ty::Array(elem_ty, len) => {
+                        // For arrays, `to` is an index from the beginning
+                        // of the array if `from_end` is false
+                        let len = len.as_value().as_uint().unwrap();
+                        let sub_len = if from_end {
+                            len - (from as u128) - (to as u128)
+                        } else {
+                            (to as u128) - (from as u128)
+                        };
+                        let sub_len =
+                            v::ScalarValue::from_uint(ty::IntegerTy::Usize, sub_len).unwrap();
+                        ty::Ty::Array(elem_ty, ty::ConstGeneric::Value(sub_len))
+                    }
+                    _ => unreachable!("Unexpected type: {:?}", path_type),
+                };
+                projection.push_back(e::ProjectionElem::Subslice { from, to, from_end });
             }
             mir::ProjectionElem::OpaqueCast(_) => {
                 unimplemented!();
@@ -666,12 +784,17 @@ fn translate_constant_scalar_type(ty: &TyKind, decls: &DeclTransContext<'_, '_>)
             // variant, and this variant doesn't take parameters.
             // Retrieve the definition.
             let id = decls.ordered.type_rid_to_id.get(&adt_def.did()).unwrap();
-            ty::Ty::Adt(ty::TypeId::Adt(*id), Vector::new(), Vector::new())
+            ty::Ty::Adt(
+                ty::TypeId::Adt(*id),
+                Vector::new(),
+                Vector::new(),
+                Box::new(Vector::new()),
+            )
         }
         TyKind::Tuple(substs) => {
             // There can be tuple([]) for unit
             assert!(substs.is_empty());
-            ty::Ty::Adt(ty::TypeId::Tuple, Vector::new(), Vector::new())
+            ty::Ty::mk_unit()
         }
         // Only accept scalars that are shared references with erased regions : it's a static.
         TyKind::Ref(region, ref_ty, mir::Mutability::Not) => match region.kind() {
@@ -682,11 +805,7 @@ fn translate_constant_scalar_type(ty: &TyKind, decls: &DeclTransContext<'_, '_>)
             ),
             _ => unreachable!(),
         },
-        TyKind::Float(_) => {
-            // We don't support floating point numbers:
-            // this should have been detected and eliminated before.
-            unreachable!();
-        }
+        TyKind::Float(float_ty) => ty::Ty::Float(ty::FloatTy::rust_float_ty_to_float_ty(*float_ty)),
         _ => {
             // The remaining types should not be used for constants, or
             // should have been filtered by the caller.
@@ -709,7 +828,12 @@ fn translate_constant_reference_type<'tcx>(
             let type_params = translate_subst_in_body(bt_ctx, substs).unwrap();
             trace!("{:?}", type_params);
             let field_tys = type_params.into_iter().collect();
-            ty::Ty::Adt(ty::TypeId::Tuple, Vector::new(), field_tys)
+            ty::Ty::Adt(
+                ty::TypeId::Tuple,
+                Vector::new(),
+                field_tys,
+                Box::new(Vector::new()),
+            )
         }
         TyKind::Adt(_, _) => {
             // Following tests, it seems rustc doesn't introduce constants
@@ -727,7 +851,8 @@ fn translate_constant_reference_type<'tcx>(
     }
 }
 
-/// Translate a typed constant value (either a bool, a char or an integer).
+/// Translate a typed constant value (either a bool, a char, an integer or a
+/// floating-point number).
 fn translate_constant_integer_like_value(
     ty: &ty::ETy,
     scalar: &mir::interpret::Scalar,
@@ -763,6 +888,12 @@ fn translate_constant_integer_like_value(
             ty::IntegerTy::I128 => v::ScalarValue::I128(scalar.to_i128().unwrap()),
             ty::IntegerTy::U128 => v::ScalarValue::U128(scalar.to_u128().unwrap()),
         }),
+        // We retrieve the bit patterns of the floating-point numbers, so that
+        // the translation is lossless
+        ty::Ty::Float(f) => v::PrimitiveValue::Float(match f {
+            ty::FloatTy::F32 => v::FloatValue::F32(scalar.to_u32().unwrap()),
+            ty::FloatTy::F64 => v::FloatValue::F64(scalar.to_u64().unwrap()),
+        }),
         _ => {
             // The remaining types should not be used for constants,
             // or should have been filtered by the caller.
@@ -788,13 +919,14 @@ fn translate_constant_scalar_value(
     // degenerate ADT or tuple (if an ADT has only one variant and no fields,
     // it is a constant, and unit is encoded by MIR as a 0-tuple).
     match llbc_ty {
-        ty::Ty::Bool | ty::Ty::Char | ty::Ty::Integer(_) => {
+        ty::Ty::Bool | ty::Ty::Char | ty::Ty::Integer(_) | ty::Ty::Float(_) => {
             let v = translate_constant_integer_like_value(llbc_ty, scalar);
             e::OperandConstantValue::PrimitiveValue(v)
         }
-        ty::Ty::Adt(ty::TypeId::Adt(id), region_tys, field_tys) => {
+        ty::Ty::Adt(ty::TypeId::Adt(id), region_tys, field_tys, cgs) => {
             assert!(region_tys.is_empty());
             assert!(field_tys.is_empty());
+            assert!(cgs.is_empty());
 
             let def = decls.type_defs.get_type_def(*id).unwrap();
 
@@ -807,13 +939,16 @@ fn translate_constant_scalar_value(
                     Option::Some(ty::VariantId::ZERO)
                 }
                 ty::TypeDeclKind::Struct(_) => Option::None,
+                ty::TypeDeclKind::Union(_) => {
+                    unimplemented!("Constant values of union types are not supported")
+                }
                 ty::TypeDeclKind::Opaque => {
                     unreachable!("Can't analyze a constant value built from an opaque type")
                 }
             };
             e::OperandConstantValue::Adt(variant_id, Vec::new())
         }
-        ty::Ty::Adt(ty::TypeId::Tuple, region_tys, field_tys) => {
+        ty::Ty::Adt(ty::TypeId::Tuple, region_tys, field_tys, _) => {
             assert!(region_tys.is_empty());
             assert!(field_tys.is_empty());
             e::OperandConstantValue::Adt(Option::None, Vec::new())
@@ -824,6 +959,16 @@ fn translate_constant_scalar_value(
                     let id = decls.ordered.global_rid_to_id.get(&s).unwrap();
                     e::OperandConstantValue::StaticId(*id)
                 }
+                mir::interpret::GlobalAlloc::Memory(alloc) => {
+                    // This happens with the byte strings (`b"abc"`), which
+                    // have type `&[u8; N]`
+                    let (_, offset) = p.into_parts();
+                    let alloc = alloc.inner();
+                    let bytes = alloc.inspect_with_uninit_and_ptr_outside_interpreter(
+                        offset.bytes_usize()..alloc.len(),
+                    );
+                    translate_constant_bytes(llbc_ty, bytes)
+                }
                 _ => unreachable!(
                     "Expected static pointer, got {:?}",
                     tcx.global_alloc(p.provenance)
@@ -838,8 +983,27 @@ fn translate_constant_scalar_value(
     }
 }
 
+/// Translate the content of a string literal or of a byte string, given the
+/// type of the reference to it (`&str`, `&[u8; N]` or `&[u8]`).
+fn translate_constant_bytes(llbc_ty: &ty::ETy, bytes: &[u8]) -> e::OperandConstantValue {
+    let v = match llbc_ty {
+        ty::Ty::Ref(_, ty, ty::RefKind::Shared) => match ty.as_ref() {
+            ty::Ty::Str => v::PrimitiveValue::String(String::from_utf8(bytes.to_vec()).unwrap()),
+            ty::Ty::Array(elem_ty, _) | ty::Ty::Slice(elem_ty)
+                if matches!(elem_ty.as_ref(), ty::Ty::Integer(ty::IntegerTy::U8)) =>
+            {
+                v::PrimitiveValue::ByteStr(bytes.to_vec())
+            }
+            _ => unimplemented!("Unsupported constant reference: {:?}", llbc_ty),
+        },
+        _ => unreachable!("Expected a shared reference, got {:?}", llbc_ty),
+    };
+    e::OperandConstantValue::PrimitiveValue(v)
+}
+
 /// Translate a constant typed by [translate_constant_reference_type].
-/// This should always be a tuple.
+/// This should be a tuple or an ADT (for instance, a structure containing a
+/// string literal in optimized MIR).
 fn translate_constant_reference_value<'tcx>(
     bt_ctx: &BodyTransContext<'tcx, '_, '_>,
     llbc_ty: &ty::ETy,
@@ -866,8 +1030,13 @@ fn translate_constant_reference_value<'tcx>(
         .unwrap();
     trace!("{:?}", dc);
 
-    // Iterate over the fields
-    assert!(dc.variant.is_none());
+    // Retrieve the variant, if the value is an enumeration
+    let variant_id = match mir_ty.kind() {
+        TyKind::Adt(adt_def, _) if adt_def.is_enum() => {
+            Option::Some(translate_variant_id(dc.variant.unwrap()))
+        }
+        _ => Option::None,
+    };
 
     // Below: we are mutually recursive with [translate_constant_kind],
     // which takes a [ConstantKind] as input (see `cvalue` above), but it should be
@@ -880,15 +1049,16 @@ fn translate_constant_reference_value<'tcx>(
 
     // Sanity check
     match llbc_ty {
-        ty::Ty::Adt(ty::TypeId::Tuple, regions, fields_tys) => {
+        ty::Ty::Adt(ty::TypeId::Tuple, regions, fields_tys, _) => {
             assert!(regions.is_empty());
             assert!(zip(&fields, fields_tys).all(|(f, ty)| &f.0 == ty));
         }
-        _ => unreachable!("Expected a tuple, got {:?}", mir_ty),
+        ty::Ty::Adt(ty::TypeId::Adt(_), _, _, _) => (),
+        _ => unreachable!("Expected a tuple or an ADT, got {:?}", mir_ty),
     };
 
     let fields: Vec<e::OperandConstantValue> = fields.into_iter().map(|f| f.1).collect();
-    e::OperandConstantValue::Adt(Option::None, fields)
+    e::OperandConstantValue::Adt(variant_id, fields)
 }
 
 /// Translate a [mir::interpret::ConstValue]
@@ -906,7 +1076,11 @@ fn translate_const_value<'tcx>(
         mir::interpret::ConstValue::ByRef { .. } => {
             translate_constant_reference_value(bt_ctx, llbc_ty, mir_ty, val)
         }
-        mir::interpret::ConstValue::Slice { .. } => unimplemented!(),
+        mir::interpret::ConstValue::Slice { .. } => {
+            // String literals (`&str`) and byte slices (`&[u8]`)
+            let bytes = mir::interpret::get_slice_bytes(&bt_ctx.ft_ctx.tcx, *val);
+            translate_constant_bytes(llbc_ty, bytes)
+        }
         mir::interpret::ConstValue::ZeroSized { .. } => {
             // Should be unit
             assert!(llbc_ty.is_unit());
@@ -970,6 +1144,15 @@ fn translate_constant_kind<'tcx>(
 ) -> (ty::ETy, e::OperandConstantValue) {
     trace!("{:?}", constant);
 
+    // Function items used as values (for instance, given as arguments to
+    // other functions): the constant is zero-sized, and all the information
+    // is in the type.
+    if let TyKind::FnDef(def_id, substs) = constant.ty().kind() {
+        let ty = translate_ety(bt_ctx, &constant.ty()).unwrap();
+        let fn_ptr = translate_fn_ptr(bt_ctx, *def_id, substs).unwrap();
+        return (ty, e::OperandConstantValue::FnPtr(fn_ptr));
+    }
+
     match constant {
         // This is the "normal" constant case
         // TODO: this changed when we updated from Nightly 2022-01-29 to
@@ -1020,6 +1203,114 @@ fn translate_constant_kind<'tcx>(
     }
 }
 
+/// Translate a function item used as a value.
+///
+/// The function item is either a function (which we resolve if it is a trait
+/// method, in the same manner as for function calls), or the constructor of
+/// a tuple structure or of an enumeration variant (ex.: `Some`).
+fn translate_fn_ptr<'tcx>(
+    bt_ctx: &BodyTransContext<'tcx, '_, '_>,
+    def_id: DefId,
+    substs: SubstsRef<'tcx>,
+) -> Result<e::FnPtr> {
+    let tcx = bt_ctx.ft_ctx.tcx;
+
+    // Constructors
+    if let DefKind::Ctor(ctor_of, _) = tcx.def_kind(def_id) {
+        // Retrieve the ADT from the output type of the constructor
+        let sig = tcx.bound_fn_sig(def_id).subst(tcx, substs);
+        let (adt_def, adt_substs) = match sig.skip_binder().output().kind() {
+            TyKind::Adt(adt_def, adt_substs) => (*adt_def, *adt_substs),
+            _ => unreachable!(),
+        };
+        let adt_id = adt_def.did();
+        let variant_idx = adt_def.variant_index_with_ctor_id(def_id);
+        let (region_params, mut type_params, cg_params) =
+            translate_subst_generic_args_in_body(bt_ctx, None, adt_substs)?;
+
+        let akind = if let Some(id_t) = bt_ctx.ft_ctx.ordered.type_rid_to_id.get(&adt_id) {
+            let id_t = *id_t;
+            let variant_id = match ctor_of {
+                CtorOf::Variant => Some(translate_variant_id(variant_idx)),
+                CtorOf::Struct => None,
+            };
+            e::AggregateKind::Adt(id_t, variant_id, region_params, type_params, cg_params)
+        } else {
+            // Primitive ADT: we only support `Option` (see the translation
+            // of the aggregated values)
+            let name = type_def_id_to_name(tcx, adt_id);
+            assert!(name.equals_ref_name(&assumed::OPTION_NAME));
+            assert!(region_params.is_empty());
+            assert!(type_params.len() == 1);
+            assert!(cg_params.is_empty());
+            e::AggregateKind::Option(
+                translate_variant_id(variant_idx),
+                type_params.pop().unwrap(),
+            )
+        };
+        return Ok(e::FnPtr::Ctor(akind));
+    }
+
+    // Primitive functions
+    if let Option::Some(aid) = get_primitive_function_id(tcx, def_id, substs) {
+        let name = function_def_id_to_name(tcx, def_id);
+        let used = assumed::function_to_info(&name).unwrap();
+        let (region_args, type_args, cg_args) = translate_subst_generic_args_in_body(
+            bt_ctx,
+            Option::Some(used.used_type_params),
+            substs,
+        )?;
+        return Ok(e::FnPtr::Fun(
+            ast::FunId::Assumed(aid),
+            region_args,
+            type_args,
+            cg_args,
+        ));
+    }
+
+    // Regular functions and trait methods. Note that we don't have the span
+    // of the operand: we report the errors at the span of the body owner (the
+    // resolution is also performed, with the precise span, when registering
+    // the declarations).
+    let sess = bt_ctx.ft_ctx.sess;
+    let span = tcx.def_span(bt_ctx.def_id);
+    let resolved = resolve_function_call(sess, tcx, span, bt_ctx.def_id, def_id, substs)?;
+    let (func, substs) = match resolved {
+        ResolvedCall::Fun(def_id, substs) => {
+            // If the function is a closure, only the generic arguments of the
+            // parent function are relevant
+            let fid = bt_ctx.ft_ctx.get_def_id_from_rid(def_id).unwrap();
+            let substs = if tcx.is_closure(def_id) {
+                translate_types::get_closure_parent_substs(tcx, def_id, substs)
+            } else {
+                substs
+            };
+            (ast::FunId::Regular(fid), substs)
+        }
+        ResolvedCall::TraitMethod {
+            trait_id,
+            method,
+            impl_source,
+        } => {
+            let trait_id = *bt_ctx
+                .ft_ctx
+                .ordered
+                .trait_decl_rid_to_id
+                .get(&trait_id)
+                .unwrap();
+            let func = ast::FunId::TraitMethod {
+                trait_id,
+                method,
+                impl_source,
+            };
+            (func, substs)
+        }
+    };
+    let (region_args, type_args, cg_args) =
+        translate_subst_generic_args_in_body(bt_ctx, Option::None, substs)?;
+    Ok(e::FnPtr::Fun(func, region_args, type_args, cg_args))
+}
+
 /// Translate a constant which may not be yet evaluated.
 fn translate_operand_constant<'tcx>(
     bt_ctx: &BodyTransContext<'tcx, '_, '_>,
@@ -1183,9 +1474,11 @@ fn translate_rvalue<'tcx>(
             let place = translate_place(bt_ctx, place);
             e::Rvalue::Use(e::Operand::Copy(place))
         }
-        mir::Rvalue::Repeat(_operand, _const) => {
+        mir::Rvalue::Repeat(operand, cnst) => {
             // [x; 32]
-            unimplemented!();
+            let len = translate_const_generic(bt_ctx, cnst).unwrap();
+            let (operand, ty) = translate_operand_with_type(bt_ctx, operand);
+            e::Rvalue::Repeat(operand, ty, len)
         }
         mir::Rvalue::Ref(_region, borrow_kind, place) => {
             let place = translate_place(bt_ctx, place);
@@ -1195,42 +1488,89 @@ fn translate_rvalue<'tcx>(
         mir::Rvalue::ThreadLocalRef(_) => {
             unreachable!();
         }
-        mir::Rvalue::AddressOf(_, _) => {
-            unreachable!();
+        mir::Rvalue::AddressOf(mutability, place) => {
+            // `&raw const x`, `&raw mut x`, or a coercion from a reference
+            // to a raw pointer
+            let place = translate_place(bt_ctx, place);
+            let kind = match mutability {
+                mir::Mutability::Not => ty::RefKind::Shared,
+                mir::Mutability::Mut => ty::RefKind::Mut,
+            };
+            e::Rvalue::RawPtr(place, kind)
         }
-        mir::Rvalue::Len(_place) => {
-            unimplemented!();
+        mir::Rvalue::Len(place) => {
+            // Length of an array or a slice
+            e::Rvalue::Len(translate_place(bt_ctx, place))
+        }
+        mir::Rvalue::Cast(
+            rustc_middle::mir::CastKind::Pointer(mir_ty::adjustment::PointerCast::ReifyFnPointer),
+            operand,
+            _,
+        ) => {
+            trace!("Rvalue::Cast: {:?}", rvalue);
+            // Conversion from a function item to a function pointer: as we
+            // translate the types of function items to function pointer types,
+            // this is the identity
+            e::Rvalue::Use(translate_operand(bt_ctx, operand))
         }
         mir::Rvalue::Cast(cast_kind, operand, tgt_ty) => {
             trace!("Rvalue::Cast: {:?}", rvalue);
-            // Put aside the pointer casts (which we don't support), I think
-            // casts should only be from integers/booleans to integer/booleans.
-
-            // Sanity check
-            assert!(match cast_kind {
-                rustc_middle::mir::CastKind::IntToInt => true,
-                rustc_middle::mir::CastKind::FloatToInt
-                | rustc_middle::mir::CastKind::FloatToFloat
-                | rustc_middle::mir::CastKind::IntToFloat
-                | rustc_middle::mir::CastKind::PtrToPtr
-                | rustc_middle::mir::CastKind::FnPtrToPtr
-                | rustc_middle::mir::CastKind::Pointer(_)
-                | rustc_middle::mir::CastKind::PointerExposeAddress
-                | rustc_middle::mir::CastKind::PointerFromExposedAddress
-                | rustc_middle::mir::CastKind::DynStar => false,
-            });
+            use rustc_middle::mir::CastKind;
+            use rustc_middle::ty::adjustment::PointerCast;
 
             // Translate the target type
             let tgt_ty = translate_ety(bt_ctx, tgt_ty).unwrap();
 
-            // Translate the operand
-            let (op, src_ty) = translate_operand_with_type(bt_ctx, operand);
-
-            // We only support source and target types for integers
-            let tgt_ty = *tgt_ty.as_integer();
-            let src_ty = *src_ty.as_integer();
-
-            e::Rvalue::UnaryOp(e::UnOp::Cast(src_ty, tgt_ty), op)
+            // Check if we are casting a fieldless enumeration, in which case
+            // the operand is the discriminant of this enumeration (see
+            // [BodyTransContext::discriminant_casts])
+            let enum_place = operand
+                .place()
+                .and_then(|p| p.as_local())
+                .and_then(|local| bt_ctx.discriminant_casts.get(&local));
+            if let Some(enum_place) = enum_place {
+                // We directly cast the enumeration
+                let (op, src_ty) =
+                    translate_operand_with_type(bt_ctx, &mir::Operand::Move(*enum_place));
+                let kind = e::CastKind::EnumDiscriminant(src_ty, tgt_ty);
+                e::Rvalue::UnaryOp(e::UnOp::Cast(kind), op)
+            } else {
+                // Translate the operand
+                let (op, src_ty) = translate_operand_with_type(bt_ctx, operand);
+
+                let kind = match cast_kind {
+                    CastKind::IntToInt
+                    | CastKind::FloatToInt
+                    | CastKind::FloatToFloat
+                    | CastKind::IntToFloat => {
+                        // Note that the integer casts include the casts
+                        // from booleans and characters, and the casts from
+                        // `u8` to characters
+                        e::CastKind::Scalar(src_ty, tgt_ty)
+                    }
+                    CastKind::Pointer(PointerCast::Unsize) => {
+                        // Unsizing coercion, for instance from `&T` to
+                        // `&dyn Trait`, from `Box<T>` to `Box<dyn Trait>`
+                        // or from `&[T; N]` to `&[T]`
+                        e::CastKind::Unsize(src_ty, tgt_ty)
+                    }
+                    CastKind::PtrToPtr
+                    | CastKind::Pointer(PointerCast::MutToConstPointer)
+                    | CastKind::Pointer(PointerCast::ArrayToPointer) => {
+                        e::CastKind::RawPtr(e::PtrCastKind::PtrToPtr, src_ty, tgt_ty)
+                    }
+                    CastKind::PointerExposeAddress => {
+                        e::CastKind::RawPtr(e::PtrCastKind::PtrToInt, src_ty, tgt_ty)
+                    }
+                    CastKind::PointerFromExposedAddress => {
+                        e::CastKind::RawPtr(e::PtrCastKind::IntToPtr, src_ty, tgt_ty)
+                    }
+                    CastKind::FnPtrToPtr | CastKind::Pointer(_) | CastKind::DynStar => {
+                        unimplemented!("Unsupported cast: {:?}", rvalue)
+                    }
+                };
+                e::Rvalue::UnaryOp(e::UnOp::Cast(kind), op)
+            }
         }
         mir::Rvalue::BinaryOp(binop, operands) | mir::Rvalue::CheckedBinaryOp(binop, operands) => {
             // We merge checked and unchecked binary operations
@@ -1276,8 +1616,10 @@ fn translate_rvalue<'tcx>(
                 .collect();
 
             match aggregate_kind.deref() {
-                mir::AggregateKind::Array(_ty) => {
-                    unimplemented!();
+                mir::AggregateKind::Array(ty) => {
+                    // [x, y, z]
+                    let ty = translate_ety(bt_ctx, ty).unwrap();
+                    e::Rvalue::Aggregate(e::AggregateKind::Array(ty), operands_t)
                 }
                 mir::AggregateKind::Tuple => {
                     e::Rvalue::Aggregate(e::AggregateKind::Tuple, operands_t)
@@ -1291,26 +1633,26 @@ fn translate_rvalue<'tcx>(
                 ) => {
                     trace!("{:?}", rvalue);
 
-                    // Not sure what those two parameters are used for, so
-                    // panicking if they are not none (to catch a use case).
-                    // I'm not even sure that "field_index" is a proper name:
-                    // the documentation seems outdated (it says the 4th parameter
-                    // is a field index, while it makes more sense for it to be
-                    // the 5th, and I don't know how I should use it anyway).
+                    // Not sure what the user annotation is used for, so
+                    // panicking if it is not none (to catch a use case).
+                    // The field index is the active field of a union: the
+                    // union initializations are filtered in [translate_statement].
                     assert!(user_annotation.is_none());
                     assert!(field_index.is_none());
 
                     // Translate the substitution
-                    let (region_params, mut type_params) =
+                    let (region_params, mut type_params, cg_params) =
                         translate_subst_generic_args_in_body(bt_ctx, None, substs).unwrap();
 
-                    if adt_id.is_local() {
-                        // Local ADT: retrieve the definition
-                        let id_t = *bt_ctx.ft_ctx.ordered.type_rid_to_id.get(adt_id).unwrap();
+                    if let Some(id_t) = bt_ctx.ft_ctx.ordered.type_rid_to_id.get(adt_id) {
+                        // Local ADT, or external ADT which we extracted (see
+                        // `--transparent-external-adts`): retrieve the definition
+                        let id_t = *id_t;
                         let def = bt_ctx.get_type_defs().get_type_def(id_t).unwrap();
 
                         assert!(region_params.len() == def.region_params.len());
                         assert!(type_params.len() == def.type_params.len());
+                        assert!(cg_params.len() == def.const_generic_params.len());
 
                         let variant_id = match &def.kind {
                             ty::TypeDeclKind::Enum(variants) => {
@@ -1326,27 +1668,33 @@ fn translate_rvalue<'tcx>(
                                 assert!(variant_idx.as_usize() == 0);
                                 None
                             }
+                            ty::TypeDeclKind::Union(_) => {
+                                // Filtered in [translate_statement]
+                                unreachable!()
+                            }
                             ty::TypeDeclKind::Opaque => {
                                 unreachable!("Can't build an aggregate from an opaque type")
                             }
                         };
 
-                        let akind =
-                            e::AggregateKind::Adt(id_t, variant_id, region_params, type_params);
+                        let akind = e::AggregateKind::Adt(
+                            id_t,
+                            variant_id,
+                            region_params,
+                            type_params,
+                            cg_params,
+                        );
 
                         e::Rvalue::Aggregate(akind, operands_t)
                     } else {
-                        // External ADT.
-                        // Can be `Option`
-                        // TODO: treat all external ADTs in a consistant manner.
-                        // For instance, we can access the variants of any external
-                        // enumeration marked as `public`.
+                        // Primitive ADT: can only be `Option`
                         let name = type_def_id_to_name(tcx, *adt_id);
                         assert!(name.equals_ref_name(&assumed::OPTION_NAME));
 
                         // Sanity checks
                         assert!(region_params.is_empty());
                         assert!(type_params.len() == 1);
+                        assert!(cg_params.is_empty());
 
                         // Find the variant
                         let variant_id = translate_variant_id(*variant_idx);
@@ -1364,8 +1712,28 @@ fn translate_rvalue<'tcx>(
                         e::Rvalue::Aggregate(akind, operands_t)
                     }
                 }
-                mir::AggregateKind::Closure(_def_id, _subst) => {
-                    unimplemented!();
+                mir::AggregateKind::Closure(def_id, substs) => {
+                    // We build the state of the closure: we translate the
+                    // closure type, which gives us the state type
+                    let closure_ty = tcx.mk_closure(def_id.to_def_id(), substs);
+                    match translate_ety(bt_ctx, &closure_ty).unwrap() {
+                        ty::Ty::Adt(
+                            ty::TypeId::Adt(id_t),
+                            region_params,
+                            type_params,
+                            cg_params,
+                        ) => {
+                            let akind = e::AggregateKind::Adt(
+                                id_t,
+                                None,
+                                region_params.into_iter().collect(),
+                                type_params.into_iter().collect(),
+                                cg_params.into_iter().collect(),
+                            );
+                            e::Rvalue::Aggregate(akind, operands_t)
+                        }
+                        _ => unreachable!(),
+                    }
                 }
                 mir::AggregateKind::Generator(_def_id, _subst, _movability) => {
                     unimplemented!();
@@ -1394,10 +1762,49 @@ fn translate_statement<'tcx>(
     let tcx = bt_ctx.ft_ctx.tcx;
 
     let t_statement: Option<ast::RawStatement> = match &statement.kind {
+        StatementKind::Assign(assign)
+            if assign.0.as_local().map_or(false, |local| {
+                bt_ctx.discriminant_casts.contains_key(&local)
+                    || bt_ctx.assume_locals.contains(&local)
+            }) =>
+        {
+            // This statement reads the discriminant of an enumeration to
+            // cast it (we merge the read with the cast), or computes the
+            // condition of an `assume` hint introduced by this cast: we
+            // ignore it. See [BodyTransContext::discriminant_casts].
+            None
+        }
         StatementKind::Assign(assign) => {
             let (place, rvalue) = assign.deref();
-            let t_place = translate_place(bt_ctx, place);
-            let t_rvalue = translate_rvalue(tcx, bt_ctx, rvalue);
+            let mut t_place = translate_place(bt_ctx, place);
+
+            // Check if this is the initialization of a union (in which case
+            // the aggregate has an active field)
+            let union_init = match rvalue {
+                mir::Rvalue::Aggregate(aggregate_kind, operands) => match aggregate_kind.deref() {
+                    mir::AggregateKind::Adt(adt_id, _, _, _, Some(field_index)) => {
+                        Some((adt_id, field_index, operands))
+                    }
+                    _ => None,
+                },
+                _ => None,
+            };
+
+            let t_rvalue = match union_init {
+                Some((adt_id, field_index, operands)) => {
+                    // Initializing a union is the same as writing to its
+                    // active field: we translate `u = U { f: x }` to `u.f = x`.
+                    assert!(operands.len() == 1);
+                    let id_t = *bt_ctx.ft_ctx.ordered.type_rid_to_id.get(adt_id).unwrap();
+                    let proj_kind = e::FieldProjKind::Union(id_t);
+                    let field_id = FieldId::Id::new(*field_index);
+                    t_place
+                        .projection
+                        .push_back(e::ProjectionElem::Field(proj_kind, field_id));
+                    e::Rvalue::Use(translate_operand(bt_ctx, &operands[0]))
+                }
+                None => translate_rvalue(tcx, bt_ctx, rvalue),
+            };
 
             Some(ast::RawStatement::Assign(t_place, t_rvalue))
         }
@@ -1446,9 +1853,36 @@ fn translate_statement<'tcx>(
             let t_place = translate_place(bt_ctx, place);
             Some(ast::RawStatement::Deinit(t_place))
         }
-        StatementKind::Intrinsic(_) => {
-            unimplemented!();
-        }
+        StatementKind::Intrinsic(intrinsic) => match intrinsic.deref() {
+            mir::NonDivergingIntrinsic::Assume(op)
+                if op
+                    .place()
+                    .and_then(|p| p.as_local())
+                    .map_or(false, |local| bt_ctx.assume_locals.contains(&local)) =>
+            {
+                // We ignore the `assume` hints introduced by the casts of
+                // enumerations (see [BodyTransContext::assume_locals])
+                None
+            }
+            mir::NonDivergingIntrinsic::Assume(op) => {
+                let op = translate_operand(bt_ctx, op);
+                Some(ast::RawStatement::Intrinsic(
+                    ast::IntrinsicFunId::Assume,
+                    vec![op],
+                ))
+            }
+            mir::NonDivergingIntrinsic::CopyNonOverlapping(copy) => {
+                let args = vec![
+                    translate_operand(bt_ctx, &copy.src),
+                    translate_operand(bt_ctx, &copy.dst),
+                    translate_operand(bt_ctx, &copy.count),
+                ];
+                Some(ast::RawStatement::Intrinsic(
+                    ast::IntrinsicFunId::CopyNonOverlapping,
+                    args,
+                ))
+            }
+        },
     };
 
     // Add the meta information
@@ -1493,65 +1927,87 @@ fn translate_terminator<'tcx>(
             ast::RawTerminator::Goto { target }
         }
         TerminatorKind::SwitchInt { discr, targets } => {
+            // Check if we switch over the discriminant of an enumeration
+            // (see [BodyTransContext::discriminant_reads])
+            let enum_adt = discr
+                .place()
+                .and_then(|p| p.as_local())
+                .and_then(|local| bt_ctx.discriminant_reads.get(&local))
+                .and_then(|p| match p.ty(body, bt_ctx.ft_ctx.tcx).ty.kind() {
+                    TyKind::Adt(adt_def, _) if adt_def.is_enum() => Some(*adt_def),
+                    _ => None,
+                });
+
             // Translate the operand which gives the discriminant
             let (discr, discr_ty) = translate_operand_with_type(bt_ctx, discr);
 
             // Translate the switch targets
-            let targets = translate_switch_targets(bt_ctx, body, &discr_ty, targets)?;
+            let targets = translate_switch_targets(bt_ctx, body, &discr_ty, enum_adt, targets)?;
 
             ast::RawTerminator::Switch { discr, targets }
         }
         TerminatorKind::Resume => {
-            // This is used to correctly unwind. We shouldn't get there: if
-            // we panic, the state gets stuck.
-            unreachable!();
+            // This is used to correctly unwind. We shouldn't get there unless
+            // we translate the cleanup blocks: otherwise, if we panic, the
+            // state gets stuck.
+            assert!(bt_ctx.ft_ctx.unwind);
+            ast::RawTerminator::UnwindResume
         }
         TerminatorKind::Abort => {
-            // TODO: we will translate this to `ast::RawTerminator::Abort`,
-            // but I want to see in which situations Abort appears.
-            unimplemented!();
+            // This appears in the cleanup blocks (for instance, if a drop
+            // panics while unwinding)
+            assert!(bt_ctx.ft_ctx.unwind);
+            ast::RawTerminator::Abort
         }
         TerminatorKind::Return => ast::RawTerminator::Return,
         TerminatorKind::Unreachable => ast::RawTerminator::Unreachable,
         TerminatorKind::Drop {
             place,
             target,
-            unwind: _,
+            unwind,
         } => ast::RawTerminator::Drop {
             place: translate_place(bt_ctx, place),
             target: translate_basic_block(bt_ctx, body, *target)?,
+            unwind: translate_unwind_target(bt_ctx, body, unwind)?,
         },
         TerminatorKind::DropAndReplace {
             place,
             value,
             target,
-            unwind: _,
+            unwind,
         } => {
             // We desugar this to `drop(place); place := value;
+            // Note that the assignment is also performed if the drop panics.
 
-            // Translate the next block
+            // Translate the next blocks
             let target = translate_basic_block(bt_ctx, body, *target)?;
+            let unwind = translate_unwind_target(bt_ctx, body, unwind)?;
 
             // Translate the assignment
             let place = translate_place(bt_ctx, place);
             let rv = e::Rvalue::Use(translate_operand(bt_ctx, value));
             let assign = ast::Statement::new(meta, ast::RawStatement::Assign(place.clone(), rv));
 
-            // Generate a goto
-            let goto = ast::Terminator::new(meta, ast::RawTerminator::Goto { target });
-
-            // This introduces a new block, which doesn't appear in the original MIR
-            let assign_id = bt_ctx.blocks_counter.fresh_id();
-            let assign_block = ast::BlockData {
-                statements: vec![assign],
-                terminator: goto,
+            // Introduce a block performing the assignment then going to `target`:
+            // this block doesn't appear in the original MIR
+            let mut push_assign_block = |target| {
+                let goto = ast::Terminator::new(meta, ast::RawTerminator::Goto { target });
+                let assign_id = bt_ctx.blocks_counter.fresh_id();
+                let assign_block = ast::BlockData {
+                    statements: vec![assign.clone()],
+                    terminator: goto,
+                };
+                bt_ctx.push_block(assign_id, assign_block);
+                assign_id
             };
-            bt_ctx.push_block(assign_id, assign_block);
+            let assign_id = push_assign_block(target);
+            let unwind = unwind.map(push_assign_block);
 
             // Translate the drop
             ast::RawTerminator::Drop {
                 place,
                 target: assign_id,
+                unwind,
             }
         }
         TerminatorKind::Call {
@@ -1559,25 +2015,35 @@ fn translate_terminator<'tcx>(
             args,
             destination,
             target,
-            cleanup: _, // Note that the state gets stuck if we need to unwind
+            cleanup,
             from_hir_call: _,
             fn_span: _,
         } => {
             trace!("Call: func: {:?}", func);
-            translate_function_call(bt_ctx, body, func, args, destination, target)?
+            let span = terminator.source_info.span;
+            let mut call =
+                translate_function_call(bt_ctx, body, span, func, args, destination, target)?;
+            // Note that unless we use `--unwind`, the state gets stuck if we need
+            // to unwind
+            if let ast::RawTerminator::Call { unwind, .. } = &mut call {
+                *unwind = translate_unwind_target(bt_ctx, body, cleanup)?;
+            }
+            call
         }
         TerminatorKind::Assert {
             cond,
             expected,
-            msg: _, // We ignore the message: if we panic, the state gets stuck
+            msg,
             target,
             cleanup: _, // If we panic, the state gets stuck: we don't need to model cleanup
         } => {
             let cond = translate_operand(bt_ctx, cond);
+            let kind = translate_assert_kind(msg);
             let target = translate_basic_block(bt_ctx, body, *target)?;
             ast::RawTerminator::Assert {
                 cond,
                 expected: *expected,
+                kind,
                 target,
             }
         }
@@ -1620,15 +2086,32 @@ fn translate_terminator<'tcx>(
             ast::RawTerminator::Goto { target }
         }
         TerminatorKind::InlineAsm {
-            template: _,
-            operands: _,
-            options: _,
+            template,
+            operands,
+            options,
             line_spans: _,
-            destination: _,
-            cleanup: _,
+            destination,
+            cleanup: _, // If we panic, the state gets stuck: we don't need to model cleanup
         } => {
-            // This case should have been eliminated during the registration phase
-            unreachable!();
+            // We desugar this to a block containing an opaque inline assembly
+            // statement, followed by a goto to the destination (if there is no
+            // destination, the assembly block never returns).
+            let asm = translate_inline_asm(bt_ctx, template, operands, *options);
+            let next = match destination {
+                Option::Some(target) => ast::RawTerminator::Goto {
+                    target: translate_basic_block(bt_ctx, body, *target)?,
+                },
+                Option::None => ast::RawTerminator::Unreachable,
+            };
+
+            // Introduce the block: it doesn't appear in the original MIR
+            let asm_id = bt_ctx.blocks_counter.fresh_id();
+            let asm_block = ast::BlockData {
+                statements: vec![ast::Statement::new(meta, ast::RawStatement::InlineAsm(asm))],
+                terminator: ast::Terminator::new(meta, next),
+            };
+            bt_ctx.push_block(asm_id, asm_block);
+            ast::RawTerminator::Goto { target: asm_id }
         }
     };
 
@@ -1636,11 +2119,102 @@ fn translate_terminator<'tcx>(
     Ok(ast::Terminator::new(meta, t_terminator))
 }
 
+/// Translate an inline assembly block. We don't interpret the assembly code:
+/// we only retrieve the template, the operands and the options.
+fn translate_inline_asm<'tcx>(
+    bt_ctx: &BodyTransContext<'tcx, '_, '_>,
+    template: &[InlineAsmTemplatePiece],
+    operands: &[mir::InlineAsmOperand<'tcx>],
+    options: InlineAsmOptions,
+) -> ast::InlineAsm {
+    let tcx = bt_ctx.ft_ctx.tcx;
+    let template = InlineAsmTemplatePiece::to_string(template);
+
+    let operands = operands
+        .iter()
+        .map(|op| match op {
+            mir::InlineAsmOperand::In { reg, value } => ast::InlineAsmOperand::In {
+                reg: reg.to_string(),
+                value: translate_operand(bt_ctx, value),
+            },
+            mir::InlineAsmOperand::Out { reg, late, place } => ast::InlineAsmOperand::Out {
+                reg: reg.to_string(),
+                late: *late,
+                place: place.map(|p| translate_place(bt_ctx, &p)),
+            },
+            mir::InlineAsmOperand::InOut {
+                reg,
+                late,
+                in_value,
+                out_place,
+            } => ast::InlineAsmOperand::InOut {
+                reg: reg.to_string(),
+                late: *late,
+                in_value: translate_operand(bt_ctx, in_value),
+                out_place: out_place.map(|p| translate_place(bt_ctx, &p)),
+            },
+            mir::InlineAsmOperand::Const { value } => {
+                let (ty, value) = translate_operand_constant(bt_ctx, value);
+                ast::InlineAsmOperand::Const(e::Operand::Const(ty, value))
+            }
+            mir::InlineAsmOperand::SymFn { value } => match value.literal.ty().kind() {
+                TyKind::FnDef(def_id, _) => {
+                    ast::InlineAsmOperand::Sym(function_def_id_to_name(tcx, *def_id).to_string())
+                }
+                _ => unreachable!(),
+            },
+            mir::InlineAsmOperand::SymStatic { def_id } => {
+                ast::InlineAsmOperand::Sym(global_def_id_to_name(tcx, *def_id).to_string())
+            }
+        })
+        .collect();
+
+    // The options, in the order in which they are printed by rustc
+    let all_options = [
+        (InlineAsmOptions::PURE, "pure"),
+        (InlineAsmOptions::NOMEM, "nomem"),
+        (InlineAsmOptions::READONLY, "readonly"),
+        (InlineAsmOptions::PRESERVES_FLAGS, "preserves_flags"),
+        (InlineAsmOptions::NORETURN, "noreturn"),
+        (InlineAsmOptions::NOSTACK, "nostack"),
+        (InlineAsmOptions::ATT_SYNTAX, "att_syntax"),
+        (InlineAsmOptions::RAW, "raw"),
+        (InlineAsmOptions::MAY_UNWIND, "may_unwind"),
+    ];
+    let options = all_options
+        .iter()
+        .filter(|(opt, _)| options.contains(*opt))
+        .map(|(_, name)| name.to_string())
+        .collect();
+
+    ast::InlineAsm {
+        template,
+        operands,
+        options,
+    }
+}
+
+/// Translate the cleanup block a terminator goes to upon panicking. We ignore
+/// it unless we use `--unwind` (see [DeclTransContext::unwind]).
+fn translate_unwind_target<'tcx>(
+    bt_ctx: &mut BodyTransContext<'tcx, '_, '_>,
+    body: &Body<'tcx>,
+    unwind: &Option<BasicBlock>,
+) -> Result<Option<ast::BlockId::Id>> {
+    match unwind {
+        Option::Some(unwind) if bt_ctx.ft_ctx.unwind => {
+            Ok(Option::Some(translate_basic_block(bt_ctx, body, *unwind)?))
+        }
+        _ => Ok(Option::None),
+    }
+}
+
 /// Translate switch targets
 fn translate_switch_targets<'tcx>(
     bt_ctx: &mut BodyTransContext<'tcx, '_, '_>,
     body: &Body<'tcx>,
     switch_ty: &ty::ETy,
+    enum_adt: Option<AdtDef<'tcx>>,
     targets: &mir::SwitchTargets,
 ) -> Result<ast::SwitchTargets> {
     trace!("targets: {:?}", targets);
@@ -1663,13 +2237,35 @@ fn translate_switch_targets<'tcx>(
         }
         ty::Ty::Integer(int_ty) => {
             // This is a: switch(int).
+            // If we switch over the discriminant of an enumeration, the
+            // discriminant values may not be the variant indices (the
+            // enumeration may have a `repr` attribute, or explicit
+            // discriminants like `Ordering`): we convert them to the indices
+            // of the variants, as expected by [crate::remove_read_discriminant].
+            let tcx = bt_ctx.ft_ctx.tcx;
+            let int_ty = if enum_adt.is_some() {
+                &ty::IntegerTy::Isize
+            } else {
+                int_ty
+            };
             // Convert all the test values to the proper values.
             let mut targets_map: LinkedHashMap<v::ScalarValue, ast::BlockId::Id> =
                 LinkedHashMap::new();
             for (v, tgt) in targets_vec {
-                // We need to reinterpret the bytes (`v as i128` is not correct)
-                let raw: [u8; 16] = v.to_le_bytes();
-                let v = v::ScalarValue::from_le_bytes(*int_ty, raw);
+                let v = match enum_adt {
+                    Some(adt) => {
+                        let (variant_idx, _) = adt
+                            .discriminants(tcx)
+                            .find(|(_, discr)| discr.val == v)
+                            .unwrap();
+                        v::ScalarValue::Isize(variant_idx.as_usize() as isize)
+                    }
+                    None => {
+                        // We need to reinterpret the bytes (`v as i128` is not correct)
+                        let raw: [u8; 16] = v.to_le_bytes();
+                        v::ScalarValue::from_le_bytes(*int_ty, raw)
+                    }
+                };
                 let tgt = translate_basic_block(bt_ctx, body, tgt)?;
                 assert!(!targets_map.contains_key(&v));
                 targets_map.insert(v, tgt);
@@ -1692,51 +2288,29 @@ fn translate_switch_targets<'tcx>(
 /// Return the `DefId` of the function referenced by an operand, with the
 /// parameters substitution.
 /// The `Operand` comes from a `TerminatorKind::Call`.
-/// Only supports calls to top-level functions (which are considered as constants
-/// by rustc); doesn't support closures for now.
+/// The operand is usually a constant (calls to top-level functions are
+/// considered as constants by rustc), but may also be a local variable
+/// containing a function item or a function pointer: in the latter case,
+/// we return `None` (the call is an indirect call).
 fn get_function_from_operand<'tcx>(
+    tcx: TyCtxt<'tcx>,
+    body: &mir::Body<'tcx>,
     func: &Operand<'tcx>,
-) -> (DefId, &'tcx rustc_middle::ty::subst::InternalSubsts<'tcx>) {
+) -> Option<(DefId, SubstsRef<'tcx>)> {
     trace!("func: {:?}", func);
 
-    use std::ops::Deref;
-    // Match on the func operand: it should be a constant as we don't support
-    // closures for now.
-    match func {
-        mir::Operand::Constant(c) => {
-            trace!("Operand::Constant: {:?}", c);
-            let c = c.deref();
-            match &c.literal {
-                mir::ConstantKind::Ty(c) => {
-                    // The type of the constant should be a FnDef, allowing
-                    // us to retrieve the function's identifier and instantiation.
-                    let c_ty = c.ty();
-                    assert!(c_ty.is_fn());
-                    match c_ty.kind() {
-                        mir_ty::TyKind::FnDef(def_id, subst) => (*def_id, subst),
-                        _ => {
-                            unreachable!();
-                        }
-                    }
-                }
-                mir::ConstantKind::Val(cv, c_ty) => {
-                    trace!("cv: {:?}, ty: {:?}", cv, c_ty);
-                    // Same as for the `Ty` case above
-                    assert!(c_ty.is_fn());
-                    match c_ty.kind() {
-                        mir_ty::TyKind::FnDef(def_id, subst) => (*def_id, subst),
-                        _ => {
-                            unreachable!();
-                        }
-                    }
-                }
-                mir::ConstantKind::Unevaluated(_, _) => {
-                    unimplemented!();
-                }
-            }
+    // The type of the operand should be a FnDef, allowing us to retrieve
+    // the function's identifier and instantiation, or a FnPtr.
+    let fn_ty = func.ty(&body.local_decls, tcx);
+    match fn_ty.kind() {
+        mir_ty::TyKind::FnDef(def_id, subst) => Some((*def_id, subst)),
+        mir_ty::TyKind::FnPtr(_) => {
+            // Sanity check: function pointers can't be constants
+            assert!(func.place().is_some());
+            None
         }
-        mir::Operand::Move(_place) | mir::Operand::Copy(_place) => {
-            unimplemented!();
+        _ => {
+            unreachable!("Unexpected callee type: {:?}", fn_ty);
         }
     }
 }
@@ -1802,9 +2376,42 @@ fn get_impl_parent_type_def_id(tcx: TyCtxt, def_id: DefId) -> Option<DefId> {
 /// Note that `body` is the body of the function being translated, not of the
 /// function referenced in the function call: we need it in order to translate
 /// the blocks we go to after the function call returns.
+/// Translate the reason why a MIR assertion is checked.
+fn translate_assert_kind<O>(kind: &mir::AssertKind<O>) -> ast::AssertKind {
+    match kind {
+        mir::AssertKind::BoundsCheck { .. } => ast::AssertKind::BoundsCheck,
+        mir::AssertKind::Overflow(binop, _, _) => {
+            ast::AssertKind::Overflow(translate_binaryop_kind(*binop))
+        }
+        mir::AssertKind::OverflowNeg(_) => ast::AssertKind::OverflowNeg,
+        mir::AssertKind::DivisionByZero(_) => ast::AssertKind::DivisionByZero,
+        mir::AssertKind::RemainderByZero(_) => ast::AssertKind::RemainderByZero,
+        mir::AssertKind::ResumedAfterReturn(_) | mir::AssertKind::ResumedAfterPanic(_) => {
+            // Those are introduced for generators, which we don't support
+            unimplemented!()
+        }
+    }
+}
+
+/// Retrieve the message given to a panic function (`core::panicking::panic`
+/// or `std::panicking::begin_panic`), if it is a string literal.
+fn get_panic_message<'tcx>(tcx: TyCtxt<'tcx>, args: &[Operand<'tcx>]) -> Option<String> {
+    match args.first()? {
+        Operand::Constant(c) => match c.literal {
+            mir::ConstantKind::Val(cv @ mir::interpret::ConstValue::Slice { .. }, _) => {
+                let bytes = mir::interpret::get_slice_bytes(&tcx, cv);
+                String::from_utf8(bytes.to_vec()).ok()
+            }
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
 fn translate_function_call<'tcx>(
     bt_ctx: &mut BodyTransContext<'tcx, '_, '_>,
     body: &mir::Body<'tcx>,
+    span: Span,
     func: &Operand<'tcx>,
     args: &Vec<Operand<'tcx>>,
     destination: &Place<'tcx>,
@@ -1812,14 +2419,32 @@ fn translate_function_call<'tcx>(
 ) -> Result<ast::RawTerminator> {
     trace!();
 
-    // Translate the function operand - should be a constant: we don't
-    // support closures for now
     trace!("func: {:?}", func);
 
     let tcx = bt_ctx.ft_ctx.tcx;
 
     // Retrieve the function's identifier and instantiation
-    let (def_id, substs) = get_function_from_operand(func);
+    let (def_id, substs) = match get_function_from_operand(tcx, body, func) {
+        Option::Some(fun) => fun,
+        Option::None => {
+            // Indirect call through a function pointer
+            // Note that the function may diverge (ex.: `fn() -> !`)
+            let fn_place = translate_place(bt_ctx, &func.place().unwrap());
+            let lval = translate_place(bt_ctx, destination);
+            let next_block = translate_call_target(bt_ctx, body, span, target)?;
+            let args = translate_arguments(bt_ctx, Option::None, args);
+            return Ok(ast::RawTerminator::Call {
+                func: ast::FnOperand::Move(fn_place),
+                region_args: Vec::new(),
+                type_args: Vec::new(),
+                const_generic_args: Vec::new(),
+                args,
+                dest: lval,
+                target: next_block,
+                unwind: None,
+            });
+        }
+    };
 
     // Translate the name to check if is is `core::panicking::panic`
     let name = function_def_id_to_name(tcx, def_id);
@@ -1832,8 +2457,30 @@ fn translate_function_call<'tcx>(
         assert!(!def_id.is_local());
         assert!(target.is_none());
 
-        // We ignore the arguments
-        Ok(ast::RawTerminator::Panic)
+        // Retrieve the message, if it is a literal (we ignore the other arguments).
+        // The panic is generally introduced by a macro (`panic!`, `assert!`, etc.):
+        // we use the location of the macro call in the user code.
+        let location = meta::translate_span(
+            bt_ctx.ft_ctx.sess,
+            &bt_ctx.ft_ctx.ordered.file_to_id,
+            span.source_callsite(),
+        );
+        let info = ast::PanicInfo {
+            message: get_panic_message(tcx, args),
+            location,
+        };
+        Ok(ast::RawTerminator::Panic(info))
+    } else if is_intrinsic_call(tcx, def_id) {
+        translate_intrinsic_call(
+            bt_ctx,
+            body,
+            span,
+            def_id,
+            substs,
+            args,
+            destination,
+            target,
+        )
     } else {
         assert!(target.is_some());
         let next_block = target.unwrap();
@@ -1873,90 +2520,409 @@ fn translate_function_call<'tcx>(
 
             // Return
             Ok(ast::RawTerminator::Call {
-                func: ast::FunId::Assumed(ast::AssumedFunId::BoxFree),
+                func: ast::FnOperand::Regular(ast::FunId::Assumed(ast::AssumedFunId::BoxFree)),
                 region_args: vec![],
                 type_args: vec![t_ty],
+                const_generic_args: vec![],
                 args: vec![t_arg],
                 dest: lval,
                 target: next_block,
+                unwind: None,
             })
+        } else if let Option::Some(aid) = get_primitive_function_id(tcx, def_id, substs) {
+            // Primitive function.
+            //
+            // Retrieve the lists of used parameters (some parameters, like the
+            // allocators, are ignored)
+            let used = assumed::function_to_info(&name).unwrap();
+
+            // Translate the type parameters
+            let (region_args, type_args, cg_args) = translate_subst_generic_args_in_body(
+                bt_ctx,
+                Option::Some(used.used_type_params),
+                substs,
+            )?;
+
+            // Translate the arguments
+            let args = translate_arguments(bt_ctx, Option::Some(used.used_args), args);
+
+            // Note that there are subtleties with regards to the way types parameters
+            // are translated, because some functions are actually traits, where the
+            // types are used for the resolution. For instance, the following:
+            // `core::ops::deref::Deref::<alloc::boxed::Box<T>>::deref`
+            // is translated to:
+            // `box_deref<T>`
+            // (the type parameter is not `Box<T>` but `T`).
+            translate_primitive_function_call(
+                aid,
+                region_args,
+                type_args,
+                cg_args,
+                args,
+                lval,
+                next_block,
+            )
         } else {
-            // Retrieve the lists of used parameters, in case of non-local
-            // definitions
-            let (used_type_args, used_args) = if def_id.is_local() {
-                (Option::None, Option::None)
-            } else {
-                match assumed::function_to_info(&name) {
-                    Option::None => (Option::None, Option::None),
-                    Option::Some(used) => (
-                        Option::Some(used.used_type_params),
-                        Option::Some(used.used_args),
-                    ),
+            // If the function is a trait method, try to resolve the
+            // implementation statically.
+            let sess = bt_ctx.ft_ctx.sess;
+            let resolved = resolve_function_call(sess, tcx, span, bt_ctx.def_id, def_id, substs)?;
+            let (func, substs) = match resolved {
+                ResolvedCall::Fun(def_id, substs) if tcx.is_closure(def_id) => {
+                    // We call a closure through one of the `Fn` traits
+                    let fid = bt_ctx.ft_ctx.get_def_id_from_rid(def_id).unwrap();
+                    let substs = translate_types::get_closure_parent_substs(tcx, def_id, substs);
+                    let type_args = substs
+                        .types()
+                        .map(|ty| translate_ety(bt_ctx, &ty))
+                        .collect::<Result<Vec<ty::ETy>>>()?;
+                    let const_generic_args = substs
+                        .consts()
+                        .map(|c| translate_const_generic(bt_ctx, &c))
+                        .collect::<Result<Vec<ty::ConstGeneric>>>()?;
+                    let args = translate_closure_call_arguments(bt_ctx, body, args);
+
+                    return Ok(ast::RawTerminator::Call {
+                        func: ast::FnOperand::Regular(ast::FunId::Regular(fid)),
+                        region_args: vec![],
+                        type_args,
+                        const_generic_args,
+                        args,
+                        dest: lval,
+                        target: next_block,
+                        unwind: None,
+                    });
+                }
+                ResolvedCall::Fun(def_id, substs) => {
+                    // Retrieve the def id
+                    let def_id = bt_ctx.ft_ctx.get_def_id_from_rid(def_id).unwrap();
+                    (ast::FunId::Regular(def_id), substs)
+                }
+                ResolvedCall::TraitMethod {
+                    trait_id,
+                    method,
+                    impl_source,
+                } => {
+                    let trait_id = *bt_ctx
+                        .ft_ctx
+                        .ordered
+                        .trait_decl_rid_to_id
+                        .get(&trait_id)
+                        .unwrap();
+                    let func = ast::FunId::TraitMethod {
+                        trait_id,
+                        method,
+                        impl_source,
+                    };
+                    (func, substs)
                 }
             };
 
             // Translate the type parameters
-            let (region_args, type_args) =
-                translate_subst_generic_args_in_body(bt_ctx, used_type_args, substs)?;
+            let (region_args, type_args, const_generic_args) =
+                translate_subst_generic_args_in_body(bt_ctx, Option::None, substs)?;
 
             // Translate the arguments
-            let args = translate_arguments(bt_ctx, used_args, args);
-
-            // Check if the function is considered primitive: primitive
-            // functions benefit from special treatment.
-            let name = function_def_id_to_name(tcx, def_id);
-            let is_prim = if def_id.is_local() {
-                false
-            } else {
-                assumed::get_fun_id_from_name(&name).is_some()
-            };
+            let args = translate_arguments(bt_ctx, Option::None, args);
 
-            if !is_prim {
-                // Retrieve the def id
-                let def_id = bt_ctx.ft_ctx.get_def_id_from_rid(def_id).unwrap();
+            Ok(ast::RawTerminator::Call {
+                func: ast::FnOperand::Regular(func),
+                region_args,
+                type_args,
+                const_generic_args,
+                args,
+                dest: lval,
+                target: next_block,
+                unwind: None,
+            })
+        }
+    }
+}
 
-                let func = ast::FunId::Regular(def_id);
+/// Return `true` if the function is a compiler intrinsic (a function declared
+/// with the `rust-intrinsic` ABI, like `core::intrinsics::size_of`).
+///
+/// This is also used in [crate::register].
+pub(crate) fn is_intrinsic_call(tcx: TyCtxt<'_>, def_id: DefId) -> bool {
+    !def_id.is_local() && tcx.def_kind(def_id) == DefKind::Fn && tcx.is_intrinsic(def_id)
+}
 
-                Ok(ast::RawTerminator::Call {
-                    func,
-                    region_args,
-                    type_args,
-                    args,
-                    dest: lval,
-                    target: next_block,
-                })
-            } else {
-                // Primitive function.
-                //
-                // Note that there are subtleties with regards to the way types parameters
-                // are translated, because some functions are actually traits, where the
-                // types are used for the resolution. For instance, the following:
-                // `core::ops::deref::Deref::<alloc::boxed::Box<T>>::deref`
-                // is translated to:
-                // `box_deref<T>`
-                // (the type parameter is not `Box<T>` but `T`).
-                translate_primitive_function_call(
-                    tcx,
-                    def_id,
-                    region_args,
-                    type_args,
-                    args,
-                    lval,
-                    next_block,
-                )
-            }
+/// Translate the block we go to after a function call returns.
+///
+/// If the function diverges (there is no target), we introduce a block
+/// containing an [ast::RawTerminator::Unreachable]: this block doesn't
+/// appear in the original MIR.
+fn translate_call_target<'tcx>(
+    bt_ctx: &mut BodyTransContext<'tcx, '_, '_>,
+    body: &mir::Body<'tcx>,
+    span: Span,
+    target: &Option<BasicBlock>,
+) -> Result<ast::BlockId::Id> {
+    match target {
+        Option::Some(target) => translate_basic_block(bt_ctx, body, *target),
+        Option::None => {
+            let meta = meta::get_meta_from_rspan(
+                bt_ctx.ft_ctx.sess,
+                &bt_ctx.ft_ctx.ordered.file_to_id,
+                span,
+            );
+            let block_id = bt_ctx.blocks_counter.fresh_id();
+            let block = ast::BlockData {
+                statements: vec![],
+                terminator: ast::Terminator::new(meta, ast::RawTerminator::Unreachable),
+            };
+            bt_ctx.push_block(block_id, block);
+            Ok(block_id)
         }
     }
 }
 
-/// Translate a parameter substitution used inside a function body.
+/// Translate a call to a compiler intrinsic.
 ///
-/// Note that the regions parameters are expected to have been erased.
-fn translate_subst_generic_args_in_body<'tcx, 'ctx, 'ctx1>(
-    bt_ctx: &BodyTransContext<'tcx, 'ctx, 'ctx1>,
-    used_args: Option<Vec<bool>>,
+/// The intrinsics we recognize are translated to [ast::FunId::Intrinsic], the
+/// other ones to [ast::FunId::OpaqueIntrinsic]. The diverging intrinsics
+/// (like `abort`) are followed by an [ast::RawTerminator::Unreachable] (see
+/// [translate_call_target]), at the exception of `unreachable` which we
+/// directly translate to [ast::RawTerminator::Unreachable].
+#[allow(clippy::too_many_arguments)]
+fn translate_intrinsic_call<'tcx>(
+    bt_ctx: &mut BodyTransContext<'tcx, '_, '_>,
+    body: &mir::Body<'tcx>,
+    span: Span,
+    def_id: DefId,
+    substs: SubstsRef<'tcx>,
+    args: &Vec<Operand<'tcx>>,
+    destination: &Place<'tcx>,
+    target: &Option<BasicBlock>,
+) -> Result<ast::RawTerminator> {
+    let tcx = bt_ctx.ft_ctx.tcx;
+    let name = tcx.item_name(def_id).to_ident_string();
+
+    if target.is_none() && name == "unreachable" {
+        return Ok(ast::RawTerminator::Unreachable);
+    }
+
+    let func = match assumed::get_intrinsic_id_from_name(&name) {
+        Option::Some(id) => ast::FunId::Intrinsic(id),
+        Option::None => ast::FunId::OpaqueIntrinsic(name),
+    };
+
+    // Translate the target
+    let lval = translate_place(bt_ctx, destination);
+    let next_block = translate_call_target(bt_ctx, body, span, target)?;
+
+    // Translate the type parameters
+    let (region_args, type_args, const_generic_args) =
+        translate_subst_generic_args_in_body(bt_ctx, Option::None, substs)?;
+
+    // Translate the arguments
+    let args = translate_arguments(bt_ctx, Option::None, args);
+
+    Ok(ast::RawTerminator::Call {
+        func: ast::FnOperand::Regular(func),
+        region_args,
+        type_args,
+        const_generic_args,
+        args,
+        dest: lval,
+        target: next_block,
+        unwind: None,
+    })
+}
+
+/// Return `true` if a function call should be translated to a call to a
+/// primitive function.
+///
+/// This is also used in [crate::register].
+pub(crate) fn is_primitive_function_call<'tcx>(
+    tcx: TyCtxt<'tcx>,
+    def_id: DefId,
+    substs: SubstsRef<'tcx>,
+) -> bool {
+    // The panic functions don't have primitive identifiers: their calls
+    // are translated to [ast::RawTerminator::Panic]
+    if !def_id.is_local() {
+        let name = function_def_id_to_name(tcx, def_id);
+        if name.equals_ref_name(&assumed::PANIC_NAME)
+            || name.equals_ref_name(&assumed::BEGIN_PANIC_NAME)
+        {
+            return true;
+        }
+    }
+    get_primitive_function_id(tcx, def_id, substs).is_some()
+}
+
+/// Return the identifier of the primitive function called, if the call
+/// should be translated to a call to a primitive function.
+///
+/// Some of the primitive functions are actually trait methods (`Deref::deref`,
+/// `Index::index`, etc.): we consider those calls as primitive only if the
+/// type arguments are the proper types (`Box`, `Vec`, slices...), which also
+/// determine the primitive function (see [assumed::ArgTyKind]).
+///
+/// Must not be called on the panic functions.
+fn get_primitive_function_id<'tcx>(
+    tcx: TyCtxt<'tcx>,
+    def_id: DefId,
+    substs: SubstsRef<'tcx>,
+) -> Option<ast::AssumedFunId> {
+    if def_id.is_local() {
+        return Option::None;
+    }
+
+    let name = function_def_id_to_name(tcx, def_id);
+    assumed::function_to_info(&name)?;
+
+    let type_args: Vec<assumed::ArgTyKind> = substs
+        .types()
+        .map(|ty| match ty.kind() {
+            TyKind::Adt(adt_def, substs) => {
+                let name = type_def_id_to_name(tcx, adt_def.did());
+                match assumed::get_type_id_from_name(&name) {
+                    Option::Some(id) => assumed::ArgTyKind::Assumed(id),
+                    Option::None
+                        if name.equals_ref_name(&assumed::RANGE_NAME)
+                            && matches!(
+                                substs.type_at(0).kind(),
+                                TyKind::Uint(mir_ty::UintTy::Usize)
+                            ) =>
+                    {
+                        assumed::ArgTyKind::RangeUsize
+                    }
+                    Option::None if name.equals_ref_name(&assumed::RANGE_NAME) => {
+                        assumed::ArgTyKind::Range
+                    }
+                    Option::None if name.equals_ref_name(&assumed::RANGE_INCLUSIVE_NAME) => {
+                        assumed::ArgTyKind::RangeInclusive
+                    }
+                    Option::None if name.equals_ref_name(&assumed::SLICE_ITER_TY_NAME) => {
+                        assumed::ArgTyKind::SliceIter
+                    }
+                    Option::None if name.equals_ref_name(&assumed::SLICE_ITER_MUT_TY_NAME) => {
+                        assumed::ArgTyKind::SliceIterMut
+                    }
+                    Option::None if name.equals_ref_name(&assumed::RESULT_NAME) => {
+                        assumed::ArgTyKind::Result
+                    }
+                    Option::None => assumed::ArgTyKind::Other,
+                }
+            }
+            TyKind::Ref(_, ty, mutability) if ty.is_slice() => match mutability {
+                mir::Mutability::Not => assumed::ArgTyKind::SharedSliceRef,
+                mir::Mutability::Mut => assumed::ArgTyKind::MutSliceRef,
+            },
+            TyKind::Slice(_) => assumed::ArgTyKind::Slice,
+            TyKind::Array(..) => assumed::ArgTyKind::Array,
+            TyKind::Uint(mir_ty::UintTy::Usize) => assumed::ArgTyKind::Usize,
+            _ => assumed::ArgTyKind::Other,
+        })
+        .collect();
+    assumed::get_fun_id_from_name(&name, &type_args)
+}
+
+/// The result of [resolve_function_call]
+pub(crate) enum ResolvedCall<'tcx> {
+    /// We know which function is called: it is either not a trait method, or
+    /// a trait method whose implementation we could resolve.
+    Fun(DefId, SubstsRef<'tcx>),
+    /// We couldn't resolve the trait method implementation.
+    TraitMethod {
+        trait_id: DefId,
+        method: ast::TraitItemName,
+        impl_source: ast::TraitImplSource,
+    },
+}
+
+/// Statically resolve a call to a trait method to the function implementing
+/// this method, by using the rustc instance resolution. This is possible only
+/// if the implementation doesn't depend on the generic parameters of the caller.
+///
+/// For instance, in the following, we resolve the call to `to_u64` to a call
+/// to the method defined in the implementation of `ToU64` for `u32`:
+/// ```text
+/// fn f(x: u32) -> u64 {
+///   x.to_u64()
+/// }
+/// ```
+///
+/// The resolution may fail (for instance, if the trait bounds are ill-formed):
+/// in this case we report an error at `span`.
+///
+/// This is also used in [crate::register].
+pub(crate) fn resolve_function_call<'tcx>(
+    sess: &Session,
+    tcx: TyCtxt<'tcx>,
+    span: Span,
+    caller_id: DefId,
+    def_id: DefId,
+    substs: SubstsRef<'tcx>,
+) -> Result<ResolvedCall<'tcx>> {
+    // Only the trait methods need to be resolved
+    let trait_id = match tcx.trait_of_item(def_id) {
+        Option::None => return Ok(ResolvedCall::Fun(def_id, substs)),
+        Option::Some(trait_id) => trait_id,
+    };
+    let method = tcx.item_name(def_id).to_ident_string();
+
+    let param_env = tcx.param_env(caller_id);
+    let resolved = match mir_ty::Instance::resolve(tcx, param_env, def_id, substs) {
+        Ok(Option::Some(instance)) => match instance.def {
+            mir_ty::InstanceDef::Item(def) => {
+                // Note that if the method is not overriden by the implementation,
+                // `def` is the method provided by the trait declaration.
+                ResolvedCall::Fun(def.did, instance.substs)
+            }
+            mir_ty::InstanceDef::Virtual(_, _) => {
+                // The receiver is a trait object: this is a virtual call
+                ResolvedCall::TraitMethod {
+                    trait_id,
+                    method,
+                    impl_source: ast::TraitImplSource::Dyn,
+                }
+            }
+            mir_ty::InstanceDef::Intrinsic(_)
+            | mir_ty::InstanceDef::VTableShim(_)
+            | mir_ty::InstanceDef::ReifyShim(_)
+            | mir_ty::InstanceDef::FnPtrShim(_, _)
+            | mir_ty::InstanceDef::ClosureOnceShim { .. }
+            | mir_ty::InstanceDef::DropGlue(_, _)
+            | mir_ty::InstanceDef::CloneShim(_, _) => ResolvedCall::TraitMethod {
+                trait_id,
+                method,
+                impl_source: ast::TraitImplSource::Builtin,
+            },
+        },
+        Ok(Option::None) => {
+            // The implementation depends on the generic parameters
+            ResolvedCall::TraitMethod {
+                trait_id,
+                method,
+                impl_source: ast::TraitImplSource::Param,
+            }
+        }
+        Err(_) => {
+            span_err(
+                sess,
+                span,
+                &format!(
+                    "Could not resolve the implementation of the trait method {}",
+                    function_def_id_to_name(tcx, def_id)
+                ),
+            );
+            return Err(());
+        }
+    };
+    Ok(resolved)
+}
+
+/// Translate a parameter substitution used inside a function body.
+///
+/// Note that the regions parameters are expected to have been erased.
+fn translate_subst_generic_args_in_body<'tcx, 'ctx, 'ctx1>(
+    bt_ctx: &BodyTransContext<'tcx, 'ctx, 'ctx1>,
+    used_args: Option<Vec<bool>>,
     substs: &rustc_middle::ty::subst::InternalSubsts<'tcx>,
-) -> Result<(Vec<ty::ErasedRegion>, Vec<ty::ETy>)> {
+) -> Result<(Vec<ty::ErasedRegion>, Vec<ty::ETy>, Vec<ty::ConstGeneric>)> {
     let substs: Vec<rustc_middle::ty::subst::GenericArg<'tcx>> = match used_args {
         Option::None => substs.iter().collect(),
         Option::Some(used_args) => {
@@ -1971,6 +2937,7 @@ fn translate_subst_generic_args_in_body<'tcx, 'ctx, 'ctx1>(
 
     let mut t_args_regions = Vec::new();
     let mut t_args_tys = Vec::new();
+    let mut t_args_cgs = Vec::new();
     for param in substs.iter() {
         match param.unpack() {
             rustc_middle::ty::subst::GenericArgKind::Type(param_ty) => {
@@ -1981,13 +2948,13 @@ fn translate_subst_generic_args_in_body<'tcx, 'ctx, 'ctx1>(
             rustc_middle::ty::subst::GenericArgKind::Lifetime(region) => {
                 t_args_regions.push(translate_erased_region(region.kind()));
             }
-            rustc_middle::ty::subst::GenericArgKind::Const(_) => {
-                unimplemented!();
+            rustc_middle::ty::subst::GenericArgKind::Const(c) => {
+                t_args_cgs.push(translate_const_generic(bt_ctx, &c)?);
             }
         }
     }
 
-    Ok((t_args_regions, t_args_tys))
+    Ok((t_args_regions, t_args_tys, t_args_cgs))
 }
 
 /// Translate a parameter substitution used inside a function body.
@@ -2043,27 +3010,57 @@ fn translate_arguments<'tcx, 'ctx, 'ctx1>(
     t_args
 }
 
+/// Translate the arguments of a call to a closure through one of the `Fn`
+/// traits (`Fn::call`, `FnMut::call_mut`, `FnOnce::call_once`).
+///
+/// Those methods take the closure arguments in a tuple, while the closure
+/// itself receives them one by one: we untuple the last argument.
+fn translate_closure_call_arguments<'tcx>(
+    bt_ctx: &BodyTransContext<'tcx, '_, '_>,
+    body: &mir::Body<'tcx>,
+    args: &Vec<Operand<'tcx>>,
+) -> Vec<e::Operand> {
+    let tcx = bt_ctx.ft_ctx.tcx;
+    assert!(args.len() == 2);
+
+    // The state (or a borrow of the state)
+    let mut t_args = translate_arguments(bt_ctx, Option::None, &vec![args[0].clone()]);
+
+    // The tupled arguments
+    let tupled_args = &args[1];
+    match tupled_args {
+        mir::Operand::Move(place) | mir::Operand::Copy(place) => {
+            let tys = match tupled_args.ty(&body.local_decls, tcx).kind() {
+                TyKind::Tuple(tys) => *tys,
+                _ => unreachable!(),
+            };
+            for (i, ty) in tys.iter().enumerate() {
+                let place = tcx.mk_place_field(*place, mir::Field::from_usize(i), ty);
+                t_args.push(translate_operand(bt_ctx, &mir::Operand::Move(place)));
+            }
+        }
+        mir::Operand::Constant(_) => {
+            // The arguments are given as a constant: this happens when there
+            // are no arguments (the tuple is the unit value)
+            assert!(tupled_args.ty(&body.local_decls, tcx).is_unit());
+        }
+    }
+
+    t_args
+}
+
 /// Translate a call to a function considered primitive and which is not:
 /// panic, begin_panic, box_free (those have a *very* special treatment).
 fn translate_primitive_function_call(
-    tcx: TyCtxt<'_>,
-    def_id: DefId,
+    aid: ast::AssumedFunId,
     region_args: Vec<ty::ErasedRegion>,
     type_args: Vec<ty::ETy>,
+    const_generic_args: Vec<ty::ConstGeneric>,
     args: Vec<e::Operand>,
     dest: e::Place,
     target: ast::BlockId::Id,
 ) -> Result<ast::RawTerminator> {
-    trace!("- def_id: {:?}", def_id,);
-
-    // Translate the function name
-    let name = function_def_id_to_name(tcx, def_id);
-    trace!("name: {}", name);
-
-    // Check if the function has primitive support, by trying to look up
-    // its primitive identifier
-    let aid = assumed::get_fun_id_from_name(&name).unwrap();
-    // The function is considered primitive
+    trace!("- aid: {:?}", aid);
 
     // Translate the function call
     // Note that some functions are actually traits (deref, index, etc.):
@@ -2083,13 +3080,45 @@ fn translate_primitive_function_call(
         | ast::AssumedFunId::VecNew
         | ast::AssumedFunId::VecPush
         | ast::AssumedFunId::VecInsert
-        | ast::AssumedFunId::VecLen => Ok(ast::RawTerminator::Call {
-            func: ast::FunId::Assumed(aid),
+        | ast::AssumedFunId::VecLen
+        | ast::AssumedFunId::SliceLen
+        | ast::AssumedFunId::SliceGet
+        | ast::AssumedFunId::SliceGetMut
+        | ast::AssumedFunId::SliceSwap
+        | ast::AssumedFunId::SliceSplitAt
+        | ast::AssumedFunId::SliceSplitAtMut
+        | ast::AssumedFunId::SliceIter
+        | ast::AssumedFunId::SliceIterMut
+        | ast::AssumedFunId::SliceToVec
+        | ast::AssumedFunId::ArrayToSliceShared
+        | ast::AssumedFunId::ArrayToSliceMut
+        | ast::AssumedFunId::CellNew
+        | ast::AssumedFunId::CellGet
+        | ast::AssumedFunId::CellSet
+        | ast::AssumedFunId::RefCellNew
+        | ast::AssumedFunId::RefCellBorrow
+        | ast::AssumedFunId::RefCellBorrowMut
+        | ast::AssumedFunId::RcNew
+        | ast::AssumedFunId::ArcNew
+        | ast::AssumedFunId::ResultBranch
+        | ast::AssumedFunId::ResultFromResidual
+        | ast::AssumedFunId::OptionBranch
+        | ast::AssumedFunId::OptionFromResidual
+        | ast::AssumedFunId::IntoIter
+        | ast::AssumedFunId::RangeNext
+        | ast::AssumedFunId::RangeInclusiveNew
+        | ast::AssumedFunId::RangeInclusiveNext
+        | ast::AssumedFunId::SliceIterNext
+        | ast::AssumedFunId::SliceIterMutNext
+        | ast::AssumedFunId::User(_) => Ok(ast::RawTerminator::Call {
+            func: ast::FnOperand::Regular(ast::FunId::Assumed(aid)),
             region_args,
             type_args,
+            const_generic_args,
             args,
             dest,
             target,
+            unwind: None,
         }),
         ast::AssumedFunId::BoxDeref | ast::AssumedFunId::BoxDerefMut => {
             translate_box_deref(aid, region_args, type_args, args, dest, target)
@@ -2097,6 +3126,22 @@ fn translate_primitive_function_call(
         ast::AssumedFunId::VecIndex | ast::AssumedFunId::VecIndexMut => {
             translate_vec_index(aid, region_args, type_args, args, dest, target)
         }
+        ast::AssumedFunId::RcClone
+        | ast::AssumedFunId::RcDeref
+        | ast::AssumedFunId::ArcClone
+        | ast::AssumedFunId::ArcDeref => {
+            translate_rc_or_arc_method(aid, region_args, type_args, args, dest, target)
+        }
+        ast::AssumedFunId::SliceIndexShared
+        | ast::AssumedFunId::SliceIndexMut
+        | ast::AssumedFunId::SliceSubsliceShared
+        | ast::AssumedFunId::SliceSubsliceMut
+        | ast::AssumedFunId::ArrayIndexShared
+        | ast::AssumedFunId::ArrayIndexMut
+        | ast::AssumedFunId::ArraySubsliceShared
+        | ast::AssumedFunId::ArraySubsliceMut => {
+            translate_slice_index(aid, region_args, type_args, args, dest, target)
+        }
         ast::AssumedFunId::BoxFree => {
             unreachable!();
         }
@@ -2132,12 +3177,54 @@ fn translate_box_deref(
     let type_args = vec![boxed_ty.clone()];
 
     Ok(ast::RawTerminator::Call {
-        func: ast::FunId::Assumed(aid),
+        func: ast::FnOperand::Regular(ast::FunId::Assumed(aid)),
+        region_args,
+        type_args,
+        const_generic_args: vec![],
+        args,
+        dest,
+        target,
+        unwind: None,
+    })
+}
+
+/// Translate `core::clone::Clone::clone` or `core::ops::deref::Deref::deref`
+/// applied on `Rc` or `Arc`. We need a custom function because those are traits.
+fn translate_rc_or_arc_method(
+    aid: ast::AssumedFunId,
+    region_args: Vec<ty::ErasedRegion>,
+    type_args: Vec<ty::ETy>,
+    args: Vec<e::Operand>,
+    dest: e::Place,
+    target: ast::BlockId::Id,
+) -> Result<ast::RawTerminator> {
+    // Check the arguments
+    assert!(region_args.is_empty());
+    assert!(type_args.len() == 1);
+    assert!(args.len() == 1);
+
+    // Retrieve the shared value
+    let arg_ty = type_args.get(0).unwrap(); // should be `Rc<...>` or `Arc<...>`
+    let shared_ty = match arg_ty.as_rc_or_arc() {
+        Option::Some(ty) => ty,
+        Option::None => {
+            panic!(
+                "Clone/Deref trait applied with parameter {:?} while we expected a Rc<T> or an Arc<T>",
+                arg_ty
+            );
+        }
+    };
+    let type_args = vec![shared_ty.clone()];
+
+    Ok(ast::RawTerminator::Call {
+        func: ast::FnOperand::Regular(ast::FunId::Assumed(aid)),
         region_args,
         type_args,
+        const_generic_args: vec![],
         args,
         dest,
         target,
+        unwind: None,
     })
 }
 
@@ -2171,12 +3258,57 @@ fn translate_vec_index(
 
     let type_args = vec![arg_ty.clone()];
     Ok(ast::RawTerminator::Call {
-        func: ast::FunId::Assumed(aid),
+        func: ast::FnOperand::Regular(ast::FunId::Assumed(aid)),
         region_args,
         type_args,
+        const_generic_args: vec![],
         args,
         dest,
         target,
+        unwind: None,
+    })
+}
+
+/// Translate `core::ops::index::{Index,IndexMut}::{index,index_mut}`
+/// applied on slices and arrays. We need a custom function because it is a
+/// trait.
+///
+/// For instance, `<[u32; 4] as Index<Range<usize>>>::index` is translated
+/// to `array_subslice<u32, 4>`.
+fn translate_slice_index(
+    aid: ast::AssumedFunId,
+    region_args: Vec<ty::ErasedRegion>,
+    type_args: Vec<ty::ETy>,
+    args: Vec<e::Operand>,
+    dest: e::Place,
+    target: ast::BlockId::Id,
+) -> Result<ast::RawTerminator> {
+    // Check the arguments
+    assert!(region_args.is_empty());
+    assert!(type_args.len() == 1);
+    assert!(args.len() == 2);
+
+    // Retrieve the type of the elements, and the length of the array
+    let (type_args, const_generic_args) = match type_args.get(0).unwrap() {
+        ty::Ty::Slice(ty) => (vec![(**ty).clone()], vec![]),
+        ty::Ty::Array(ty, len) => (vec![(**ty).clone()], vec![len.clone()]),
+        arg_ty => {
+            panic!(
+            "Index/IndexMut trait applied with parameter {:?} while we expected a slice or an array",
+            arg_ty
+        );
+        }
+    };
+
+    Ok(ast::RawTerminator::Call {
+        func: ast::FnOperand::Regular(ast::FunId::Assumed(aid)),
+        region_args,
+        type_args,
+        const_generic_args,
+        args,
+        dest,
+        target,
+        unwind: None,
     })
 }
 
@@ -2195,7 +3327,6 @@ pub(crate) fn check_impl_item(impl_item: &rustc_hir::Impl<'_>) {
     assert!(impl_item.defaultness == Defaultness::Final);
     // Note sure what this is about
     assert!(impl_item.constness == Constness::NotConst);
-    // assert!(impl_item.of_trait.is_none()); // We don't support traits for now // patch
 }
 
 /// Translate a function's signature, and initialize a body translation context
@@ -2277,8 +3408,21 @@ fn translate_function_signature<'tcx, 'ctx, 'ctx1>(
                 let name = translate_region_name(&region);
                 bt_ctx.push_region(*region, name);
             }
-            rustc_middle::ty::subst::GenericArgKind::Const(_) => {
-                unimplemented!();
+            rustc_middle::ty::subst::GenericArgKind::Const(c) => {
+                // This constant should be a param
+                match c.kind() {
+                    mir_ty::ConstKind::Param(param) => {
+                        let ty = translate_types::translate_const_generic_ty(&c.ty());
+                        bt_ctx.push_const_generic_var(
+                            param.index,
+                            ty,
+                            param.name.to_ident_string(),
+                        );
+                    }
+                    _ => {
+                        unreachable!();
+                    }
+                }
             }
         }
     }
@@ -2308,6 +3452,13 @@ fn translate_function_signature<'tcx, 'ctx, 'ctx1>(
         "# Type variables:\n{}",
         iterator_to_string(&|x: &ty::TypeVar| x.to_string(), bt_ctx.type_vars.iter())
     );
+    trace!(
+        "# Const generic variables:\n{}",
+        iterator_to_string(
+            &|x: &ty::ConstGenericVar| x.to_string(),
+            bt_ctx.const_generic_vars.iter()
+        )
+    );
 
     // Now that we instantiated all the binders and introduced identifiers for
     // all the variables, we can translate the function's signature.
@@ -2330,6 +3481,178 @@ fn translate_function_signature<'tcx, 'ctx, 'ctx1>(
         num_early_bound_regions: late_bound_regions.len(),
         regions_hierarchy: rh::RegionGroups::new(), // Hierarchy not yet computed
         type_params: bt_ctx.type_vars.clone(),
+        const_generic_params: bt_ctx.const_generic_vars.clone(),
+        inputs,
+        output,
+    };
+
+    // Analyze the signature to compute the regions hierarchy
+    let regions_hierarchy = rh::compute_regions_hierarchy_for_sig(types_constraints, &sig);
+    let sig = ast::FunSig {
+        regions_hierarchy,
+        ..sig
+    };
+
+    (bt_ctx, sig)
+}
+
+/// Translate the signature of a closure. This is similar to
+/// [translate_function_signature], but we have to retrieve the information
+/// from the closure substitution.
+///
+/// The closures take as first input their state (the structure of captured
+/// variables, see [crate::translate_types]), possibly borrowed depending on
+/// the closure kind. The state has the type parameters of the parent function,
+/// and one region parameter per region appearing in the types of the captured
+/// variables: those regions are parameters of the closure.
+///
+/// Also note that in MIR, the signature of a closure takes its arguments
+/// in a tuple, while the body receives them one by one: we untuple them.
+fn translate_closure_signature<'tcx, 'ctx, 'ctx1>(
+    types_constraints: &TypesConstraintsMap,
+    decl_ctx: &'ctx DeclTransContext<'tcx, 'ctx1>,
+    def_id: DefId,
+) -> (BodyTransContext<'tcx, 'ctx, 'ctx1>, ast::FunSig) {
+    let tcx = decl_ctx.tcx;
+    let parent_id = tcx.typeck_root_def_id(def_id);
+
+    // We need a body translation context to keep track of all the variables
+    let mut bt_ctx = BodyTransContext::new(def_id, decl_ctx);
+
+    // **Sanity checks on the HIR**
+    generics::check_function_generics(tcx, def_id);
+
+    let substs = match tcx.type_of(def_id).kind() {
+        TyKind::Closure(_, substs) => *substs,
+        _ => unreachable!(),
+    };
+    let closure = substs.as_closure();
+
+    // The type and const generic parameters are the ones of the parent function
+    let parent_substs = rustc_middle::ty::subst::InternalSubsts::identity_for_item(tcx, parent_id);
+    for param_ty in parent_substs.types() {
+        match param_ty.kind() {
+            TyKind::Param(param_ty) => {
+                bt_ctx.push_type_var(param_ty.index, param_ty.name.to_ident_string());
+            }
+            _ => {
+                unreachable!();
+            }
+        }
+    }
+    for c in parent_substs.consts() {
+        match c.kind() {
+            mir_ty::ConstKind::Param(param) => {
+                let ty = translate_types::translate_const_generic_ty(&c.ty());
+                bt_ctx.push_const_generic_var(param.index, ty, param.name.to_ident_string());
+            }
+            _ => {
+                unreachable!();
+            }
+        }
+    }
+    let type_args: Vector<ty::RTy> = bt_ctx
+        .type_vars
+        .iter()
+        .map(|v| ty::Ty::TypeVar(v.index))
+        .collect();
+    let cg_args: Vector<ty::ConstGeneric> = bt_ctx
+        .const_generic_vars
+        .iter()
+        .map(|v| ty::ConstGeneric::Var(v.index))
+        .collect();
+
+    // The region used to borrow the state, if the closure is not `FnOnce`
+    let env_region = match closure.kind() {
+        mir_ty::ClosureKind::Fn | mir_ty::ClosureKind::FnMut => {
+            let r = mir_ty::RegionKind::ReFree(mir_ty::FreeRegion {
+                scope: def_id,
+                bound_region: mir_ty::BoundRegionKind::BrEnv,
+            });
+            Option::Some(bt_ctx.push_region(r, None))
+        }
+        mir_ty::ClosureKind::FnOnce => Option::None,
+    };
+
+    // The regions of the state
+    let (_, state_regions) = translate_types::get_closure_upvar_tys(tcx, def_id, substs, true);
+    let num_state_regions = state_regions.len();
+    let region_args: Vector<ty::Region<ty::RegionVarId::Id>> = state_regions
+        .into_iter()
+        .map(|r| ty::Region::Var(bt_ctx.push_region(r, None)))
+        .collect();
+
+    // The state type
+    let state_id = *decl_ctx.ordered.type_rid_to_id.get(&def_id).unwrap();
+    let state_ty = ty::Ty::Adt(
+        ty::TypeId::Adt(state_id),
+        region_args,
+        type_args,
+        Box::new(cg_args),
+    );
+    let state_ty = match env_region {
+        Option::None => state_ty,
+        Option::Some(r) => {
+            let kind = if closure.kind() == mir_ty::ClosureKind::Fn {
+                ty::RefKind::Shared
+            } else {
+                ty::RefKind::Mut
+            };
+            ty::Ty::Ref(ty::Region::Var(r), Box::new(state_ty), kind)
+        }
+    };
+
+    // Instantiate the late-bound regions of the signature
+    let (signature, late_bound_regions) =
+        generics::replace_late_bound_regions(tcx, closure.sig(), def_id);
+    for (_, region) in &late_bound_regions {
+        let name = translate_region_name(region);
+        bt_ctx.push_region(**region, name);
+    }
+
+    // The other regions have been erased: we introduce fresh regions for them
+    let mut erased_regions = vec![];
+    let signature = tcx.fold_regions(signature, |r, _| {
+        if r.is_erased() {
+            let index = (num_state_regions + erased_regions.len()) as u32;
+            let r = tcx.mk_region(mir_ty::RegionKind::ReFree(mir_ty::FreeRegion {
+                scope: parent_id,
+                bound_region: mir_ty::BoundRegionKind::BrAnon(index, None),
+            }));
+            erased_regions.push(*r);
+            r
+        } else {
+            r
+        }
+    });
+    for r in erased_regions {
+        bt_ctx.push_region(r, None);
+    }
+
+    // Translate the inputs: the state, followed by the untupled arguments
+    let mut inputs: Vec<ty::RTy> = vec![state_ty];
+    match signature.inputs()[0].kind() {
+        TyKind::Tuple(tys) => {
+            for ty in tys.iter() {
+                inputs.push(translate_sig_ty(&bt_ctx, &ty).unwrap());
+            }
+        }
+        _ => unreachable!(),
+    }
+    let output = translate_sig_ty(&bt_ctx, &signature.output()).unwrap();
+
+    trace!(
+        "# Input variables types:\n{}",
+        iterator_to_string(&|x| bt_ctx.format_object(x), inputs.iter())
+    );
+    trace!("# Output variable type:\n{}", bt_ctx.format_object(&output));
+
+    let sig = ast::FunSig {
+        region_params: bt_ctx.regions.clone(),
+        num_early_bound_regions: late_bound_regions.len(),
+        regions_hierarchy: rh::RegionGroups::new(), // Hierarchy not yet computed
+        type_params: bt_ctx.type_vars.clone(),
+        const_generic_params: bt_ctx.const_generic_vars.clone(),
         inputs,
         output,
     };
@@ -2346,13 +3669,13 @@ fn translate_function_signature<'tcx, 'ctx, 'ctx1>(
 
 fn translate_body(
     mut bt_ctx: BodyTransContext<'_, '_, '_>,
-    local_id: LocalDefId,
+    def_id: DefId,
     arg_count: usize,
 ) -> Result<ast::ExprBody> {
     let sess = bt_ctx.ft_ctx.sess;
     let tcx = bt_ctx.ft_ctx.tcx;
 
-    let body = get_mir_for_def_id_and_level(tcx, local_id, bt_ctx.ft_ctx.mir_level);
+    let body = get_mir_for_def_id(tcx, def_id, bt_ctx.ft_ctx.mir_level);
 
     // Compute the meta information
     let meta = meta::get_meta_from_rspan(sess, &bt_ctx.ft_ctx.ordered.file_to_id, body.span);
@@ -2361,6 +3684,9 @@ fn translate_body(
     trace!("Translating the body locals");
     translate_body_locals(&mut bt_ctx, body)?;
 
+    // Look for the casts of enumerations
+    compute_discriminant_casts(&mut bt_ctx, body);
+
     // Translate the expression body
     trace!("Translating the expression body");
     translate_transparent_expression_body(&mut bt_ctx, body)?;
@@ -2383,6 +3709,164 @@ fn translate_body(
     })
 }
 
+/// Return true if the cast involves raw pointers. See [e::CastKind::RawPtr].
+fn is_ptr_cast(cast_kind: &mir::CastKind) -> bool {
+    matches!(
+        cast_kind,
+        mir::CastKind::PtrToPtr
+            | mir::CastKind::PointerExposeAddress
+            | mir::CastKind::PointerFromExposedAddress
+            | mir::CastKind::Pointer(
+                mir_ty::adjustment::PointerCast::MutToConstPointer
+                    | mir_ty::adjustment::PointerCast::ArrayToPointer,
+            )
+    )
+}
+
+/// Return true if the function is declared `unsafe`, or if its body (if we
+/// translate it) performs unsafe operations: operations on raw pointers
+/// (creating, casting or dereferencing a raw pointer) or calls to unsafe
+/// functions.
+fn function_is_unsafe<'tcx>(tcx: TyCtxt<'tcx>, def_id: DefId, body: Option<&Body<'tcx>>) -> bool {
+    // The closures can't be declared `unsafe`
+    if !tcx.is_closure(def_id) && tcx.fn_sig(def_id).unsafety() == rustc_hir::Unsafety::Unsafe {
+        return true;
+    }
+
+    struct UnsafeOpsVisitor<'a, 'tcx> {
+        tcx: TyCtxt<'tcx>,
+        body: &'a Body<'tcx>,
+        found: bool,
+    }
+
+    impl<'a, 'tcx> mir::visit::Visitor<'tcx> for UnsafeOpsVisitor<'a, 'tcx> {
+        fn visit_rvalue(&mut self, rvalue: &mir::Rvalue<'tcx>, location: mir::Location) {
+            match rvalue {
+                mir::Rvalue::AddressOf(_, _) => self.found = true,
+                mir::Rvalue::Cast(cast_kind, _, _) if is_ptr_cast(cast_kind) => self.found = true,
+                _ => (),
+            }
+            self.super_rvalue(rvalue, location);
+        }
+
+        fn visit_place(
+            &mut self,
+            place: &Place<'tcx>,
+            context: mir::visit::PlaceContext,
+            location: mir::Location,
+        ) {
+            // Look for dereferences of raw pointers
+            if place.iter_projections().any(|(base, elem)| {
+                elem == mir::ProjectionElem::Deref
+                    && base.ty(self.body, self.tcx).ty.is_unsafe_ptr()
+            }) {
+                self.found = true;
+            }
+            self.super_place(place, context, location);
+        }
+
+        fn visit_terminator(&mut self, terminator: &Terminator<'tcx>, location: mir::Location) {
+            if let TerminatorKind::Call { func, .. } = &terminator.kind {
+                let fun_ty = func.ty(self.body, self.tcx);
+                if fun_ty.is_fn()
+                    && fun_ty.fn_sig(self.tcx).unsafety() == rustc_hir::Unsafety::Unsafe
+                {
+                    self.found = true;
+                }
+            }
+            self.super_terminator(terminator, location);
+        }
+    }
+
+    match body {
+        Option::None => false,
+        Option::Some(body) => {
+            use mir::visit::Visitor;
+            let mut visitor = UnsafeOpsVisitor {
+                tcx,
+                body,
+                found: false,
+            };
+            visitor.visit_body(body);
+            visitor.found
+        }
+    }
+}
+
+/// Look for the discriminant reads used to cast enumerations to integers,
+/// and for the `assume` hints introduced by those casts.
+/// See [BodyTransContext::discriminant_casts].
+fn compute_discriminant_casts<'tcx>(
+    bt_ctx: &mut BodyTransContext<'tcx, '_, '_>,
+    body: &Body<'tcx>,
+) {
+    use std::ops::Deref;
+
+    // Collect the discriminant reads, and the locals which are cast
+    let mut discriminants: im::OrdMap<mir::Local, Place<'tcx>> = im::OrdMap::new();
+    let mut cast_locals: im::OrdSet<mir::Local> = im::OrdSet::new();
+    // Collect the conditions given to `assume`, and the locals they are
+    // computed from
+    let mut assumed: Vec<mir::Local> = Vec::new();
+    let mut comparisons: im::OrdMap<mir::Local, Vec<mir::Local>> = im::OrdMap::new();
+    for block in body.basic_blocks.iter() {
+        for statement in block.statements.iter() {
+            match &statement.kind {
+                StatementKind::Assign(assign) => match assign.deref() {
+                    (place, mir::Rvalue::Discriminant(enum_place)) => {
+                        if let Some(local) = place.as_local() {
+                            discriminants.insert(local, *enum_place);
+                        }
+                    }
+                    (_, mir::Rvalue::Cast(_, op, _)) => {
+                        if let Some(local) = op.place().and_then(|p| p.as_local()) {
+                            cast_locals.insert(local);
+                        }
+                    }
+                    (place, mir::Rvalue::BinaryOp(_, ops)) => {
+                        if let Some(local) = place.as_local() {
+                            let operands = [&ops.0, &ops.1]
+                                .iter()
+                                .filter_map(|op| op.place().and_then(|p| p.as_local()))
+                                .collect();
+                            comparisons.insert(local, operands);
+                        }
+                    }
+                    _ => (),
+                },
+                StatementKind::Intrinsic(intrinsic) => {
+                    if let mir::NonDivergingIntrinsic::Assume(op) = intrinsic.deref() {
+                        if let Some(local) = op.place().and_then(|p| p.as_local()) {
+                            assumed.push(local);
+                        }
+                    }
+                }
+                _ => (),
+            }
+        }
+    }
+
+    // Only keep the discriminants which are cast
+    bt_ctx.discriminant_casts = discriminants
+        .clone()
+        .into_iter()
+        .filter(|(local, _)| cast_locals.contains(local))
+        .collect();
+    bt_ctx.discriminant_reads = discriminants;
+
+    // Only keep the `assume` hints which constrain those discriminants
+    bt_ctx.assume_locals = assumed
+        .into_iter()
+        .filter(|local| {
+            comparisons.get(local).map_or(false, |operands| {
+                operands
+                    .iter()
+                    .any(|op| bt_ctx.discriminant_casts.contains_key(op))
+            })
+        })
+        .collect();
+}
+
 /// Translate one function.
 fn translate_function(
     sess: &Session,
@@ -2393,6 +3877,7 @@ fn translate_function(
     fun_defs: &ast::FunDecls,
     global_defs: &ast::GlobalDecls,
     mir_level: MirLevel,
+    unwind: bool,
     def_id: ast::FunDeclId::Id,
 ) -> Result<ast::FunDecl> {
     trace!("{:?}", def_id);
@@ -2403,6 +3888,10 @@ fn translate_function(
     // Compute the meta information
     let meta = meta::get_meta_from_rid(sess, tcx, &ordered.file_to_id, info.rid);
 
+    // The bodies of the external functions come from the crate metadata,
+    // and are thus optimized (see [get_mir_for_def_id])
+    let mir_level = get_mir_level_for_def_id(info.rid, mir_level);
+
     // Initialize the function translation context
     let ft_ctx = DeclTransContext {
         sess,
@@ -2412,6 +3901,7 @@ fn translate_function(
         fun_defs,
         global_defs,
         mir_level,
+        unwind,
     };
 
     // Translate the function name
@@ -2421,25 +3911,35 @@ fn translate_function(
     // at the same time (the signature gives us the region and type parameters,
     // that we put in the translation context).
     trace!("Translating function signature");
-    let (bt_ctx, signature) = translate_function_signature(types_constraints, &ft_ctx, info.rid);
+    let (bt_ctx, signature) = if tcx.is_closure(info.rid) {
+        translate_closure_signature(types_constraints, &ft_ctx, info.rid)
+    } else {
+        translate_function_signature(types_constraints, &ft_ctx, info.rid)
+    };
 
-    // Check if the type is opaque or transparent
-    let body = if !info.is_transparent || !info.is_local() {
+    // Check if the function is opaque or transparent. Note that the external
+    // functions are opaque, unless we extract their bodies (see
+    // [crate::register::CrateInfo]).
+    let body = if !info.is_transparent {
         Option::None
     } else {
-        Option::Some(translate_body(
-            bt_ctx,
-            info.rid.expect_local(),
-            signature.inputs.len(),
-        )?)
+        Option::Some(translate_body(bt_ctx, info.rid, signature.inputs.len())?)
     };
 
+    // Check if the function is unsafe: we need to explore the body, if we
+    // translated it
+    let mir_body = body
+        .as_ref()
+        .map(|_| get_mir_for_def_id(tcx, info.rid, mir_level));
+    let is_unsafe = function_is_unsafe(tcx, info.rid, mir_body);
+
     // Return the new function
     Ok(ast::FunDecl {
         meta,
         def_id,
         name,
         signature,
+        is_unsafe,
         body,
     })
 }
@@ -2501,6 +4001,7 @@ fn translate_global(
     fun_defs: &ast::FunDecls,
     global_defs: &ast::GlobalDecls,
     mir_level: MirLevel,
+    unwind: bool,
     def_id: ast::GlobalDeclId::Id,
 ) -> Result<ast::GlobalDecl> {
     trace!("{:?}", def_id);
@@ -2520,18 +4021,26 @@ fn translate_global(
         fun_defs,
         global_defs,
         mir_level,
+        unwind,
     };
 
     // Translate the global name
     let name = global_def_id_to_name(tcx, info.rid);
 
     trace!("Translating global type");
-    let mir_ty = tcx.type_of(info.rid);
+    // The type may contain `'static` regions (for instance: `&'static str`):
+    // we erase them, as in the bodies
+    let mir_ty = tcx.erase_regions(tcx.type_of(info.rid));
 
     let type_ = {
         let ty_ctx = TypeTransContext::new(ft_ctx.type_defs, ft_ctx.ordered);
-        let empty = im::OrdMap::new();
-        translate_types::translate_ety(tcx, &ty_ctx, &empty, &mir_ty)?
+        translate_types::translate_ety(
+            tcx,
+            &ty_ctx,
+            &im::OrdMap::new(),
+            &im::OrdMap::new(),
+            &mir_ty,
+        )?
     };
 
     let bt_ctx = BodyTransContext::new(info.rid, &ft_ctx);
@@ -2540,7 +4049,7 @@ fn translate_global(
         (true, false) => Option::None,
 
         // It's a local and transparent global: we extract its body as for functions.
-        (true, true) => Option::Some(translate_body(bt_ctx, info.rid.expect_local(), 0)?),
+        (true, true) => Option::Some(translate_body(bt_ctx, info.rid, 0)?),
 
         // It's an external global.
         // The fact that it is listed among the declarations to extract means that
@@ -2595,6 +4104,7 @@ pub fn translate_functions(
     types_constraints: &TypesConstraintsMap,
     type_defs: &ty::TypeDecls,
     mir_level: MirLevel,
+    unwind: bool,
 ) -> Result<(ast::FunDecls, ast::GlobalDecls)> {
     let mut fun_defs = ast::FunDecls::new();
     let mut const_defs = ast::GlobalDecls::new();
@@ -2613,6 +4123,7 @@ pub fn translate_functions(
                     &fun_defs,
                     &const_defs,
                     mir_level,
+                    unwind,
                     *def_id,
                 )?;
                 // We have to make sure we translate the definitions in the
@@ -2631,6 +4142,7 @@ pub fn translate_functions(
                         &fun_defs,
                         &const_defs,
                         mir_level,
+                        unwind,
                         *def_id,
                     )?;
                     // We have to make sure we translate the definitions in the
@@ -2649,6 +4161,7 @@ pub fn translate_functions(
                     &fun_defs,
                     &const_defs,
                     mir_level,
+                    unwind,
                     *def_id,
                 )?;
                 // We have to make sure we translate the definitions in the
@@ -2667,6 +4180,7 @@ pub fn translate_functions(
                         &fun_defs,
                         &const_defs,
                         mir_level,
+                        unwind,
                         *def_id,
                     )?;
                     // We have to make sure we translate the definitions in the
@@ -2675,8 +4189,10 @@ pub fn translate_functions(
                     const_defs.push_back(const_def);
                 }
             }
-            DeclarationGroup::Type(_) => {
-                // Ignore the type declarations
+            DeclarationGroup::Type(_)
+            | DeclarationGroup::TraitDecl(_)
+            | DeclarationGroup::TraitImpl(_) => {
+                // Ignore the type and trait declarations
                 continue;
             }
         }
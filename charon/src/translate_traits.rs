@@ -0,0 +1,245 @@
+//! Translate the trait declarations and the trait implementations.
+//!
+//! The trait methods and the functions implementing them are translated
+//! in [crate::translate_functions_to_ullbc], like the other functions: we
+//! only link the trait items to the corresponding function identifiers.
+use crate::common::*;
+use crate::generics;
+use crate::id_vector::ToUsize;
+use crate::meta;
+use crate::names::{trait_def_id_to_name, trait_impl_def_id_to_name};
+use crate::reorder_decls::{DeclarationGroup, GDeclarationGroup};
+use crate::rust_to_local_ids::*;
+use crate::translate_types::{
+    translate_generics, translate_non_erased_region, translate_sig_ty, translate_substs,
+    TypeGenericsInfo, TypeTransContext,
+};
+use crate::types as ty;
+use crate::ullbc_ast as ast;
+use crate::ullbc_ast::{TraitDeclId, TraitImplId, TraitItemName};
+use rustc_hir::def_id::DefId;
+use rustc_middle::ty::{AssocKind, RegionKind, TyCtxt};
+use rustc_session::Session;
+
+/// Retrieve the methods defined in a trait declaration or in a trait
+/// implementation, together with the identifiers of the function declarations
+/// they were registered as.
+fn translate_methods(
+    tcx: TyCtxt,
+    decls: &OrderedDecls,
+    rid: DefId,
+) -> Vec<(TraitItemName, ast::FunDeclId::Id)> {
+    tcx.associated_items(rid)
+        .in_definition_order()
+        .filter(|item| item.kind == AssocKind::Fn)
+        .map(|item| {
+            let name = item.name.to_ident_string();
+            let id = *decls.fun_rid_to_id.get(&item.def_id).unwrap();
+            (name, id)
+        })
+        .collect()
+}
+
+/// Retrieve the associated types defined in a trait implementation, and
+/// translate their definitions.
+fn translate_impl_types<'tcx>(
+    tcx: TyCtxt<'tcx>,
+    trans_ctx: &TypeTransContext,
+    region_params_map: &im::OrdMap<RegionKind<'tcx>, ty::RegionVarId::Id>,
+    type_params_map: &im::OrdMap<u32, ty::RTy>,
+    const_generic_params_map: &im::OrdMap<u32, ty::ConstGeneric>,
+    rid: DefId,
+) -> Result<Vec<(TraitItemName, ty::RTy)>> {
+    let mut types = Vec::new();
+    for item in tcx.associated_items(rid).in_definition_order() {
+        if item.kind == AssocKind::Type {
+            let name = item.name.to_ident_string();
+            let ty = tcx.type_of(item.def_id);
+            let ty = translate_sig_ty(
+                tcx,
+                trans_ctx,
+                region_params_map,
+                type_params_map,
+                const_generic_params_map,
+                &ty,
+            )?;
+            types.push((name, ty));
+        }
+    }
+    Ok(types)
+}
+
+/// Translate a trait declaration.
+fn translate_trait_decl(
+    sess: &Session,
+    tcx: TyCtxt,
+    decls: &OrderedDecls,
+    trait_decls: &mut ast::TraitDecls,
+    trans_id: TraitDeclId::Id,
+) {
+    let info = decls
+        .decls_info
+        .get(&AnyDeclId::TraitDecl(trans_id))
+        .unwrap();
+    let rid = info.rid;
+
+    // Check and translate the generics - note that the first type parameter
+    // is `Self`
+    generics::check_trait_generics(tcx, rid);
+    let TypeGenericsInfo {
+        substs: _,
+        region_params,
+        region_params_map: _,
+        type_params,
+        type_params_map: _,
+        const_generic_params,
+        const_generic_params_map: _,
+    } = translate_generics(tcx, rid);
+    // The associated types
+    let types = tcx
+        .associated_items(rid)
+        .in_definition_order()
+        .filter(|item| item.kind == AssocKind::Type)
+        .map(|item| item.name.to_ident_string())
+        .collect();
+
+    // The methods are only exposed for the transparent traits
+    let methods = if info.is_local() && info.is_transparent {
+        Some(translate_methods(tcx, decls, rid))
+    } else {
+        None
+    };
+
+    let trait_decl = ast::TraitDecl {
+        def_id: trans_id,
+        meta: meta::get_meta_from_rid(sess, tcx, &decls.file_to_id, rid),
+        name: trait_def_id_to_name(tcx, rid),
+        region_params: ty::RegionVarId::Vector::from(region_params),
+        type_params: ty::TypeVarId::Vector::from(type_params),
+        const_generic_params: ty::ConstGenericVarId::Vector::from(const_generic_params),
+        types,
+        methods,
+    };
+
+    // Sanity check: the declarations are translated in order
+    assert!(trait_decls.len() == trans_id.to_usize());
+    trait_decls.push_back(trait_decl);
+}
+
+/// Translate a trait implementation.
+fn translate_trait_impl(
+    sess: &Session,
+    tcx: TyCtxt,
+    decls: &OrderedDecls,
+    trans_ctx: &TypeTransContext,
+    trait_impls: &mut ast::TraitImpls,
+    trans_id: TraitImplId::Id,
+) -> Result<()> {
+    let info = decls
+        .decls_info
+        .get(&AnyDeclId::TraitImpl(trans_id))
+        .unwrap();
+    let rid = info.rid;
+
+    // Check and translate the generics introduced by the `impl` block
+    generics::check_trait_impl_generics(tcx, rid);
+    let TypeGenericsInfo {
+        substs: _,
+        region_params,
+        region_params_map,
+        type_params,
+        type_params_map,
+        const_generic_params,
+        const_generic_params_map,
+    } = translate_generics(tcx, rid);
+    // Retrieve the implemented trait and translate its arguments (as for the
+    // trait declarations, the first type argument is `Self`)
+    let trait_ref = tcx.impl_trait_ref(rid).unwrap();
+    let impl_trait = *decls.trait_decl_rid_to_id.get(&trait_ref.def_id).unwrap();
+    let (trait_region_args, trait_type_args, trait_const_generic_args) = translate_substs(
+        tcx,
+        trans_ctx,
+        &|r| translate_non_erased_region(&region_params_map, *r),
+        &type_params_map,
+        &const_generic_params_map,
+        None,
+        &trait_ref.substs,
+    )?;
+
+    let (types, methods) = if info.is_local() && info.is_transparent {
+        let types = translate_impl_types(
+            tcx,
+            trans_ctx,
+            &region_params_map,
+            &type_params_map,
+            &const_generic_params_map,
+            rid,
+        )?;
+        (Some(types), Some(translate_methods(tcx, decls, rid)))
+    } else {
+        (None, None)
+    };
+
+    let trait_impl = ast::TraitImpl {
+        def_id: trans_id,
+        meta: meta::get_meta_from_rid(sess, tcx, &decls.file_to_id, rid),
+        name: trait_impl_def_id_to_name(tcx, rid),
+        region_params: ty::RegionVarId::Vector::from(region_params),
+        type_params: ty::TypeVarId::Vector::from(type_params),
+        const_generic_params: ty::ConstGenericVarId::Vector::from(const_generic_params),
+        impl_trait,
+        trait_region_args,
+        trait_type_args,
+        trait_const_generic_args,
+        types,
+        methods,
+    };
+
+    // Sanity check: the declarations are translated in order
+    assert!(trait_impls.len() == trans_id.to_usize());
+    trait_impls.push_back(trait_impl);
+    Ok(())
+}
+
+/// Translate the trait declarations and the trait implementations.
+///
+/// Must be called after [crate::translate_types::translate_types], because
+/// the trait implementations refer to the type declarations.
+pub fn translate_traits(
+    sess: &Session,
+    tcx: TyCtxt,
+    decls: &OrderedDecls,
+    type_defs: &ty::TypeDecls,
+) -> std::result::Result<(ast::TraitDecls, ast::TraitImpls), TranslationError> {
+    trace!();
+
+    let trans_ctx = TypeTransContext::new(type_defs, decls);
+    let mut trait_decls = ast::TraitDecls::new();
+    let mut trait_impls = ast::TraitImpls::new();
+
+    for decl in &decls.decls {
+        match decl {
+            DeclarationGroup::TraitDecl(GDeclarationGroup::NonRec(id)) => {
+                translate_trait_decl(sess, tcx, decls, &mut trait_decls, *id)
+            }
+            DeclarationGroup::TraitDecl(GDeclarationGroup::Rec(ids)) => {
+                for id in ids {
+                    translate_trait_decl(sess, tcx, decls, &mut trait_decls, *id)
+                }
+            }
+            DeclarationGroup::TraitImpl(GDeclarationGroup::NonRec(id)) => {
+                translate_trait_impl(sess, tcx, decls, &trans_ctx, &mut trait_impls, *id)?
+            }
+            DeclarationGroup::TraitImpl(GDeclarationGroup::Rec(ids)) => {
+                for id in ids {
+                    translate_trait_impl(sess, tcx, decls, &trans_ctx, &mut trait_impls, *id)?
+                }
+            }
+            DeclarationGroup::Type(_) | DeclarationGroup::Fun(_) | DeclarationGroup::Global(_) => {
+                // Ignore the other declarations
+            }
+        }
+    }
+
+    Ok((trait_decls, trait_impls))
+}
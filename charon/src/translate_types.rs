@@ -11,10 +11,14 @@ use crate::reorder_decls::DeclarationGroup;
 use crate::rust_to_local_ids::*;
 use crate::types as ty;
 use crate::types::TypeDeclId;
+use crate::ullbc_ast::TraitDeclId;
+use crate::values::ScalarValue;
 use im::Vector;
 use rustc_hir::def_id::DefId;
 use rustc_middle::mir::Mutability;
-use rustc_middle::ty::{Ty, TyCtxt, TyKind};
+use rustc_middle::ty::{
+    AliasKind, ConstKind, ExistentialPredicate, ParamEnv, Ty, TyCtxt, TyKind, TypeVisitable,
+};
 use rustc_session::Session;
 
 /// Translation context for type definitions
@@ -34,6 +38,10 @@ impl<'ctx> TypeTransContext<'ctx> {
     pub fn get_id(&self, rid: DefId) -> TypeDeclId::Id {
         *self.decls.type_rid_to_id.get(&rid).unwrap()
     }
+
+    pub fn get_trait_decl_id(&self, rid: DefId) -> TraitDeclId::Id {
+        *self.decls.trait_decl_rid_to_id.get(&rid).unwrap()
+    }
 }
 
 /// Auxiliary definition used to format definitions.
@@ -142,6 +150,11 @@ pub fn translate_non_erased_region<'tcx>(
     match region {
         rustc_middle::ty::RegionKind::ReErased => unreachable!(),
         rustc_middle::ty::RegionKind::ReStatic => ty::Region::Static,
+        rustc_middle::ty::RegionKind::ReLateBound(db, br) => {
+            // A region bound by a binder inside the type (for instance, by
+            // the binder of a function pointer type `for<'a> fn(&'a u32)`)
+            ty::Region::Bound(db.as_usize(), ty::RegionVarId::Id::new(br.var.as_usize()))
+        }
         _ => {
             let rid = region_params.get(&region).unwrap();
             ty::Region::Var(*rid)
@@ -156,6 +169,9 @@ pub fn translate_non_erased_region<'tcx>(
 pub fn translate_erased_region(region: rustc_middle::ty::RegionKind<'_>) -> ty::ErasedRegion {
     match region {
         rustc_middle::ty::RegionKind::ReErased => ty::ErasedRegion::Erased,
+        // The regions bound inside the types (for instance, by the binders
+        // of function pointer types) are not erased
+        rustc_middle::ty::RegionKind::ReLateBound(_, _) => ty::ErasedRegion::Erased,
         _ => {
             unreachable!();
         }
@@ -179,16 +195,96 @@ pub fn translate_erased_region(region: rustc_middle::ty::RegionKind<'_>) -> ty::
 /// regions can be translated in several manners (non-erased region or erased
 /// regions), in which case the return type is different.
 pub fn translate_ty<'tcx, R>(
-    tcx: TyCtxt,
+    tcx: TyCtxt<'tcx>,
     trans_ctx: &TypeTransContext,
     region_translator: &dyn Fn(&rustc_middle::ty::RegionKind<'tcx>) -> R,
     type_params: &im::OrdMap<u32, ty::Ty<R>>,
+    const_generic_params: &im::OrdMap<u32, ty::ConstGeneric>,
     ty: &Ty<'tcx>,
 ) -> Result<ty::Ty<R>>
 where
     R: Clone + Eq,
 {
-    translate_ty_kind(tcx, trans_ctx, region_translator, type_params, ty.kind())
+    translate_ty_kind(
+        tcx,
+        trans_ctx,
+        region_translator,
+        type_params,
+        const_generic_params,
+        ty.kind(),
+    )
+}
+
+/// Translate a constant used as a generic argument (for instance, the length
+/// of an array type).
+///
+/// The constant is either a const generic parameter, in which case we look
+/// it up in `const_generic_params`, or a value we can evaluate.
+pub(crate) fn translate_const_generic<'tcx>(
+    tcx: TyCtxt<'tcx>,
+    const_generic_params: &im::OrdMap<u32, ty::ConstGeneric>,
+    c: &rustc_middle::ty::Const<'tcx>,
+) -> Result<ty::ConstGeneric> {
+    if let ConstKind::Param(param) = c.kind() {
+        return Ok(const_generic_params.get(&param.index).unwrap().clone());
+    }
+
+    let int_ty = translate_const_generic_ty(&c.ty());
+
+    // Evaluate the constant: it may refer to a top-level constant (for
+    // instance: `[u8; BLOCK_SIZE]`)
+    let bits = c.eval_bits(tcx, ParamEnv::reveal_all(), c.ty());
+    Ok(ty::ConstGeneric::Value(ScalarValue::from_le_bytes(
+        int_ty,
+        bits.to_le_bytes(),
+    )))
+}
+
+/// Translate the type of a const generic. For now, we only support integers.
+pub(crate) fn translate_const_generic_ty(ty: &Ty<'_>) -> ty::IntegerTy {
+    match ty.kind() {
+        TyKind::Int(int_ty) => ty::IntegerTy::rust_int_ty_to_integer_ty(*int_ty),
+        TyKind::Uint(int_ty) => ty::IntegerTy::rust_uint_ty_to_integer_ty(*int_ty),
+        _ => unimplemented!("Unsupported constant generic type: {:?}", ty),
+    }
+}
+
+/// Translate the inputs and the output of a function signature to a function
+/// pointer type.
+fn translate_fn_sig_ty<'tcx, R>(
+    tcx: TyCtxt<'tcx>,
+    trans_ctx: &TypeTransContext,
+    region_translator: &dyn Fn(&rustc_middle::ty::RegionKind<'tcx>) -> R,
+    type_params: &im::OrdMap<u32, ty::Ty<R>>,
+    const_generic_params: &im::OrdMap<u32, ty::ConstGeneric>,
+    inputs: &[Ty<'tcx>],
+    output: &Ty<'tcx>,
+) -> Result<ty::Ty<R>>
+where
+    R: Clone + Eq,
+{
+    let inputs = inputs
+        .iter()
+        .map(|ty| {
+            translate_ty(
+                tcx,
+                trans_ctx,
+                region_translator,
+                type_params,
+                const_generic_params,
+                ty,
+            )
+        })
+        .collect::<Result<Vector<ty::Ty<R>>>>()?;
+    let output = translate_ty(
+        tcx,
+        trans_ctx,
+        region_translator,
+        type_params,
+        const_generic_params,
+        output,
+    )?;
+    Ok(ty::Ty::FnPtr(inputs, Box::new(output)))
 }
 
 /// Translate a [TyKind].
@@ -196,10 +292,11 @@ where
 /// See the comments for [translate_ty] (the two functions do the same thing,
 /// they simply don't take the same input parameters).
 pub fn translate_ty_kind<'tcx, R>(
-    tcx: TyCtxt,
+    tcx: TyCtxt<'tcx>,
     trans_ctx: &TypeTransContext,
     region_translator: &dyn Fn(&rustc_middle::ty::RegionKind<'tcx>) -> R,
     type_params: &im::OrdMap<u32, ty::Ty<R>>,
+    const_generic_params: &im::OrdMap<u32, ty::ConstGeneric>,
     ty_kind: &TyKind<'tcx>,
 ) -> Result<ty::Ty<R>>
 where
@@ -216,14 +313,52 @@ where
             *int_ty,
         ))),
         TyKind::Str => Ok(ty::Ty::Str),
-        TyKind::Float(_) => {
-            trace!("Float");
-            // This case should have been filtered during the registration phase
-            unreachable!();
-        }
+        TyKind::Float(float_ty) => Ok(ty::Ty::Float(ty::FloatTy::rust_float_ty_to_float_ty(
+            *float_ty,
+        ))),
         TyKind::Never => Ok(ty::Ty::Never),
 
-        TyKind::Alias(_, _) => {
+        TyKind::Alias(AliasKind::Projection, alias_ty) => {
+            // Normalize the projection, if possible (this is the case if
+            // we know the implementation of the trait)
+            if let Some(ty) = try_normalize_projection(tcx, tcx.mk_ty(ty_kind.clone())) {
+                return translate_ty(
+                    tcx,
+                    trans_ctx,
+                    region_translator,
+                    type_params,
+                    const_generic_params,
+                    &ty,
+                );
+            }
+
+            // We can't normalize: we refer to the associated type of the trait
+            let (trait_ref, own_substs) = alias_ty.trait_ref_and_own_substs(tcx);
+            // We don't support generic associated types
+            if !own_substs.is_empty() {
+                unimplemented!();
+            }
+            let trait_id = trans_ctx.get_trait_decl_id(trait_ref.def_id);
+            let (region_args, type_args, cg_args) = translate_substs(
+                tcx,
+                trans_ctx,
+                region_translator,
+                type_params,
+                const_generic_params,
+                None,
+                &trait_ref.substs,
+            )?;
+            let trait_ref = ty::TraitRef {
+                trait_id,
+                region_args: Vector::from(region_args),
+                type_args: Vector::from(type_args),
+                const_generic_args: Vector::from(cg_args),
+            };
+            let name = tcx.item_name(alias_ty.def_id).to_ident_string();
+            Ok(ty::Ty::Projection(Box::new(trait_ref), name))
+        }
+        TyKind::Alias(AliasKind::Opaque, _) => {
+            // `impl Trait` types
             unimplemented!();
         }
 
@@ -240,11 +375,12 @@ where
             };
 
             // Translate the type parameters instantiation
-            let (regions, params) = translate_substs(
+            let (regions, params, cgs) = translate_substs(
                 tcx,
                 trans_ctx,
                 region_translator,
                 type_params,
+                const_generic_params,
                 used_params,
                 substs,
             )?;
@@ -257,25 +393,48 @@ where
                 def_id,
                 Vector::from(regions),
                 Vector::from(params),
+                Box::new(Vector::from(cgs)),
             ))
         }
-        TyKind::Array(ty, _const_param) => {
+        TyKind::Array(ty, const_param) => {
             trace!("Array");
 
-            let ty = translate_ty(tcx, trans_ctx, region_translator, type_params, ty)?;
-            Ok(ty::Ty::Array(Box::new(ty)))
+            let ty = translate_ty(
+                tcx,
+                trans_ctx,
+                region_translator,
+                type_params,
+                const_generic_params,
+                ty,
+            )?;
+            let len = translate_const_generic(tcx, const_generic_params, const_param)?;
+            Ok(ty::Ty::Array(Box::new(ty), len))
         }
         TyKind::Slice(ty) => {
             trace!("Slice");
 
-            let ty = translate_ty(tcx, trans_ctx, region_translator, type_params, ty)?;
+            let ty = translate_ty(
+                tcx,
+                trans_ctx,
+                region_translator,
+                type_params,
+                const_generic_params,
+                ty,
+            )?;
             Ok(ty::Ty::Slice(Box::new(ty)))
         }
         TyKind::Ref(region, ty, mutability) => {
             trace!("Ref");
 
             let region = region_translator(region);
-            let ty = translate_ty(tcx, trans_ctx, region_translator, type_params, ty)?;
+            let ty = translate_ty(
+                tcx,
+                trans_ctx,
+                region_translator,
+                type_params,
+                const_generic_params,
+                ty,
+            )?;
             let kind = match *mutability {
                 Mutability::Not => ty::RefKind::Shared,
                 Mutability::Mut => ty::RefKind::Mut,
@@ -289,6 +448,7 @@ where
                 trans_ctx,
                 region_translator,
                 type_params,
+                const_generic_params,
                 &ty_and_mut.ty,
             )?;
             let kind = match ty_and_mut.mutbl {
@@ -302,8 +462,14 @@ where
 
             let mut params = vec![];
             for param in substs.iter() {
-                let param_ty =
-                    translate_ty(tcx, trans_ctx, region_translator, type_params, &param)?;
+                let param_ty = translate_ty(
+                    tcx,
+                    trans_ctx,
+                    region_translator,
+                    type_params,
+                    const_generic_params,
+                    &param,
+                )?;
                 params.push(param_ty);
             }
 
@@ -311,12 +477,24 @@ where
                 ty::TypeId::Tuple,
                 Vector::new(),
                 Vector::from(params),
+                Box::new(Vector::new()),
             ))
         }
 
-        TyKind::FnPtr(_) => {
+        TyKind::FnPtr(sig) => {
             trace!("FnPtr");
-            unimplemented!();
+            // We don't introduce the regions bound by the signature: they
+            // are translated to bound regions (see [translate_non_erased_region])
+            let sig = sig.skip_binder();
+            translate_fn_sig_ty(
+                tcx,
+                trans_ctx,
+                region_translator,
+                type_params,
+                const_generic_params,
+                sig.inputs(),
+                &sig.output(),
+            )
         }
         TyKind::Param(param) => {
             // A type parameter, for example `T` in `fn f<T>(x : T) {}`.
@@ -346,18 +524,111 @@ where
             unreachable!();
         }
 
-        TyKind::FnDef(_, _) => {
+        TyKind::FnDef(def_id, substs) => {
             trace!("FnDef");
-            unreachable!();
+            // A function item: we translate it to the type of a function
+            // pointer with the instantiated signature
+            let sig = tcx.bound_fn_sig(*def_id).subst(tcx, substs);
+            let sig = sig.skip_binder();
+            translate_fn_sig_ty(
+                tcx,
+                trans_ctx,
+                region_translator,
+                type_params,
+                const_generic_params,
+                sig.inputs(),
+                &sig.output(),
+            )
         }
 
-        TyKind::Dynamic(_, _, _) => {
+        TyKind::Dynamic(preds, region, _) => {
             trace!("Dynamic");
-            unreachable!();
+            // The predicates may bind regions: as for the function pointers,
+            // we ignore the binders (see [translate_non_erased_region])
+            let mut dyn_preds = vec![];
+            for pred in preds.iter() {
+                let pred = match pred.skip_binder() {
+                    ExistentialPredicate::Trait(trait_ref) => {
+                        let trait_id = trans_ctx.get_trait_decl_id(trait_ref.def_id);
+                        let (region_args, type_args, cg_args) = translate_substs(
+                            tcx,
+                            trans_ctx,
+                            region_translator,
+                            type_params,
+                            const_generic_params,
+                            None,
+                            &trait_ref.substs,
+                        )?;
+                        // We don't support const generics in the traits
+                        if !cg_args.is_empty() {
+                            unimplemented!();
+                        }
+                        ty::DynPredicate::Trait(
+                            trait_id,
+                            Vector::from(region_args),
+                            Vector::from(type_args),
+                        )
+                    }
+                    ExistentialPredicate::Projection(proj) => {
+                        let trait_id = trans_ctx.get_trait_decl_id(proj.trait_ref(tcx).def_id);
+                        let name = tcx.item_name(proj.def_id).to_ident_string();
+                        let ty = translate_ty(
+                            tcx,
+                            trans_ctx,
+                            region_translator,
+                            type_params,
+                            const_generic_params,
+                            &proj.term.ty().unwrap(),
+                        )?;
+                        ty::DynPredicate::Projection(trait_id, name, ty)
+                    }
+                    ExistentialPredicate::AutoTrait(trait_id) => {
+                        let trait_id = trans_ctx.get_trait_decl_id(trait_id);
+                        ty::DynPredicate::Trait(trait_id, Vector::new(), Vector::new())
+                    }
+                };
+                dyn_preds.push(pred);
+            }
+            let region = region_translator(region);
+            Ok(ty::Ty::DynTrait(Vector::from(dyn_preds), region))
         }
-        TyKind::Closure(_, _) => {
+        TyKind::Closure(def_id, substs) => {
             trace!("Closure");
-            unreachable!();
+            // We translate the closure type to the type of its state (the
+            // captured variables): see [translate_closure_state]
+            let id = trans_ctx.get_id(*def_id);
+
+            // The region arguments of the state are the regions appearing in
+            // the types of the captured variables
+            let (_, upvar_regions) = get_closure_upvar_tys(tcx, *def_id, substs, false);
+            let regions = upvar_regions.iter().map(region_translator).collect();
+
+            // The state has the same type parameters and const generic
+            // parameters as the parent function
+            let parent_substs = get_closure_parent_substs(tcx, *def_id, substs);
+            let mut params = vec![];
+            for param_ty in parent_substs.types() {
+                let param_ty = translate_ty(
+                    tcx,
+                    trans_ctx,
+                    region_translator,
+                    type_params,
+                    const_generic_params,
+                    &param_ty,
+                )?;
+                params.push(param_ty);
+            }
+            let cgs = parent_substs
+                .consts()
+                .map(|c| translate_const_generic(tcx, const_generic_params, &c))
+                .collect::<Result<Vector<ty::ConstGeneric>>>()?;
+
+            Ok(ty::Ty::Adt(
+                ty::TypeId::Adt(id),
+                regions,
+                Vector::from(params),
+                Box::new(cgs),
+            ))
         }
 
         TyKind::Generator(_, _, _) | TyKind::GeneratorWitness(_) => {
@@ -380,14 +651,38 @@ where
     }
 }
 
+/// Attempt to normalize a projection type (i.e., `<T as Trait>::Item`).
+///
+/// We only normalize the projections which don't contain regions, and whose
+/// normalized form doesn't contain regions: normalizing erases the regions,
+/// which we don't want to lose in the signatures.
+/// Return `None` if we couldn't normalize (for instance because the `Self`
+/// type is a type parameter and we thus don't know the trait implementation).
+pub(crate) fn try_normalize_projection<'tcx>(tcx: TyCtxt<'tcx>, ty: Ty<'tcx>) -> Option<Ty<'tcx>> {
+    if ty.has_free_regions() {
+        return None;
+    }
+    match tcx.try_normalize_erasing_regions(ParamEnv::reveal_all(), ty) {
+        Ok(nty) => {
+            if matches!(nty.kind(), TyKind::Alias(_, _)) || nty.has_erased_regions() {
+                None
+            } else {
+                Some(nty)
+            }
+        }
+        Err(_) => None,
+    }
+}
+
 /// Translate a signature type, where the regions are not erased and use region
 /// variable ids.
 /// Simply calls [`translate_ty`](translate_ty)
 pub fn translate_sig_ty<'tcx>(
-    tcx: TyCtxt,
+    tcx: TyCtxt<'tcx>,
     trans_ctx: &TypeTransContext,
     region_params: &im::OrdMap<rustc_middle::ty::RegionKind<'tcx>, ty::RegionVarId::Id>,
     type_params: &im::OrdMap<u32, ty::RTy>,
+    const_generic_params: &im::OrdMap<u32, ty::ConstGeneric>,
     ty: &Ty<'tcx>,
 ) -> Result<ty::RTy> {
     translate_ty(
@@ -395,51 +690,58 @@ pub fn translate_sig_ty<'tcx>(
         trans_ctx,
         &|r| translate_non_erased_region(region_params, *r),
         type_params,
+        const_generic_params,
         ty,
     )
 }
 
 /// Translate a type where the regions are erased
 /// Simply calls [translate_ty]
-pub fn translate_ety(
-    tcx: TyCtxt,
+pub fn translate_ety<'tcx>(
+    tcx: TyCtxt<'tcx>,
     trans_ctx: &TypeTransContext,
     type_params: &im::OrdMap<u32, ty::ETy>,
-    ty: &Ty,
+    const_generic_params: &im::OrdMap<u32, ty::ConstGeneric>,
+    ty: &Ty<'tcx>,
 ) -> Result<ty::ETy> {
     translate_ty(
         tcx,
         trans_ctx,
         &|r| translate_erased_region(*r),
         type_params,
+        const_generic_params,
         ty,
     )
 }
 
 /// Simply calls [translate_ty_kind]
-pub fn translate_ety_kind(
-    tcx: TyCtxt,
+pub fn translate_ety_kind<'tcx>(
+    tcx: TyCtxt<'tcx>,
     trans_ctx: &TypeTransContext,
     type_params: &im::OrdMap<u32, ty::ETy>,
-    ty: &TyKind,
+    const_generic_params: &im::OrdMap<u32, ty::ConstGeneric>,
+    ty: &TyKind<'tcx>,
 ) -> Result<ty::ETy> {
     translate_ty_kind(
         tcx,
         trans_ctx,
         &|r| translate_erased_region(*r),
         type_params,
+        const_generic_params,
         ty,
     )
 }
 
-fn translate_substs<'tcx, R>(
-    tcx: TyCtxt,
+#[allow(clippy::type_complexity)]
+pub(crate) fn translate_substs<'tcx, R>(
+    tcx: TyCtxt<'tcx>,
     trans_ctx: &TypeTransContext,
     region_translator: &dyn Fn(&rustc_middle::ty::RegionKind<'tcx>) -> R,
     type_params: &im::OrdMap<u32, ty::Ty<R>>,
+    const_generic_params: &im::OrdMap<u32, ty::ConstGeneric>,
     used_params: Option<Vec<bool>>,
     substs: &rustc_middle::ty::subst::SubstsRef<'tcx>,
-) -> Result<(Vec<R>, Vec<ty::Ty<R>>)>
+) -> Result<(Vec<R>, Vec<ty::Ty<R>>, Vec<ty::ConstGeneric>)>
 where
     R: Clone + Eq,
 {
@@ -471,24 +773,31 @@ where
 
     let mut regions: Vec<R> = vec![];
     let mut params = vec![];
+    let mut cgs = vec![];
     for (param, param_i) in substs.iter() {
         trace!("Adt: param {}: {:?}", param_i, param);
         match param.unpack() {
             rustc_middle::ty::subst::GenericArgKind::Type(param_ty) => {
-                let param_ty =
-                    translate_ty(tcx, trans_ctx, region_translator, type_params, &param_ty)?;
+                let param_ty = translate_ty(
+                    tcx,
+                    trans_ctx,
+                    region_translator,
+                    type_params,
+                    const_generic_params,
+                    &param_ty,
+                )?;
                 params.push(param_ty);
             }
             rustc_middle::ty::subst::GenericArgKind::Lifetime(region) => {
                 regions.push(region_translator(&region));
             }
-            rustc_middle::ty::subst::GenericArgKind::Const(_) => {
-                unimplemented!();
+            rustc_middle::ty::subst::GenericArgKind::Const(c) => {
+                cgs.push(translate_const_generic(tcx, const_generic_params, &c)?);
             }
         }
     }
 
-    Result::Ok((regions, params))
+    Result::Ok((regions, params, cgs))
 }
 
 /// Translate a type def id
@@ -516,13 +825,68 @@ fn translate_defid(tcx: TyCtxt, trans_ctx: &TypeTransContext, def_id: DefId) ->
     }
 }
 
-/// Helper type
-struct TypeGenericsInfo<'tcx> {
+/// Retrieve the generic arguments of the function in which a closure is
+/// defined. The closures (and their states) have the same generic parameters
+/// as this function: the other parameters of the closure substitution are
+/// synthetic parameters introduced by rustc (closure kind, signature...).
+pub(crate) fn get_closure_parent_substs<'tcx>(
+    tcx: TyCtxt<'tcx>,
+    closure_id: DefId,
     substs: rustc_middle::ty::subst::SubstsRef<'tcx>,
-    region_params: Vec<ty::RegionVar>,
-    region_params_map: im::OrdMap<rustc_middle::ty::RegionKind<'tcx>, ty::RegionVarId::Id>,
-    type_params: Vec<ty::TypeVar>,
-    type_params_map: im::OrdMap<u32, ty::RTy>,
+) -> rustc_middle::ty::subst::SubstsRef<'tcx> {
+    let parent_id = tcx.typeck_root_def_id(closure_id);
+    let num_params = tcx.generics_of(parent_id).count();
+    tcx.intern_substs(&substs[..num_params])
+}
+
+/// Retrieve the types of the variables captured by a closure, together with
+/// the list of regions appearing in those types.
+///
+/// The regions are erased in the types of the captured variables: if
+/// `fresh_regions` is true, we replace them with fresh regions, which we
+/// use as the region parameters of the closure state.
+pub(crate) fn get_closure_upvar_tys<'tcx>(
+    tcx: TyCtxt<'tcx>,
+    closure_id: DefId,
+    substs: rustc_middle::ty::subst::SubstsRef<'tcx>,
+    fresh_regions: bool,
+) -> (Vec<Ty<'tcx>>, Vec<rustc_middle::ty::RegionKind<'tcx>>) {
+    let scope = tcx.typeck_root_def_id(closure_id);
+    let mut regions = vec![];
+    let tys = substs
+        .as_closure()
+        .upvar_tys()
+        .map(|ty| {
+            tcx.fold_regions(ty, |r, _| {
+                let r = if fresh_regions {
+                    let index = regions.len() as u32;
+                    tcx.mk_region(rustc_middle::ty::RegionKind::ReFree(
+                        rustc_middle::ty::FreeRegion {
+                            scope,
+                            bound_region: rustc_middle::ty::BoundRegionKind::BrAnon(index, None),
+                        },
+                    ))
+                } else {
+                    r
+                };
+                regions.push(*r);
+                r
+            })
+        })
+        .collect();
+    (tys, regions)
+}
+
+/// Helper type
+pub(crate) struct TypeGenericsInfo<'tcx> {
+    pub(crate) substs: rustc_middle::ty::subst::SubstsRef<'tcx>,
+    pub(crate) region_params: Vec<ty::RegionVar>,
+    pub(crate) region_params_map:
+        im::OrdMap<rustc_middle::ty::RegionKind<'tcx>, ty::RegionVarId::Id>,
+    pub(crate) type_params: Vec<ty::TypeVar>,
+    pub(crate) type_params_map: im::OrdMap<u32, ty::RTy>,
+    pub(crate) const_generic_params: Vec<ty::ConstGenericVar>,
+    pub(crate) const_generic_params_map: im::OrdMap<u32, ty::ConstGeneric>,
 }
 
 /// Auxiliary helper.
@@ -534,14 +898,22 @@ struct TypeGenericsInfo<'tcx> {
 ///
 /// Rem.: this seems simpler in [crate::translate_functions_to_ullbc].
 /// TODO: compare and simplify/factorize?
-fn translate_type_generics<'tcx>(tcx: TyCtxt<'tcx>, def_id: DefId) -> TypeGenericsInfo<'tcx> {
+fn translate_type_generics(tcx: TyCtxt<'_>, def_id: DefId) -> TypeGenericsInfo<'_> {
     // Check the generics
     generics::check_type_generics(tcx, def_id);
 
+    translate_generics(tcx, def_id)
+}
+
+/// Translate the generics of a definition, without performing any check.
+///
+/// This is also used in [crate::translate_traits] to translate the generics
+/// of the trait declarations and trait implementations.
+pub(crate) fn translate_generics<'tcx>(tcx: TyCtxt<'tcx>, def_id: DefId) -> TypeGenericsInfo<'tcx> {
     // Use a dummy substitution to instantiate the type parameters
     let substs = rustc_middle::ty::subst::InternalSubsts::identity_for_item(tcx, def_id);
 
-    // Handle the region and type parameters:
+    // Handle the region, type and const generic parameters:
     // - we need to know how many parameters there are
     // - we need to create a map linking the rust parameters to our pure
     //   parameters
@@ -552,6 +924,9 @@ fn translate_type_generics<'tcx>(tcx: TyCtxt<'tcx>, def_id: DefId) -> TypeGeneri
     let mut type_params: Vec<ty::TypeVar> = vec![];
     let mut type_params_map: im::OrdMap<u32, ty::RTy> = im::OrdMap::new();
     let mut type_params_counter = ty::TypeVarId::Generator::new();
+    let mut const_generic_params: Vec<ty::ConstGenericVar> = vec![];
+    let mut const_generic_params_map: im::OrdMap<u32, ty::ConstGeneric> = im::OrdMap::new();
+    let mut const_generic_params_counter = ty::ConstGenericVarId::Generator::new();
     for p in substs.iter() {
         match p.unpack() {
             rustc_middle::ty::subst::GenericArgKind::Type(param_ty) => {
@@ -580,8 +955,23 @@ fn translate_type_generics<'tcx>(tcx: TyCtxt<'tcx>, def_id: DefId) -> TypeGeneri
                 region_params_map.insert(*region, t_region.index);
                 region_params.push(t_region);
             }
-            rustc_middle::ty::subst::GenericArgKind::Const(_) => {
-                unimplemented!();
+            rustc_middle::ty::subst::GenericArgKind::Const(c) => {
+                // The constant should be a Param:
+                match c.kind() {
+                    ConstKind::Param(param) => {
+                        let cg_var = ty::ConstGenericVar {
+                            index: const_generic_params_counter.fresh_id(),
+                            name: param.name.to_ident_string(),
+                            ty: translate_const_generic_ty(&c.ty()),
+                        };
+                        const_generic_params_map
+                            .insert(param.index, ty::ConstGeneric::Var(cg_var.index));
+                        const_generic_params.push(cg_var);
+                    }
+                    _ => {
+                        panic!("Inconsistent state");
+                    }
+                }
             }
         }
     }
@@ -597,7 +987,7 @@ fn translate_type_generics<'tcx>(tcx: TyCtxt<'tcx>, def_id: DefId) -> TypeGeneri
             let name = &r.name;
             if name.is_some() {
                 let name = name.as_ref().unwrap();
-                assert!(s.contains(name));
+                assert!(!s.contains(name));
                 s.insert(name.clone());
             }
         }
@@ -609,6 +999,8 @@ fn translate_type_generics<'tcx>(tcx: TyCtxt<'tcx>, def_id: DefId) -> TypeGeneri
         region_params_map,
         type_params,
         type_params_map,
+        const_generic_params,
+        const_generic_params_map,
     }
 }
 
@@ -638,6 +1030,8 @@ fn translate_transparent_type<'tcx>(
         region_params_map,
         type_params: _,
         type_params_map,
+        const_generic_params: _,
+        const_generic_params_map,
     } = generics;
 
     // Explore the variants
@@ -657,7 +1051,14 @@ fn translate_transparent_type<'tcx>(
             let ty = field_def.ty(tcx, substs);
 
             // Translate the field type
-            let ty = translate_sig_ty(tcx, &trans_ctx, region_params_map, type_params_map, &ty)?;
+            let ty = translate_sig_ty(
+                tcx,
+                &trans_ctx,
+                region_params_map,
+                type_params_map,
+                const_generic_params_map,
+                &ty,
+            )?;
 
             // Retrieve the field name.
             // Note that the only way to check if the user wrote the name or
@@ -715,15 +1116,105 @@ fn translate_transparent_type<'tcx>(
         rustc_middle::ty::AdtKind::Enum => {
             ty::TypeDeclKind::Enum(ty::VariantId::Vector::from(variants))
         }
-        rustc_middle::ty::AdtKind::Union => {
-            // Should have been filtered during the registration phase
-            unreachable!();
-        }
+        rustc_middle::ty::AdtKind::Union => ty::TypeDeclKind::Union(variants[0].fields.clone()),
     };
 
     Ok(type_def_kind)
 }
 
+/// Translate the type of the state of a closure, that is the structure
+/// of its captured variables. Those types are generated: they don't appear
+/// in the Rust sources (see [crate::rust_to_local_ids]).
+///
+/// The state has the type parameters of the function in which the closure
+/// is defined, and one region parameter per region appearing in the types
+/// of the captured variables.
+fn translate_closure_state(
+    sess: &Session,
+    tcx: TyCtxt,
+    decls: &OrderedDecls,
+    type_defs: &mut ty::TypeDecls,
+    trans_id: ty::TypeDeclId::Id,
+) -> Result<()> {
+    let info = decls.decls_info.get(&AnyDeclId::Type(trans_id)).unwrap();
+    let closure_id = info.rid;
+    let parent_id = tcx.typeck_root_def_id(closure_id);
+
+    // The type and const generic parameters are the ones of the parent function
+    let TypeGenericsInfo {
+        substs: _,
+        region_params: _,
+        region_params_map: _,
+        type_params,
+        type_params_map,
+        const_generic_params,
+        const_generic_params_map,
+    } = translate_generics(tcx, parent_id);
+
+    // Introduce fresh regions for the captured variables
+    let substs = match tcx.type_of(closure_id).kind() {
+        TyKind::Closure(_, substs) => *substs,
+        _ => unreachable!(),
+    };
+    let (upvar_tys, upvar_regions) = get_closure_upvar_tys(tcx, closure_id, substs, true);
+    let mut region_params: Vec<ty::RegionVar> = vec![];
+    let mut region_params_map = im::OrdMap::new();
+    let mut region_params_counter = ty::RegionVarId::Generator::new();
+    for r in upvar_regions {
+        let t_region = ty::RegionVar {
+            index: region_params_counter.fresh_id(),
+            name: None,
+        };
+        region_params_map.insert(r, t_region.index);
+        region_params.push(t_region);
+    }
+
+    let meta = meta::get_meta_from_rid(sess, tcx, &decls.file_to_id, closure_id);
+    let kind = if info.is_transparent {
+        // The fields are named after the captured variables
+        let trans_ctx = TypeTransContext::new(type_defs, decls);
+        let names =
+            tcx.symbols_for_closure_captures((parent_id.expect_local(), closure_id.expect_local()));
+        let mut fields = vec![];
+        for (ty, name) in upvar_tys.iter().zip(names.iter()) {
+            let ty = translate_sig_ty(
+                tcx,
+                &trans_ctx,
+                &region_params_map,
+                &type_params_map,
+                &const_generic_params_map,
+                ty,
+            )?;
+            fields.push(ty::Field {
+                meta,
+                name: Some(name.to_ident_string()),
+                ty,
+            });
+        }
+        ty::TypeDeclKind::Struct(ty::FieldId::Vector::from(fields))
+    } else {
+        ty::TypeDeclKind::Opaque
+    };
+
+    let type_def = ty::TypeDecl {
+        def_id: trans_id,
+        meta,
+        name: type_def_id_to_name(tcx, closure_id),
+        extern_crate: None,
+        region_params: ty::RegionVarId::Vector::from(region_params),
+        type_params: ty::TypeVarId::Vector::from(type_params),
+        const_generic_params: ty::ConstGenericVarId::Vector::from(const_generic_params),
+        kind,
+        // Computed later, like for the other types
+        regions_hierarchy: regions_hierarchy::RegionGroups::new(),
+    };
+
+    trace!("{} -> {}", trans_id.to_string(), type_def.to_string());
+    assert!(type_defs.types.len() == trans_id.to_usize());
+    type_defs.types.push_back(type_def);
+    Ok(())
+}
+
 /// Translate a type definition.
 ///
 /// Note that we translate the types one by one: we don't need to take into
@@ -738,14 +1229,20 @@ fn translate_type(
 ) -> Result<()> {
     let info = decls.decls_info.get(&AnyDeclId::Type(trans_id)).unwrap();
 
+    // The closure states have a special treatment
+    if tcx.is_closure(info.rid) {
+        return translate_closure_state(sess, tcx, decls, type_defs, trans_id);
+    }
+
     // Check and translate the generics
     let generics = translate_type_generics(tcx, info.rid);
 
     // Check if the type is opaque or external, and delegate the translation
     // of the "body" to the proper function
-    let kind = if !info.is_local() || !info.is_transparent {
+    let kind = if !info.is_transparent {
         // Opaque types are:
-        // - external types
+        // - external types (unless we reveal the external ADTs, see
+        //   [crate::register::CrateInfo])
         // - local types flagged as opaque
         ty::TypeDeclKind::Opaque
     } else {
@@ -759,21 +1256,33 @@ fn translate_type(
         region_params_map: _,
         type_params,
         type_params_map: _,
+        const_generic_params,
+        const_generic_params_map: _,
     } = generics;
 
     let name = type_def_id_to_name(tcx, info.rid);
     let region_params = ty::RegionVarId::Vector::from(region_params);
     let type_params = ty::TypeVarId::Vector::from(type_params);
+    let const_generic_params = ty::ConstGenericVarId::Vector::from(const_generic_params);
 
     // Translate the span information
     let meta = meta::get_meta_from_rid(sess, tcx, &decls.file_to_id, info.rid);
 
+    // Tag the external types with their crate of origin
+    let extern_crate = if info.is_local() {
+        None
+    } else {
+        Some(tcx.crate_name(info.rid.krate).to_string())
+    };
+
     let type_def = ty::TypeDecl {
         def_id: trans_id,
         meta,
         name,
+        extern_crate,
         region_params,
         type_params,
+        const_generic_params,
         kind,
         // For now, initialize the regions hierarchy with a dummy value:
         // we compute it later (after returning to [translate_types]
@@ -829,8 +1338,11 @@ pub fn translate_types(
                     );
                 }
             },
-            DeclarationGroup::Fun(_) | DeclarationGroup::Global(_) => {
-                // Ignore the functions and constants
+            DeclarationGroup::Fun(_)
+            | DeclarationGroup::Global(_)
+            | DeclarationGroup::TraitDecl(_)
+            | DeclarationGroup::TraitImpl(_) => {
+                // Ignore the functions, constants and traits
             }
         }
     }
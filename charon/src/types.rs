@@ -1,12 +1,14 @@
 #![allow(dead_code)]
 
+use crate::gast::{TraitDeclId, TraitItemName};
 use crate::meta::Meta;
 use crate::names::TypeName;
 use crate::regions_hierarchy::RegionGroups;
 pub use crate::types_utils::*;
+use crate::values::ScalarValue;
 use im::Vector;
 use macros::{generate_index_type, EnumAsGetters, EnumIsA, VariantIndexArity, VariantName};
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 
 pub type FieldName = String;
 
@@ -20,6 +22,8 @@ generate_index_type!(TypeDeclId);
 generate_index_type!(VariantId);
 generate_index_type!(FieldId);
 generate_index_type!(RegionVarId);
+generate_index_type!(ConstGenericVarId);
+generate_index_type!(UserAssumedTyId);
 
 /// Type variable.
 /// We make sure not to mix variables and type variables by having two distinct
@@ -32,6 +36,17 @@ pub struct TypeVar {
     pub name: String,
 }
 
+/// Const generic variable, for instance `N` in `struct Buf<const N: usize>`.
+#[derive(Debug, Clone, Serialize)]
+pub struct ConstGenericVar {
+    /// Unique index identifying the variable
+    pub index: ConstGenericVarId::Id,
+    /// Const generic name
+    pub name: String,
+    /// Type of the const generic
+    pub ty: IntegerTy,
+}
+
 /// Region variable.
 #[derive(Debug, Clone, Serialize)]
 pub struct RegionVar {
@@ -52,6 +67,13 @@ pub enum Region<Rid: Copy + Eq> {
     Static,
     /// Non-static region.
     Var(Rid),
+    /// A region bound by a function pointer type, for instance `'a` in
+    /// `for<'a> fn(&'a u32) -> &'a u32`.
+    ///
+    /// We use De Bruijn indices: the first field identifies the binder (0 is
+    /// the innermost function pointer type), the second field identifies the
+    /// region among the ones bound by this binder.
+    Bound(usize, RegionVarId::Id),
 }
 
 /// The type of erased regions. See [`Ty`](Ty) for more explanations.
@@ -80,8 +102,12 @@ pub struct TypeDecl {
     /// Meta information associated with the type.
     pub meta: Meta,
     pub name: TypeName,
+    /// The name of the crate the type comes from, if the type is external
+    /// (`None` if the type is defined in the extracted crate).
+    pub extern_crate: Option<String>,
     pub region_params: RegionVarId::Vector<RegionVar>,
     pub type_params: TypeVarId::Vector<TypeVar>,
+    pub const_generic_params: ConstGenericVarId::Vector<ConstGenericVar>,
     /// The lifetime's hierarchy between the different regions.
     pub regions_hierarchy: RegionGroups,
     /// The type kind: enum, struct, or opaque.
@@ -92,9 +118,12 @@ pub struct TypeDecl {
 pub enum TypeDeclKind {
     Struct(FieldId::Vector<Field>),
     Enum(VariantId::Vector<Variant>),
+    /// A union: all the fields share the same memory location.
+    Union(FieldId::Vector<Field>),
     /// An opaque type.
     ///
-    /// Either a local type marked as opaque, or an external type.
+    /// Either a local type marked as opaque, or an external type (unless
+    /// we extract the external ADTs with `--transparent-external-adts`).
     Opaque,
 }
 
@@ -128,6 +157,12 @@ pub enum IntegerTy {
     U128,
 }
 
+#[derive(Debug, PartialEq, Eq, Copy, Clone, EnumIsA, VariantName, Serialize)]
+pub enum FloatTy {
+    F32,
+    F64,
+}
+
 #[derive(Debug, PartialEq, Eq, Clone, Copy, VariantName, EnumIsA, Serialize)]
 pub enum RefKind {
     Mut,
@@ -186,7 +221,10 @@ where
     /// - tuples (including `unit`, which is a 0-tuple)
     /// - assumed types
     /// The information on the nature of the ADT is stored in (`TypeId`)[TypeId].
-    Adt(TypeId, Vector<R>, Vector<Ty<R>>),
+    /// The last vector contains the const generic arguments. We box it to keep
+    /// [Ty] small: most ADTs don't have const generics, and the size of the
+    /// types has a big impact on the stack usage of the (recursive) passes.
+    Adt(TypeId, Vector<R>, Vector<Ty<R>>, Box<Vector<ConstGeneric>>),
     TypeVar(TypeVarId::Id),
     Bool,
     Char,
@@ -204,10 +242,10 @@ where
     /// TODO: but do we really use this type for variables?...
     Never,
     Integer(IntegerTy),
-    // We don't support floating point numbers on purpose
+    Float(FloatTy),
     Str,
-    // TODO: there should be a constant with the array
-    Array(Box<Ty<R>>),
+    /// An array, with its length (for instance `[u8; 32]`)
+    Array(Box<Ty<R>>, ConstGeneric),
     Slice(Box<Ty<R>>),
     /// A borrow
     Ref(R, Box<Ty<R>>, RefKind),
@@ -233,6 +271,73 @@ where
     /// For now, we detect this case (this is hardcoded in [crate::register] and
     /// [crate::translate_functions_to_ullbc]) to rewrite it to `free(move b)`.
     RawPtr(Box<Ty<R>>, RefKind),
+    /// A projection on an associated type of a trait, for instance:
+    /// `<T as Iterator>::Item`.
+    ///
+    /// Note that we normalize the projections whenever possible: we only use
+    /// this variant when the implementation of the trait is not known (typically,
+    /// because the `Self` type contains type parameters).
+    Projection(Box<TraitRef<R>>, TraitItemName),
+    /// A function pointer, for instance `fn(u32) -> bool`.
+    ///
+    /// We also use this type for the function items (i.e., the type of the
+    /// functions used as values, like `f` in `let g = f;`), which can always
+    /// be coerced to function pointers.
+    ///
+    /// The regions bound by the function pointer are referred to with
+    /// [Region::Bound].
+    FnPtr(Vector<Ty<R>>, Box<Ty<R>>),
+    /// A trait object, for instance `dyn Trait<u32> + Send + 'a`.
+    ///
+    /// We store the predicates satisfied by the object (the principal trait,
+    /// the auto traits and the constraints on the associated types), and the
+    /// region bound of the object (`'a`).
+    DynTrait(Vector<DynPredicate<R>>, R),
+}
+
+/// A constant used as a generic argument, for instance the length of an
+/// array type.
+///
+/// This is either a concrete value, or a const generic variable (for instance,
+/// `N` in `[u8; N]`).
+#[derive(Debug, PartialEq, Eq, Clone, VariantName, EnumIsA, EnumAsGetters, Serialize)]
+pub enum ConstGeneric {
+    /// A concrete value
+    Value(ScalarValue),
+    /// A const generic variable
+    Var(ConstGenericVarId::Id),
+}
+
+/// A reference to a trait, instantiated with some generic arguments.
+/// For instance, in `<u32 as From<u8>>`: the trait is `From`, the self type
+/// is `u32` and the type arguments are `[u32, u8]`.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct TraitRef<R>
+where
+    R: Clone + std::cmp::Eq,
+{
+    pub trait_id: TraitDeclId::Id,
+    pub region_args: Vector<R>,
+    /// Note that the first type argument is always the `Self` type.
+    pub type_args: Vector<Ty<R>>,
+    pub const_generic_args: Vector<ConstGeneric>,
+}
+
+/// A predicate satisfied by a trait object. See [Ty::DynTrait].
+#[derive(Debug, PartialEq, Eq, Clone, VariantName, EnumIsA, EnumAsGetters, VariantIndexArity)]
+pub enum DynPredicate<R>
+where
+    R: Clone + std::cmp::Eq,
+{
+    /// The object implements a trait, for instance `Trait<u32>` in
+    /// `dyn Trait<u32>` (this is also used for the auto traits, like `Send`).
+    ///
+    /// The `Self` type is the trait object itself: contrary to [TraitRef],
+    /// the type arguments don't contain the `Self` type.
+    Trait(TraitDeclId::Id, Vector<R>, Vector<Ty<R>>),
+    /// A constraint on an associated type of a trait implemented by the
+    /// object, for instance `Item = u32` in `dyn Iterator<Item = u32>`.
+    Projection(TraitDeclId::Id, TraitItemName, Ty<R>),
 }
 
 /// Type with *R*egions.
@@ -247,12 +352,21 @@ pub type RTy = Ty<Region<RegionVarId::Id>>;
 /// Used in function bodies, "general" value types, etc.
 pub type ETy = Ty<ErasedRegion>;
 
+/// The variance of a type in one of its type parameters.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, EnumIsA, VariantName, Serialize, Deserialize)]
+pub enum Variance {
+    Covariant,
+    /// For instance, `Cell<T>` is invariant in `T`
+    Invariant,
+}
+
 /// Assumed types identifiers.
 ///
-/// WARNING: for now, all the assumed types are covariant in the generic
-/// parameters (if there are). Adding types which don't satisfy this
-/// will require to update the code abstracting the signatures (to properly
-/// take into account the lifetime constraints).
+/// WARNING: not all the assumed types are covariant in their type parameters
+/// (`Cell` and `RefCell` are invariant). We don't use the variances in Charon
+/// (the regions hierarchy doesn't depend on them): we export them (see
+/// [crate::assumed::get_assumed_types_variances]), and the code abstracting
+/// the signatures in the backends must take them into account.
 ///
 /// TODO: update to not hardcode the types (except `Box` maybe) and be more
 /// modular.
@@ -270,4 +384,15 @@ pub enum AssumedTy {
     PtrUnique,
     /// Same comments as for [AssumedTy::PtrUnique]
     PtrNonNull,
+    /// `core::cell::Cell`: comes from the standard library
+    Cell,
+    /// `core::cell::RefCell`: comes from the standard library
+    RefCell,
+    /// `alloc::rc::Rc`: comes from the standard library
+    Rc,
+    /// `alloc::sync::Arc`: comes from the standard library
+    Arc,
+    /// A type declared as primitive by the user (see
+    /// [crate::assumed::UserAssumedDecls])
+    User(UserAssumedTyId::Id),
 }
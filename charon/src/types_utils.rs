@@ -4,18 +4,20 @@
 use crate::assumed::get_name_from_type_id;
 use crate::common::*;
 use crate::formatter::Formatter;
+use crate::gast::trait_decl_id_to_pretty_string;
 use crate::id_vector;
 use crate::types::*;
 use crate::ullbc_ast::GlobalDeclId;
 use im::{HashMap, OrdSet, Vector};
-use rustc_middle::ty::{IntTy, UintTy};
-use serde::ser::SerializeTupleVariant;
+use rustc_middle::ty::{FloatTy as FloatTy_, IntTy, UintTy};
+use serde::ser::{SerializeStruct, SerializeTupleVariant};
 use serde::{Serialize, Serializer};
 use std::iter::FromIterator;
 use std::iter::Iterator;
 
 pub type RegionSubst<R> = HashMap<RegionVarId::Id, R>;
 pub type TypeSubst<R> = HashMap<TypeVarId::Id, Ty<R>>;
+pub type ConstGenericSubst = HashMap<ConstGenericVarId::Id, ConstGeneric>;
 /// Type substitution where the regions are erased
 pub type ETypeSubst = TypeSubst<ErasedRegion>;
 
@@ -36,6 +38,7 @@ impl<Rid: Copy + Eq> Region<Rid> {
         match self {
             Region::Static => "'static".to_string(),
             Region::Var(id) => ctx.format_object(*id),
+            Region::Bound(db, id) => format!("'^{db}_{id}"),
         }
     }
 }
@@ -48,12 +51,14 @@ impl<Rid1: Copy + Eq + Ord + std::hash::Hash> Region<Rid1> {
         match self {
             Region::Static => Region::Static,
             Region::Var(id) => *rsubst.get(id).unwrap(),
+            // The bound regions are local to the function pointer types
+            Region::Bound(db, id) => Region::Bound(*db, *id),
         }
     }
 
     pub fn contains_var(&self, rset: &OrdSet<Rid1>) -> bool {
         match self {
-            Region::Static => false,
+            Region::Static | Region::Bound(_, _) => false,
             Region::Var(id) => rset.contains(id),
         }
     }
@@ -78,6 +83,12 @@ impl std::string::ToString for TypeVar {
     }
 }
 
+impl std::string::ToString for ConstGenericVar {
+    fn to_string(&self) -> String {
+        format!("const {} : {}", self.name, self.ty)
+    }
+}
+
 impl std::string::ToString for RegionVar {
     fn to_string(&self) -> String {
         let id = region_var_id_to_pretty_string(self.index);
@@ -89,12 +100,12 @@ impl std::string::ToString for RegionVar {
 }
 
 impl TypeDecl {
-    /// The variant id should be `None` if it is a structure and `Some` if it
-    /// is an enumeration.
+    /// The variant id should be `None` if it is a structure or a union and
+    /// `Some` if it is an enumeration.
     pub fn get_fields(&self, variant_id: Option<VariantId::Id>) -> &FieldId::Vector<Field> {
         match &self.kind {
             TypeDeclKind::Enum(variants) => &variants.get(variant_id.unwrap()).unwrap().fields,
-            TypeDeclKind::Struct(fields) => {
+            TypeDeclKind::Struct(fields) | TypeDeclKind::Union(fields) => {
                 assert!(variant_id.is_none());
                 fields
             }
@@ -112,6 +123,7 @@ impl TypeDecl {
         &self,
         inst_regions: &Vector<Region<RegionVarId::Id>>,
         inst_types: &Vector<RTy>,
+        inst_cgs: &Vector<ConstGeneric>,
     ) -> Option<VariantId::Vector<FieldId::Vector<RTy>>> {
         // Introduce the substitutions
         let r_subst = make_region_subst(
@@ -119,22 +131,23 @@ impl TypeDecl {
             inst_regions.iter(),
         );
         let ty_subst = make_type_subst(self.type_params.iter().map(|x| x.index), inst_types.iter());
+        let cg_subst = make_const_generic_subst(
+            self.const_generic_params.iter().map(|x| x.index),
+            inst_cgs.iter(),
+        );
 
         match &self.kind {
-            TypeDeclKind::Struct(fields) => {
-                Option::Some(VariantId::Vector::from(vec![FieldId::Vector::from_iter(
-                    fields
-                        .iter()
-                        .map(|f| f.ty.substitute_regions_types(&r_subst, &ty_subst)),
-                )]))
-            }
+            // We see the fields of a union as the fields of a single variant
+            TypeDeclKind::Struct(fields) | TypeDeclKind::Union(fields) => Option::Some(
+                VariantId::Vector::from(vec![FieldId::Vector::from_iter(fields.iter().map(|f| {
+                    f.ty.substitute_regions_types(&r_subst, &ty_subst, &cg_subst)
+                }))]),
+            ),
             TypeDeclKind::Enum(variants) => {
                 Option::Some(VariantId::Vector::from_iter(variants.iter().map(|v| {
-                    FieldId::Vector::from_iter(
-                        v.fields
-                            .iter()
-                            .map(|f| f.ty.substitute_regions_types(&r_subst, &ty_subst)),
-                    )
+                    FieldId::Vector::from_iter(v.fields.iter().map(|f| {
+                        f.ty.substitute_regions_types(&r_subst, &ty_subst, &cg_subst)
+                    }))
                 })))
             }
             TypeDeclKind::Opaque => Option::None,
@@ -147,14 +160,19 @@ impl TypeDecl {
         &self,
         variant_id: Option<VariantId::Id>,
         inst_types: &Vector<ETy>,
+        inst_cgs: &Vector<ConstGeneric>,
     ) -> Vector<ETy> {
-        // Introduce the substitution
+        // Introduce the substitutions
         let ty_subst = make_type_subst(self.type_params.iter().map(|x| x.index), inst_types.iter());
+        let cg_subst = make_const_generic_subst(
+            self.const_generic_params.iter().map(|x| x.index),
+            inst_cgs.iter(),
+        );
 
         let fields = self.get_fields(variant_id);
         let field_types: Vec<ETy> = fields
             .iter()
-            .map(|f| f.ty.erase_regions_substitute_types(&ty_subst))
+            .map(|f| f.ty.erase_regions_substitute_types(&ty_subst, &cg_subst))
             .collect();
 
         Vector::from(field_types)
@@ -166,18 +184,22 @@ impl TypeDecl {
         &self,
         variant_id: Option<VariantId::Id>,
         inst_types: &Vector<ETy>,
+        inst_cgs: &Vector<ConstGeneric>,
         field_id: FieldId::Id,
     ) -> ETy {
-        // Introduce the substitution
+        // Introduce the substitutions
         let ty_subst = make_type_subst(self.type_params.iter().map(|x| x.index), inst_types.iter());
+        let cg_subst = make_const_generic_subst(
+            self.const_generic_params.iter().map(|x| x.index),
+            inst_cgs.iter(),
+        );
 
         let fields = self.get_fields(variant_id);
         let field_type = fields
             .get(field_id)
             .unwrap()
             .ty
-            .erase_regions()
-            .substitute_types(&ty_subst);
+            .erase_regions_substitute_types(&ty_subst, &cg_subst);
         field_type
     }
 
@@ -195,7 +217,11 @@ impl TypeDecl {
             .collect();
         let regions_hierarchy = regions_hierarchy.join("\n");
 
-        let params = TypeDecl::fmt_params(&self.region_params, &self.type_params);
+        let params = TypeDecl::fmt_params(
+            &self.region_params,
+            &self.type_params,
+            &self.const_generic_params,
+        );
         match &self.kind {
             TypeDeclKind::Struct(fields) => {
                 if !fields.is_empty() {
@@ -223,6 +249,17 @@ impl TypeDecl {
                     self.name, params, variants, regions_hierarchy
                 )
             }
+            TypeDeclKind::Union(fields) => {
+                let fields: Vec<String> = fields
+                    .iter()
+                    .map(|f| format!("\n  {}", f.fmt_with_ctx(ctx)))
+                    .collect();
+                let fields = fields.join(",");
+                format!(
+                    "union {}{} = {{{}\n}}\n{}",
+                    self.name, params, fields, regions_hierarchy
+                )
+            }
             TypeDeclKind::Opaque => format!(
                 "opaque type {}{}\nRegions hierarchy:\n{}",
                 self.name, params, regions_hierarchy
@@ -233,11 +270,13 @@ impl TypeDecl {
     fn fmt_params(
         region_params: &RegionVarId::Vector<RegionVar>,
         type_params: &TypeVarId::Vector<TypeVar>,
+        const_generic_params: &ConstGenericVarId::Vector<ConstGenericVar>,
     ) -> String {
-        if region_params.len() + type_params.len() > 0 {
+        if region_params.len() + type_params.len() + const_generic_params.len() > 0 {
             let regions = region_params.iter().map(|r| r.to_string());
             let type_params = type_params.iter().map(|p| p.to_string());
-            let params: Vec<String> = regions.chain(type_params).collect();
+            let cg_params = const_generic_params.iter().map(|p| p.to_string());
+            let params: Vec<String> = regions.chain(type_params).chain(cg_params).collect();
             format!("<{}>", params.join(", "))
         } else {
             "".to_string()
@@ -351,6 +390,15 @@ impl IntegerTy {
     }
 }
 
+impl FloatTy {
+    pub fn rust_float_ty_to_float_ty(ty: FloatTy_) -> FloatTy {
+        match ty {
+            FloatTy_::F32 => FloatTy::F32,
+            FloatTy_::F64 => FloatTy::F64,
+        }
+    }
+}
+
 pub fn type_def_id_to_pretty_string(id: TypeDeclId::Id) -> String {
     format!("@Adt{id}")
 }
@@ -385,6 +433,41 @@ impl std::fmt::Display for IntegerTy {
     }
 }
 
+pub fn float_ty_to_string(ty: FloatTy) -> String {
+    match ty {
+        FloatTy::F32 => "f32".to_string(),
+        FloatTy::F64 => "f64".to_string(),
+    }
+}
+
+impl std::fmt::Display for FloatTy {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::result::Result<(), std::fmt::Error> {
+        write!(f, "{}", float_ty_to_string(*self))
+    }
+}
+
+impl std::fmt::Display for ConstGeneric {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::result::Result<(), std::fmt::Error> {
+        match self {
+            ConstGeneric::Value(v) => write!(f, "{}", v.to_string()),
+            ConstGeneric::Var(id) => write!(f, "{}", const_generic_var_id_to_pretty_string(*id)),
+        }
+    }
+}
+
+impl ConstGeneric {
+    /// Substitute the const generic variables
+    pub fn substitute(
+        &self,
+        cgsubst: &dyn Fn(&ConstGenericVarId::Id) -> ConstGeneric,
+    ) -> ConstGeneric {
+        match self {
+            ConstGeneric::Value(v) => ConstGeneric::Value(*v),
+            ConstGeneric::Var(id) => cgsubst(id),
+        }
+    }
+}
+
 pub fn intty_to_string(ty: IntTy) -> String {
     match ty {
         IntTy::Isize => "isize".to_string(),
@@ -427,8 +510,9 @@ where
     /// Return true if it is actually unit (i.e.: 0-tuple)
     pub fn is_unit(&self) -> bool {
         match self {
-            Ty::Adt(TypeId::Tuple, regions, tys) => {
+            Ty::Adt(TypeId::Tuple, regions, tys, cgs) => {
                 assert!(regions.is_empty());
+                assert!(cgs.is_empty());
                 tys.is_empty()
             }
             _ => false,
@@ -437,7 +521,12 @@ where
 
     /// Return the unit type
     pub fn mk_unit() -> Ty<R> {
-        Ty::Adt(TypeId::Tuple, Vector::new(), Vector::new())
+        Ty::Adt(
+            TypeId::Tuple,
+            Vector::new(),
+            Vector::new(),
+            Box::new(Vector::new()),
+        )
     }
 
     /// Return true if this is a scalar type
@@ -464,12 +553,21 @@ where
     /// - false if adt, array...
     pub fn is_leaf(&self) -> bool {
         match self {
-            Ty::Adt(_, _, _)
-            | Ty::Array(_)
+            Ty::Adt(_, _, _, _)
+            | Ty::Array(_, _)
             | Ty::Slice(_)
             | Ty::Ref(_, _, _)
-            | Ty::RawPtr(_, _) => false,
-            Ty::TypeVar(_) | Ty::Bool | Ty::Char | Ty::Never | Ty::Integer(_) | Ty::Str => true,
+            | Ty::RawPtr(_, _)
+            | Ty::Projection(_, _)
+            | Ty::FnPtr(_, _)
+            | Ty::DynTrait(_, _) => false,
+            Ty::TypeVar(_)
+            | Ty::Bool
+            | Ty::Char
+            | Ty::Never
+            | Ty::Integer(_)
+            | Ty::Float(_)
+            | Ty::Str => true,
         }
     }
 
@@ -484,16 +582,18 @@ where
         T: Formatter<TypeVarId::Id> + Formatter<TypeDeclId::Id> + Formatter<&'a R>,
     {
         match self {
-            Ty::Adt(id, regions, inst_types) => {
+            Ty::Adt(id, regions, inst_types, cgs) => {
                 let adt_ident = id.fmt_with_ctx(ctx);
 
-                let num_params = regions.len() + inst_types.len();
+                let num_params = regions.len() + inst_types.len() + cgs.len();
 
                 let regions: Vec<String> = regions.iter().map(|r| ctx.format_object(r)).collect();
                 let mut types: Vec<String> =
                     inst_types.iter().map(|ty| ty.fmt_with_ctx(ctx)).collect();
+                let mut cgs: Vec<String> = cgs.iter().map(|cg| cg.to_string()).collect();
                 let mut all_params = regions;
                 all_params.append(&mut types);
+                all_params.append(&mut cgs);
                 let all_params = all_params.join(", ");
 
                 if id.is_tuple() {
@@ -509,8 +609,9 @@ where
             Ty::Char => "char".to_string(),
             Ty::Never => "!".to_string(),
             Ty::Integer(int_ty) => integer_ty_to_string(*int_ty),
+            Ty::Float(float_ty) => float_ty_to_string(*float_ty),
             Ty::Str => "str".to_string(),
-            Ty::Array(ty) => format!("[{}; ?]", ty.fmt_with_ctx(ctx)),
+            Ty::Array(ty, len) => format!("[{}; {}]", ty.fmt_with_ctx(ctx), len),
             Ty::Slice(ty) => format!("[{}]", ty.fmt_with_ctx(ctx)),
             Ty::Ref(r, ty, kind) => match kind {
                 RefKind::Mut => {
@@ -524,13 +625,25 @@ where
                 RefKind::Mut => format!("*const {}", ty.fmt_with_ctx(ctx)),
                 RefKind::Shared => format!("*mut {}", ty.fmt_with_ctx(ctx)),
             },
+            Ty::Projection(trait_ref, name) => {
+                format!("{}::{name}", trait_ref.fmt_with_ctx(ctx))
+            }
+            Ty::FnPtr(inputs, output) => {
+                let inputs: Vec<String> = inputs.iter().map(|ty| ty.fmt_with_ctx(ctx)).collect();
+                format!("fn({}) -> {}", inputs.join(", "), output.fmt_with_ctx(ctx))
+            }
+            Ty::DynTrait(preds, region) => {
+                let mut preds: Vec<String> = preds.iter().map(|p| p.fmt_with_ctx(ctx)).collect();
+                preds.push(ctx.format_object(region));
+                format!("dyn ({})", preds.join(" + "))
+            }
         }
     }
 
     /// Return true if the type is Box
     pub fn is_box(&self) -> bool {
         match self {
-            Ty::Adt(TypeId::Assumed(AssumedTy::Box), regions, tys) => {
+            Ty::Adt(TypeId::Assumed(AssumedTy::Box), regions, tys, _) => {
                 assert!(regions.is_empty());
                 assert!(tys.len() == 1);
                 true
@@ -541,7 +654,7 @@ where
 
     pub fn as_box(&self) -> Option<&Ty<R>> {
         match self {
-            Ty::Adt(TypeId::Assumed(AssumedTy::Box), regions, tys) => {
+            Ty::Adt(TypeId::Assumed(AssumedTy::Box), regions, tys, _) => {
                 assert!(regions.is_empty());
                 assert!(tys.len() == 1);
                 Some(tys.get(0).unwrap())
@@ -553,7 +666,7 @@ where
     /// Return true if the type is Vec
     pub fn is_vec(&self) -> bool {
         match self {
-            Ty::Adt(TypeId::Assumed(AssumedTy::Vec), regions, tys) => {
+            Ty::Adt(TypeId::Assumed(AssumedTy::Vec), regions, tys, _) => {
                 assert!(regions.is_empty());
                 assert!(tys.len() == 1);
                 true
@@ -562,9 +675,21 @@ where
         }
     }
 
+    /// Return the type of the value shared by a `Rc` or an `Arc`
+    pub fn as_rc_or_arc(&self) -> Option<&Ty<R>> {
+        match self {
+            Ty::Adt(TypeId::Assumed(AssumedTy::Rc | AssumedTy::Arc), regions, tys, _) => {
+                assert!(regions.is_empty());
+                assert!(tys.len() == 1);
+                Some(tys.get(0).unwrap())
+            }
+            _ => None,
+        }
+    }
+
     pub fn as_vec(&self) -> Option<&Ty<R>> {
         match self {
-            Ty::Adt(TypeId::Assumed(AssumedTy::Vec), regions, tys) => {
+            Ty::Adt(TypeId::Assumed(AssumedTy::Vec), regions, tys, _) => {
                 assert!(regions.is_empty());
                 assert!(tys.len() == 1);
                 Some(tys.get(0).unwrap())
@@ -580,13 +705,34 @@ impl<Rid: Copy + Eq + Ord + std::hash::Hash> Ty<Region<Rid>> {
     pub fn contains_region_var(&self, rset: &OrdSet<Rid>) -> bool {
         match self {
             Ty::TypeVar(_) => false,
-            Ty::Bool | Ty::Char | Ty::Never | Ty::Integer(_) | Ty::Str => false,
-            Ty::Array(ty) | Ty::Slice(ty) => ty.contains_region_var(rset),
+            Ty::Bool | Ty::Char | Ty::Never | Ty::Integer(_) | Ty::Float(_) | Ty::Str => false,
+            Ty::Array(ty, _) | Ty::Slice(ty) => ty.contains_region_var(rset),
             Ty::Ref(r, ty, _) => r.contains_var(rset) || ty.contains_region_var(rset),
             Ty::RawPtr(ty, _) => ty.contains_region_var(rset),
-            Ty::Adt(_, regions, tys) => regions
+            Ty::Adt(_, regions, tys, _) => regions
                 .iter()
                 .any(|r| r.contains_var(rset) || tys.iter().any(|x| x.contains_region_var(rset))),
+            Ty::Projection(trait_ref, _) => {
+                trait_ref.region_args.iter().any(|r| r.contains_var(rset))
+                    || trait_ref
+                        .type_args
+                        .iter()
+                        .any(|x| x.contains_region_var(rset))
+            }
+            Ty::FnPtr(inputs, output) => {
+                inputs.iter().any(|x| x.contains_region_var(rset))
+                    || output.contains_region_var(rset)
+            }
+            Ty::DynTrait(preds, region) => {
+                region.contains_var(rset)
+                    || preds.iter().any(|p| match p {
+                        DynPredicate::Trait(_, regions, tys) => {
+                            regions.iter().any(|r| r.contains_var(rset))
+                                || tys.iter().any(|x| x.contains_region_var(rset))
+                        }
+                        DynPredicate::Projection(_, _, ty) => ty.contains_region_var(rset),
+                    })
+            }
         }
     }
 }
@@ -595,6 +741,10 @@ pub fn type_var_id_to_pretty_string(id: TypeVarId::Id) -> String {
     format!("@T{id}")
 }
 
+pub fn const_generic_var_id_to_pretty_string(id: ConstGenericVarId::Id) -> String {
+    format!("@Cg{id}")
+}
+
 impl<Rid: Copy + Eq> std::fmt::Display for Region<Rid>
 where
     Rid: std::fmt::Display,
@@ -603,6 +753,7 @@ where
         match self {
             Region::Static => write!(f, "'static"),
             Region::Var(id) => write!(f, "'_{id}"),
+            Region::Bound(db, id) => write!(f, "'^{db}_{id}"),
         }
     }
 }
@@ -702,28 +853,59 @@ where
         &self,
         rsubst: &dyn Fn(&R) -> R1,
         tsubst: &dyn Fn(&TypeVarId::Id) -> Ty<R1>,
+        cgsubst: &dyn Fn(&ConstGenericVarId::Id) -> ConstGeneric,
     ) -> Ty<R1>
     where
         R1: Clone + Eq,
     {
         match self {
-            Ty::Adt(id, regions, tys) => {
+            Ty::Adt(id, regions, tys, cgs) => {
                 let nregions = Ty::substitute_regions(regions, rsubst);
-                let ntys = tys.iter().map(|ty| ty.substitute(rsubst, tsubst)).collect();
-                Ty::Adt(id.clone(), nregions, ntys)
+                let ntys = tys
+                    .iter()
+                    .map(|ty| ty.substitute(rsubst, tsubst, cgsubst))
+                    .collect();
+                let ncgs = cgs.iter().map(|cg| cg.substitute(cgsubst)).collect();
+                Ty::Adt(id.clone(), nregions, ntys, Box::new(ncgs))
             }
             Ty::TypeVar(id) => tsubst(id),
             Ty::Bool => Ty::Bool,
             Ty::Char => Ty::Char,
             Ty::Never => Ty::Never,
             Ty::Integer(k) => Ty::Integer(*k),
+            Ty::Float(k) => Ty::Float(*k),
             Ty::Str => Ty::Str,
-            Ty::Array(ty) => Ty::Array(Box::new(ty.substitute(rsubst, tsubst))),
-            Ty::Slice(ty) => Ty::Slice(Box::new(ty.substitute(rsubst, tsubst))),
-            Ty::Ref(rid, ty, kind) => {
-                Ty::Ref(rsubst(rid), Box::new(ty.substitute(rsubst, tsubst)), *kind)
+            Ty::Array(ty, len) => Ty::Array(
+                Box::new(ty.substitute(rsubst, tsubst, cgsubst)),
+                len.substitute(cgsubst),
+            ),
+            Ty::Slice(ty) => Ty::Slice(Box::new(ty.substitute(rsubst, tsubst, cgsubst))),
+            Ty::Ref(rid, ty, kind) => Ty::Ref(
+                rsubst(rid),
+                Box::new(ty.substitute(rsubst, tsubst, cgsubst)),
+                *kind,
+            ),
+            Ty::RawPtr(ty, kind) => {
+                Ty::RawPtr(Box::new(ty.substitute(rsubst, tsubst, cgsubst)), *kind)
             }
-            Ty::RawPtr(ty, kind) => Ty::RawPtr(Box::new(ty.substitute(rsubst, tsubst)), *kind),
+            Ty::Projection(trait_ref, name) => Ty::Projection(
+                Box::new(trait_ref.substitute(rsubst, tsubst, cgsubst)),
+                name.clone(),
+            ),
+            Ty::FnPtr(inputs, output) => Ty::FnPtr(
+                inputs
+                    .iter()
+                    .map(|ty| ty.substitute(rsubst, tsubst, cgsubst))
+                    .collect(),
+                Box::new(output.substitute(rsubst, tsubst, cgsubst)),
+            ),
+            Ty::DynTrait(preds, region) => Ty::DynTrait(
+                preds
+                    .iter()
+                    .map(|p| p.substitute(rsubst, tsubst, cgsubst))
+                    .collect(),
+                rsubst(region),
+            ),
         }
     }
 
@@ -736,32 +918,56 @@ where
 
     /// Substitute the type parameters
     pub fn substitute_types(&self, subst: &TypeSubst<R>) -> Self {
-        self.substitute(&|r| *r, &|tid| subst.get(tid).unwrap().clone())
+        self.substitute(&|r| *r, &|tid| subst.get(tid).unwrap().clone(), &|cgid| {
+            ConstGeneric::Var(*cgid)
+        })
     }
 
     /// Erase the regions
     pub fn erase_regions(&self) -> ETy {
-        self.substitute(&|_| ErasedRegion::Erased, &|tid| Ty::TypeVar(*tid))
+        self.substitute(
+            &|_| ErasedRegion::Erased,
+            &|tid| Ty::TypeVar(*tid),
+            &|cgid| ConstGeneric::Var(*cgid),
+        )
     }
 
-    /// Erase the regions and substitute the types at the same time
-    pub fn erase_regions_substitute_types(&self, subst: &TypeSubst<ErasedRegion>) -> ETy {
-        self.substitute(&|_| ErasedRegion::Erased, &|tid| {
-            subst.get(tid).unwrap().clone()
-        })
+    /// Erase the regions and substitute the types and the const generics at
+    /// the same time
+    pub fn erase_regions_substitute_types(
+        &self,
+        tsubst: &TypeSubst<ErasedRegion>,
+        cgsubst: &ConstGenericSubst,
+    ) -> ETy {
+        self.substitute(
+            &|_| ErasedRegion::Erased,
+            &|tid| tsubst.get(tid).unwrap().clone(),
+            &|cgid| cgsubst.get(cgid).unwrap().clone(),
+        )
     }
 
     /// Returns `true` if the type contains some region or type variables
     pub fn contains_variables(&self) -> bool {
         match self {
             Ty::TypeVar(_) => true,
-            Ty::Bool | Ty::Char | Ty::Never | Ty::Integer(_) | Ty::Str => false,
-            Ty::Array(ty) | Ty::Slice(ty) => ty.contains_variables(),
+            Ty::Bool | Ty::Char | Ty::Never | Ty::Integer(_) | Ty::Float(_) | Ty::Str => false,
+            Ty::Array(ty, _) | Ty::Slice(ty) => ty.contains_variables(),
             Ty::Ref(_, _, _) => true, // Always contains a region identifier
             Ty::RawPtr(ty, _) => ty.contains_variables(),
-            Ty::Adt(_, regions, tys) => {
-                !regions.is_empty() || tys.iter().any(|x| x.contains_variables())
+            Ty::Adt(_, regions, tys, cgs) => {
+                !regions.is_empty()
+                    || tys.iter().any(|x| x.contains_variables())
+                    || cgs.iter().any(|x| x.is_var())
+            }
+            Ty::Projection(trait_ref, _) => {
+                !trait_ref.region_args.is_empty()
+                    || trait_ref.type_args.iter().any(|x| x.contains_variables())
+                    || trait_ref.const_generic_args.iter().any(|x| x.is_var())
+            }
+            Ty::FnPtr(inputs, output) => {
+                inputs.iter().any(|x| x.contains_variables()) || output.contains_variables()
             }
+            Ty::DynTrait(_, _) => true, // Always contains a region identifier
         }
     }
 
@@ -769,31 +975,42 @@ where
     pub fn contains_regions(&self) -> bool {
         match self {
             Ty::TypeVar(_) => false,
-            Ty::Bool | Ty::Char | Ty::Never | Ty::Integer(_) | Ty::Str => false,
-            Ty::Array(ty) | Ty::Slice(ty) => ty.contains_regions(),
+            Ty::Bool | Ty::Char | Ty::Never | Ty::Integer(_) | Ty::Float(_) | Ty::Str => false,
+            Ty::Array(ty, _) | Ty::Slice(ty) => ty.contains_regions(),
             Ty::Ref(_, _, _) => true,
             Ty::RawPtr(ty, _) => ty.contains_regions(),
-            Ty::Adt(_, regions, tys) => {
+            Ty::Adt(_, regions, tys, _) => {
                 !regions.is_empty() || tys.iter().any(|x| x.contains_regions())
             }
+            Ty::Projection(trait_ref, _) => {
+                !trait_ref.region_args.is_empty()
+                    || trait_ref.type_args.iter().any(|x| x.contains_regions())
+            }
+            Ty::FnPtr(inputs, output) => {
+                inputs.iter().any(|x| x.contains_regions()) || output.contains_regions()
+            }
+            Ty::DynTrait(_, _) => true,
         }
     }
 }
 
 // TODO: mixing Copy and Clone in the trait requirements below. Update to only use Copy.
 impl RTy {
-    /// Substitute the regions and type parameters
+    /// Substitute the regions, type parameters and const generic parameters
     pub fn substitute_regions_types(
         &self,
         rsubst: &RegionSubst<Region<RegionVarId::Id>>,
         tsubst: &TypeSubst<Region<RegionVarId::Id>>,
+        cgsubst: &ConstGenericSubst,
     ) -> Self {
         self.substitute(
             &|rid| match rid {
                 Region::Static => Region::Static,
                 Region::Var(rid) => *rsubst.get(rid).unwrap(),
+                Region::Bound(db, rid) => Region::Bound(*db, *rid),
             },
             &|tid| tsubst.get(tid).unwrap().clone(),
+            &|cgid| cgsubst.get(cgid).unwrap().clone(),
         )
     }
 }
@@ -837,6 +1054,17 @@ where
     make_subst(params, types)
 }
 
+pub fn make_const_generic_subst<
+    'a,
+    I1: Iterator<Item = ConstGenericVarId::Id>,
+    I2: Iterator<Item = &'a ConstGeneric>,
+>(
+    params: I1,
+    cgs: I2,
+) -> ConstGenericSubst {
+    make_subst(params, cgs)
+}
+
 pub fn make_region_subst<
     'a,
     R: 'a + Eq,
@@ -921,12 +1149,14 @@ impl<R: Clone + std::cmp::Eq + Serialize> Serialize for Ty<R> {
                 variant_arity,
             )?;
             match self {
-                Ty::Adt(id, regions, tys) => {
+                Ty::Adt(id, regions, tys, cgs) => {
                     vs.serialize_field(id)?;
                     let regions = VectorSerializer::new(regions);
                     vs.serialize_field(&regions)?;
                     let tys = VectorSerializer::new(tys);
                     vs.serialize_field(&tys)?;
+                    let cgs = VectorSerializer::new(cgs);
+                    vs.serialize_field(&cgs)?;
                 }
                 Ty::TypeVar(var_id) => {
                     vs.serialize_field(var_id)?;
@@ -937,8 +1167,12 @@ impl<R: Clone + std::cmp::Eq + Serialize> Serialize for Ty<R> {
                 Ty::Integer(int_ty) => {
                     vs.serialize_field(int_ty)?;
                 }
-                Ty::Array(ty) => {
+                Ty::Float(float_ty) => {
+                    vs.serialize_field(float_ty)?;
+                }
+                Ty::Array(ty, len) => {
                     vs.serialize_field(ty)?;
+                    vs.serialize_field(len)?;
                 }
                 Ty::Slice(ty) => {
                     vs.serialize_field(ty)?;
@@ -952,6 +1186,20 @@ impl<R: Clone + std::cmp::Eq + Serialize> Serialize for Ty<R> {
                     vs.serialize_field(ty)?;
                     vs.serialize_field(ref_kind)?;
                 }
+                Ty::Projection(trait_ref, name) => {
+                    vs.serialize_field(trait_ref)?;
+                    vs.serialize_field(name)?;
+                }
+                Ty::FnPtr(inputs, output) => {
+                    let inputs = VectorSerializer::new(inputs);
+                    vs.serialize_field(&inputs)?;
+                    vs.serialize_field(output)?;
+                }
+                Ty::DynTrait(preds, region) => {
+                    let preds = VectorSerializer::new(preds);
+                    vs.serialize_field(&preds)?;
+                    vs.serialize_field(region)?;
+                }
             }
             vs.end()
         } else {
@@ -964,11 +1212,196 @@ impl<R: Clone + std::cmp::Eq> Ty<R> {
     pub fn contains_never(&self) -> bool {
         match self {
             Ty::Never => true,
-            Ty::Adt(_, _, tys) => tys.iter().any(|ty| ty.contains_never()),
-            Ty::TypeVar(_) | Ty::Bool | Ty::Char | Ty::Str | Ty::Integer(_) => false,
-            Ty::Array(ty) | Ty::Slice(ty) | Ty::Ref(_, ty, _) | Ty::RawPtr(ty, _) => {
+            Ty::Adt(_, _, tys, _) => tys.iter().any(|ty| ty.contains_never()),
+            Ty::TypeVar(_) | Ty::Bool | Ty::Char | Ty::Str | Ty::Integer(_) | Ty::Float(_) => false,
+            Ty::Array(ty, _) | Ty::Slice(ty) | Ty::Ref(_, ty, _) | Ty::RawPtr(ty, _) => {
                 ty.contains_never()
             }
+            Ty::Projection(trait_ref, _) => {
+                trait_ref.type_args.iter().any(|ty| ty.contains_never())
+            }
+            Ty::FnPtr(_, _) => false,
+            Ty::DynTrait(preds, _) => preds.iter().any(|p| match p {
+                DynPredicate::Trait(_, _, tys) => tys.iter().any(|ty| ty.contains_never()),
+                DynPredicate::Projection(_, _, ty) => ty.contains_never(),
+            }),
+        }
+    }
+}
+
+impl<R: Clone + std::cmp::Eq> TraitRef<R> {
+    /// Format the trait reference as a string (`<Self as Trait<...>>`).
+    /// See [Ty::fmt_with_ctx].
+    pub fn fmt_with_ctx<'a, 'b, T>(&'a self, ctx: &'b T) -> String
+    where
+        R: 'a,
+        T: Formatter<TypeVarId::Id> + Formatter<TypeDeclId::Id> + Formatter<&'a R>,
+    {
+        let self_ty = self.type_args.get(0).unwrap().fmt_with_ctx(ctx);
+        let regions: Vec<String> = self
+            .region_args
+            .iter()
+            .map(|r| ctx.format_object(r))
+            .collect();
+        let mut types: Vec<String> = self
+            .type_args
+            .iter()
+            .skip(1)
+            .map(|ty| ty.fmt_with_ctx(ctx))
+            .collect();
+        let mut cgs: Vec<String> = self
+            .const_generic_args
+            .iter()
+            .map(|cg| cg.to_string())
+            .collect();
+        let mut params = regions;
+        params.append(&mut types);
+        params.append(&mut cgs);
+        let trait_ident = trait_decl_id_to_pretty_string(self.trait_id);
+        if params.is_empty() {
+            format!("<{self_ty} as {trait_ident}>")
+        } else {
+            format!("<{self_ty} as {trait_ident}<{}>>", params.join(", "))
+        }
+    }
+}
+
+impl<R> TraitRef<R>
+where
+    R: Copy + Clone + Eq,
+{
+    /// See [Ty::substitute]
+    pub fn substitute<R1>(
+        &self,
+        rsubst: &dyn Fn(&R) -> R1,
+        tsubst: &dyn Fn(&TypeVarId::Id) -> Ty<R1>,
+        cgsubst: &dyn Fn(&ConstGenericVarId::Id) -> ConstGeneric,
+    ) -> TraitRef<R1>
+    where
+        R1: Clone + Eq,
+    {
+        TraitRef {
+            trait_id: self.trait_id,
+            region_args: Ty::substitute_regions(&self.region_args, rsubst),
+            type_args: self
+                .type_args
+                .iter()
+                .map(|ty| ty.substitute(rsubst, tsubst, cgsubst))
+                .collect(),
+            const_generic_args: self
+                .const_generic_args
+                .iter()
+                .map(|cg| cg.substitute(cgsubst))
+                .collect(),
+        }
+    }
+}
+
+impl<R: Clone + std::cmp::Eq + Serialize> Serialize for TraitRef<R> {
+    fn serialize<S>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let mut vs = serializer.serialize_struct("TraitRef", 4)?;
+        vs.serialize_field("trait_id", &self.trait_id)?;
+        let regions = VectorSerializer::new(&self.region_args);
+        vs.serialize_field("region_args", &regions)?;
+        let tys = VectorSerializer::new(&self.type_args);
+        vs.serialize_field("type_args", &tys)?;
+        let cgs = VectorSerializer::new(&self.const_generic_args);
+        vs.serialize_field("const_generic_args", &cgs)?;
+        vs.end()
+    }
+}
+
+impl<R: Clone + std::cmp::Eq> DynPredicate<R> {
+    /// Format the predicate as a string (`Trait<...>` or `Trait::Item = ...`).
+    /// See [Ty::fmt_with_ctx].
+    pub fn fmt_with_ctx<'a, 'b, T>(&'a self, ctx: &'b T) -> String
+    where
+        R: 'a,
+        T: Formatter<TypeVarId::Id> + Formatter<TypeDeclId::Id> + Formatter<&'a R>,
+    {
+        match self {
+            DynPredicate::Trait(trait_id, regions, tys) => {
+                let regions: Vec<String> = regions.iter().map(|r| ctx.format_object(r)).collect();
+                let mut types: Vec<String> = tys.iter().map(|ty| ty.fmt_with_ctx(ctx)).collect();
+                let mut params = regions;
+                params.append(&mut types);
+                let trait_ident = trait_decl_id_to_pretty_string(*trait_id);
+                if params.is_empty() {
+                    trait_ident
+                } else {
+                    format!("{trait_ident}<{}>", params.join(", "))
+                }
+            }
+            DynPredicate::Projection(trait_id, name, ty) => {
+                let trait_ident = trait_decl_id_to_pretty_string(*trait_id);
+                format!("{trait_ident}::{name} = {}", ty.fmt_with_ctx(ctx))
+            }
+        }
+    }
+}
+
+impl<R> DynPredicate<R>
+where
+    R: Copy + Clone + Eq,
+{
+    /// See [Ty::substitute]
+    pub fn substitute<R1>(
+        &self,
+        rsubst: &dyn Fn(&R) -> R1,
+        tsubst: &dyn Fn(&TypeVarId::Id) -> Ty<R1>,
+        cgsubst: &dyn Fn(&ConstGenericVarId::Id) -> ConstGeneric,
+    ) -> DynPredicate<R1>
+    where
+        R1: Clone + Eq,
+    {
+        match self {
+            DynPredicate::Trait(trait_id, regions, tys) => DynPredicate::Trait(
+                *trait_id,
+                Ty::substitute_regions(regions, rsubst),
+                tys.iter()
+                    .map(|ty| ty.substitute(rsubst, tsubst, cgsubst))
+                    .collect(),
+            ),
+            DynPredicate::Projection(trait_id, name, ty) => DynPredicate::Projection(
+                *trait_id,
+                name.clone(),
+                ty.substitute(rsubst, tsubst, cgsubst),
+            ),
+        }
+    }
+}
+
+impl<R: Clone + std::cmp::Eq + Serialize> Serialize for DynPredicate<R> {
+    fn serialize<S>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let enum_name = "DynPredicate";
+        let variant_name = self.variant_name();
+        let (variant_index, variant_arity) = self.variant_index_arity();
+        let mut vs = serializer.serialize_tuple_variant(
+            enum_name,
+            variant_index,
+            variant_name,
+            variant_arity,
+        )?;
+        match self {
+            DynPredicate::Trait(trait_id, regions, tys) => {
+                vs.serialize_field(trait_id)?;
+                let regions = VectorSerializer::new(regions);
+                vs.serialize_field(&regions)?;
+                let tys = VectorSerializer::new(tys);
+                vs.serialize_field(&tys)?;
+            }
+            DynPredicate::Projection(trait_id, name, ty) => {
+                vs.serialize_field(trait_id)?;
+                vs.serialize_field(name)?;
+                vs.serialize_field(ty)?;
+            }
         }
+        vs.end()
     }
 }
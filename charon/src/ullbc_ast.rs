@@ -38,6 +38,12 @@ pub enum RawStatement {
     StorageDead(VarId::Id),
     /// We translate this to [crate::llbc_ast::RawStatement::Drop] in LLBC
     Deinit(Place),
+    /// A call to an intrinsic which doesn't return anything, and which is
+    /// represented as a statement in MIR (`assume`, `copy_nonoverlapping`).
+    Intrinsic(IntrinsicFunId, Vec<Operand>),
+    /// Inline assembly. In MIR, this is a terminator: we translate it to a
+    /// statement followed by a goto.
+    InlineAsm(InlineAsm),
 }
 
 #[derive(Debug, Clone, Serialize)]
@@ -72,30 +78,47 @@ pub enum RawTerminator {
         discr: Operand,
         targets: SwitchTargets,
     },
-    Panic,
+    Panic(PanicInfo),
     Return,
     Unreachable,
+    /// Abort the program. This only appears in the cleanup blocks, which we
+    /// translate only with `--unwind`.
+    Abort,
+    /// Resume unwinding: this terminates a cleanup block. We translate the
+    /// cleanup blocks only with `--unwind`.
+    UnwindResume,
     Drop {
         place: Place,
         target: BlockId::Id,
+        /// The cleanup block to go to if the drop panics. This is always `None`
+        /// unless we use `--unwind`.
+        #[serde(skip_serializing_if = "Option::is_none")]
+        unwind: Option<BlockId::Id>,
     },
     /// Function call.
-    /// For now, we only accept calls to top-level functions.
+    /// The function is either a statically known function, or a function
+    /// pointer (see [FnOperand]).
     Call {
-        func: FunId,
+        func: FnOperand,
         /// Technically, this is useless, but we still keep it because we might
         /// want to introduce some information (and the way we encode from MIR
         /// is as simple as possible - and in MIR we also have a vector of erased
         /// regions).
         region_args: Vec<ErasedRegion>,
         type_args: Vec<ETy>,
+        const_generic_args: Vec<ConstGeneric>,
         args: Vec<Operand>,
         dest: Place,
         target: BlockId::Id,
+        /// The cleanup block to go to if the function panics. This is always
+        /// `None` unless we use `--unwind`.
+        #[serde(skip_serializing_if = "Option::is_none")]
+        unwind: Option<BlockId::Id>,
     },
     Assert {
         cond: Operand,
         expected: bool,
+        kind: AssertKind,
         target: BlockId::Id,
     },
 }
@@ -1,6 +1,7 @@
 //! Implementations for [crate::ullbc_ast]
 #![allow(dead_code)]
 
+use crate::assumed;
 use crate::common::*;
 use crate::expressions::*;
 use crate::formatter::Formatter;
@@ -84,12 +85,25 @@ impl Statement {
             }
             RawStatement::StorageDead(var_id) => RawStatement::StorageDead(*var_id),
             RawStatement::Deinit(place) => RawStatement::Deinit(place.substitute(subst)),
+            RawStatement::Intrinsic(id, args) => RawStatement::Intrinsic(
+                *id,
+                Vec::from_iter(args.iter().map(|arg| arg.substitute(subst))),
+            ),
+            RawStatement::InlineAsm(asm) => RawStatement::InlineAsm(asm.substitute(subst)),
         };
 
         Statement::new(self.meta, st)
     }
 }
 
+/// Format the unwind target of a call or a drop, if there is one.
+fn fmt_unwind_target(unwind: &Option<BlockId::Id>) -> String {
+    match unwind {
+        Some(unwind) => format!(" (unwind: bb{unwind})"),
+        None => "".to_string(),
+    }
+}
+
 impl Terminator {
     pub fn new(meta: Meta, content: RawTerminator) -> Self {
         Terminator { meta, content }
@@ -103,20 +117,29 @@ impl Terminator {
                 discr: discr.substitute(subst),
                 targets: targets.substitute(subst),
             },
-            RawTerminator::Panic => RawTerminator::Panic,
+            RawTerminator::Panic(info) => RawTerminator::Panic(info.clone()),
             RawTerminator::Return => RawTerminator::Return,
             RawTerminator::Unreachable => RawTerminator::Unreachable,
-            RawTerminator::Drop { place, target } => RawTerminator::Drop {
+            RawTerminator::Abort => RawTerminator::Abort,
+            RawTerminator::UnwindResume => RawTerminator::UnwindResume,
+            RawTerminator::Drop {
+                place,
+                target,
+                unwind,
+            } => RawTerminator::Drop {
                 place: place.substitute(subst),
                 target: *target,
+                unwind: *unwind,
             },
             RawTerminator::Call {
                 func,
                 region_args,
                 type_args,
+                const_generic_args,
                 args,
                 dest,
                 target,
+                unwind,
             } => RawTerminator::Call {
                 func: func.clone(),
                 region_args: region_args.clone(),
@@ -124,17 +147,21 @@ impl Terminator {
                     .iter()
                     .map(|ty| ty.substitute_types(subst))
                     .collect(),
+                const_generic_args: const_generic_args.clone(),
                 args: Vec::from_iter(args.iter().map(|arg| arg.substitute(subst))),
                 dest: dest.substitute(subst),
                 target: *target,
+                unwind: *unwind,
             },
             RawTerminator::Assert {
                 cond,
                 expected,
+                kind,
                 target,
             } => RawTerminator::Assert {
                 cond: cond.substitute(subst),
                 expected: *expected,
+                kind: kind.clone(),
                 target: *target,
             },
         };
@@ -190,6 +217,15 @@ impl Statement {
             RawStatement::Deinit(place) => {
                 format!("@deinit({})", place.fmt_with_ctx(ctx))
             }
+            RawStatement::Intrinsic(id, args) => {
+                let args: Vec<String> = args.iter().map(|x| x.fmt_with_ctx(ctx)).collect();
+                format!(
+                    "core::intrinsics::{}({})",
+                    assumed::get_name_from_intrinsic_id(*id),
+                    args.join(", ")
+                )
+            }
+            RawStatement::InlineAsm(asm) => asm.fmt_with_ctx(ctx),
         }
     }
 }
@@ -226,27 +262,47 @@ impl Terminator {
                     format!("switch {} -> {}", discr.fmt_with_ctx(ctx), maps)
                 }
             },
-            RawTerminator::Panic => "panic".to_string(),
+            RawTerminator::Panic(info) => info.to_string(),
             RawTerminator::Return => "return".to_string(),
             RawTerminator::Unreachable => "unreachable".to_string(),
-            RawTerminator::Drop { place, target } => {
-                format!("drop {} -> bb{}", place.fmt_with_ctx(ctx), target)
+            RawTerminator::Abort => "abort".to_string(),
+            RawTerminator::UnwindResume => "unwind_resume".to_string(),
+            RawTerminator::Drop {
+                place,
+                target,
+                unwind,
+            } => {
+                format!(
+                    "drop {} -> bb{}{}",
+                    place.fmt_with_ctx(ctx),
+                    target,
+                    fmt_unwind_target(unwind)
+                )
             }
             RawTerminator::Call {
                 func,
                 region_args,
                 type_args,
+                const_generic_args,
                 args,
                 dest,
                 target,
+                unwind,
             } => {
-                let call = fmt_call(ctx, func, region_args, type_args, args);
-
-                format!("{} := {} -> bb{}", dest.fmt_with_ctx(ctx), call, target,)
+                let call = fmt_call(ctx, func, region_args, type_args, const_generic_args, args);
+
+                format!(
+                    "{} := {} -> bb{}{}",
+                    dest.fmt_with_ctx(ctx),
+                    call,
+                    target,
+                    fmt_unwind_target(unwind)
+                )
             }
             RawTerminator::Assert {
                 cond,
                 expected,
+                kind: _,
                 target,
             } => format!(
                 "assert({} == {}) -> bb{}",
@@ -523,7 +579,7 @@ impl BlockData {
         f: &mut F,
     ) {
         match rval {
-            Rvalue::Use(op) | Rvalue::UnaryOp(_, op) => f(meta, nst, op),
+            Rvalue::Use(op) | Rvalue::UnaryOp(_, op) | Rvalue::Repeat(op, _, _) => f(meta, nst, op),
             Rvalue::BinaryOp(_, o1, o2) => {
                 f(meta, nst, o1);
                 f(meta, nst, o2);
@@ -533,7 +589,11 @@ impl BlockData {
                     f(meta, nst, op);
                 }
             }
-            Rvalue::Global(_) | Rvalue::Discriminant(_) | Rvalue::Ref(_, _) => {
+            Rvalue::Global(_)
+            | Rvalue::Discriminant(_)
+            | Rvalue::Ref(_, _)
+            | Rvalue::RawPtr(_, _)
+            | Rvalue::Len(_) => {
                 // No operands: nothing to do
             }
         }
@@ -554,6 +614,23 @@ impl BlockData {
                 RawStatement::Assign(_, rvalue) => {
                     BlockData::transform_rvalue_operands(meta, &mut nst, rvalue, f);
                 }
+                RawStatement::Intrinsic(_, args) => {
+                    for arg in args {
+                        f(meta, &mut nst, arg);
+                    }
+                }
+                RawStatement::InlineAsm(asm) => {
+                    for op in &mut asm.operands {
+                        match op {
+                            InlineAsmOperand::In { value: op, .. }
+                            | InlineAsmOperand::InOut { in_value: op, .. }
+                            | InlineAsmOperand::Const(op) => f(meta, &mut nst, op),
+                            InlineAsmOperand::Out { .. } | InlineAsmOperand::Sym(_) => {
+                                // No operands: nothing to do
+                            }
+                        }
+                    }
+                }
                 RawStatement::FakeRead(_)
                 | RawStatement::SetDiscriminant(_, _)
                 | RawStatement::StorageDead(_)
@@ -575,9 +652,11 @@ impl BlockData {
                 func: _,
                 region_args: _,
                 type_args: _,
+                const_generic_args: _,
                 args,
                 dest: _,
                 target: _,
+                unwind: _,
             } => {
                 for arg in args {
                     f(meta, &mut nst, arg);
@@ -586,17 +665,21 @@ impl BlockData {
             RawTerminator::Assert {
                 cond,
                 expected: _,
+                kind: _,
                 target: _,
             } => {
                 f(meta, &mut nst, cond);
             }
-            RawTerminator::Panic
+            RawTerminator::Panic(_)
             | RawTerminator::Return
             | RawTerminator::Unreachable
+            | RawTerminator::Abort
+            | RawTerminator::UnwindResume
             | RawTerminator::Goto { target: _ }
             | RawTerminator::Drop {
                 place: _,
                 target: _,
+                unwind: _,
             } => {
                 // Nothing to do
             }
@@ -55,26 +55,37 @@ fn get_block_targets(body: &src::ExprBody, block_id: src::BlockId::Id) -> Vec<sr
     let block = body.body.get(block_id).unwrap();
 
     match &block.terminator.content {
+        // We don't reconstruct the control-flow of the unwinding paths (we
+        // forbid `--unwind` without `--ullbc`): the unwind targets are `None`
         src::RawTerminator::Goto { target }
-        | src::RawTerminator::Drop { place: _, target }
+        | src::RawTerminator::Drop {
+            place: _,
+            target,
+            unwind: _,
+        }
         | src::RawTerminator::Call {
             func: _,
             region_args: _,
             type_args: _,
+            const_generic_args: _,
             args: _,
             dest: _,
             target,
+            unwind: _,
         }
         | src::RawTerminator::Assert {
             cond: _,
             expected: _,
+            kind: _,
             target,
         } => {
             vec![*target]
         }
         src::RawTerminator::Switch { discr: _, targets } => targets.get_targets(),
-        src::RawTerminator::Panic
+        src::RawTerminator::Panic(_)
         | src::RawTerminator::Unreachable
+        | src::RawTerminator::Abort
+        | src::RawTerminator::UnwindResume
         | src::RawTerminator::Return => {
             vec![]
         }
@@ -1431,6 +1442,8 @@ fn translate_statement(st: &src::Statement) -> Option<tgt::Statement> {
             // We translate a deinit as a drop
             tgt::RawStatement::Drop(place.clone())
         }
+        src::RawStatement::Intrinsic(id, args) => tgt::RawStatement::Intrinsic(*id, args.clone()),
+        src::RawStatement::InlineAsm(asm) => tgt::RawStatement::InlineAsm(asm.clone()),
     };
     Some(tgt::Statement::new(src_meta, st))
 }
@@ -1444,12 +1457,28 @@ fn translate_terminator(
     let src_meta = terminator.meta;
 
     match &terminator.content {
-        src::RawTerminator::Panic | src::RawTerminator::Unreachable => {
-            Some(tgt::Statement::new(src_meta, tgt::RawStatement::Panic))
+        src::RawTerminator::Panic(info) => Some(tgt::Statement::new(
+            src_meta,
+            tgt::RawStatement::Panic(info.clone()),
+        )),
+        src::RawTerminator::Unreachable => {
+            let info = tgt::PanicInfo {
+                message: None,
+                location: src_meta.span,
+            };
+            Some(tgt::Statement::new(
+                src_meta,
+                tgt::RawStatement::Panic(info),
+            ))
         }
         src::RawTerminator::Return => {
             Some(tgt::Statement::new(src_meta, tgt::RawStatement::Return))
         }
+        src::RawTerminator::Abort | src::RawTerminator::UnwindResume => {
+            // Those only appear in the cleanup blocks, which are translated
+            // only with `--unwind` (which requires `--ullbc`)
+            unreachable!()
+        }
         src::RawTerminator::Goto { target } => translate_child_block(
             info,
             parent_loops,
@@ -1457,7 +1486,11 @@ fn translate_terminator(
             terminator.meta,
             *target,
         ),
-        src::RawTerminator::Drop { place, target } => {
+        src::RawTerminator::Drop {
+            place,
+            target,
+            unwind: _,
+        } => {
             let opt_child = translate_child_block(
                 info,
                 parent_loops,
@@ -1472,9 +1505,11 @@ fn translate_terminator(
             func,
             region_args,
             type_args,
+            const_generic_args,
             args,
             dest,
             target,
+            unwind: _,
         } => {
             let opt_child = translate_child_block(
                 info,
@@ -1487,6 +1522,7 @@ fn translate_terminator(
                 func: func.clone(),
                 region_args: region_args.clone(),
                 type_args: type_args.clone(),
+                const_generic_args: const_generic_args.clone(),
                 args: args.clone(),
                 dest: dest.clone(),
             });
@@ -1496,6 +1532,7 @@ fn translate_terminator(
         src::RawTerminator::Assert {
             cond,
             expected,
+            kind,
             target,
         } => {
             let opt_child = translate_child_block(
@@ -1508,6 +1545,7 @@ fn translate_terminator(
             let st = tgt::RawStatement::Assert(tgt::Assert {
                 cond: cond.clone(),
                 expected: *expected,
+                kind: kind.clone(),
             });
             let st = tgt::Statement::new(src_meta, st);
             Some(combine_statement_and_statement(st, opt_child))
@@ -1657,8 +1695,10 @@ fn is_terminal_explore(num_loops: usize, st: &tgt::Statement) -> bool {
         | tgt::RawStatement::Drop(_)
         | tgt::RawStatement::Assert(_)
         | tgt::RawStatement::Call(_)
+        | tgt::RawStatement::Intrinsic(_, _)
+        | tgt::RawStatement::InlineAsm(_)
         | tgt::RawStatement::Nop => false,
-        tgt::RawStatement::Panic | tgt::RawStatement::Return => true,
+        tgt::RawStatement::Panic(_) | tgt::RawStatement::Return => true,
         tgt::RawStatement::Break(index) => *index >= num_loops,
         tgt::RawStatement::Continue(_index) => true,
         tgt::RawStatement::Sequence(st1, st2) => {
@@ -1862,6 +1902,7 @@ fn translate_function(
         meta: src_def.meta,
         name: src_def.name.clone(),
         signature: src_def.signature.clone(),
+        is_unsafe: src_def.is_unsafe,
         body: src_def
             .body
             .as_ref()
@@ -22,9 +22,13 @@ generate_index_type!(VarId);
 #[derive(Debug, PartialEq, Eq, Clone, VariantName, EnumIsA, EnumAsGetters, Serialize)]
 pub enum PrimitiveValue {
     Scalar(ScalarValue),
+    Float(FloatValue),
     Bool(bool),
     Char(char),
+    /// A string literal. The constant has type `&str`.
     String(String),
+    /// A byte string (`b"abc"`). The constant has type `&[u8; N]` or `&[u8]`.
+    ByteStr(Vec<u8>),
 }
 
 /// It might be a good idea to use a structure:
@@ -52,3 +56,18 @@ pub enum ScalarValue {
     U64(u64),
     U128(u128),
 }
+
+/// A floating-point value.
+///
+/// We store the bit pattern of the value rather than the value itself: this
+/// way, the value can be compared, hashed and serialized without any loss
+/// (including for the NaNs, the infinities or the negative zero). As for
+/// [ScalarValue], we implement a custom serialization, which serializes the
+/// bit patterns to strings.
+#[derive(
+    Debug, PartialEq, Eq, Copy, Clone, EnumIsA, EnumAsGetters, VariantName, VariantIndexArity, Hash,
+)]
+pub enum FloatValue {
+    F32(u32),
+    F64(u64),
+}
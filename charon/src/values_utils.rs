@@ -306,13 +306,33 @@ impl std::string::ToString for ScalarValue {
     }
 }
 
+impl FloatValue {
+    pub fn get_float_ty(&self) -> FloatTy {
+        match self {
+            FloatValue::F32(_) => FloatTy::F32,
+            FloatValue::F64(_) => FloatTy::F64,
+        }
+    }
+}
+
+impl std::string::ToString for FloatValue {
+    fn to_string(&self) -> String {
+        match self {
+            FloatValue::F32(bits) => format!("{:?} : f32", f32::from_bits(*bits)),
+            FloatValue::F64(bits) => format!("{:?} : f64", f64::from_bits(*bits)),
+        }
+    }
+}
+
 impl std::string::ToString for PrimitiveValue {
     fn to_string(&self) -> String {
         match self {
             PrimitiveValue::Scalar(v) => v.to_string(),
+            PrimitiveValue::Float(v) => v.to_string(),
             PrimitiveValue::Bool(v) => v.to_string(),
             PrimitiveValue::Char(v) => v.to_string(),
-            PrimitiveValue::String(v) => v.to_string(),
+            PrimitiveValue::String(v) => format!("{v:?}"),
+            PrimitiveValue::ByteStr(v) => format!("b\"{}\"", v.escape_ascii()),
         }
     }
 }
@@ -352,3 +372,25 @@ impl Serialize for ScalarValue {
         }
     }
 }
+
+impl Serialize for FloatValue {
+    fn serialize<S>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let enum_name = "FloatValue";
+        let variant_name = self.variant_name();
+        let (variant_index, variant_arity) = self.variant_index_arity();
+        let mut vs = serializer.serialize_tuple_variant(
+            enum_name,
+            variant_index,
+            variant_name,
+            variant_arity,
+        )?;
+        match self {
+            FloatValue::F32(bits) => vs.serialize_field(&bits.to_string())?,
+            FloatValue::F64(bits) => vs.serialize_field(&bits.to_string())?,
+        };
+        vs.end()
+    }
+}
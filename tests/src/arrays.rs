@@ -0,0 +1,84 @@
+//! Tests with arrays
+#![allow(dead_code)]
+
+/// A structure containing a fixed-size buffer
+pub struct Block {
+    pub data: [u8; 16],
+    pub len: usize,
+}
+
+pub fn new_block() -> Block {
+    Block {
+        data: [0; 16],
+        len: 0,
+    }
+}
+
+/// Arrays in the signature
+pub fn array_id(x: [u32; 4]) -> [u32; 4] {
+    x
+}
+
+/// Array initialization by repetition
+pub fn array_repeat(x: u32) -> [u32; 32] {
+    [x; 32]
+}
+
+/// Array literals
+pub fn array_literal(x: u32, y: u32) -> [u32; 3] {
+    [x, y, 0]
+}
+
+pub fn array_of_pairs(x: u32) -> [(u32, bool); 2] {
+    [(x, true), (0, false)]
+}
+
+/// Arrays under references
+pub fn array_ref(x: &[u32; 2]) -> &[u32; 2] {
+    x
+}
+
+/// Indexing
+pub fn index_array(x: [u32; 4], i: usize) -> u32 {
+    x[i]
+}
+
+pub fn index_array_mut(x: &mut [u32; 4], i: usize) {
+    x[i] = 0;
+}
+
+pub fn index_slice(s: &[u32], i: usize) -> u32 {
+    s[i]
+}
+
+/// Slice patterns
+pub fn slice_first(s: &[u32]) -> Option<u32> {
+    match s {
+        [first, ..] => Some(*first),
+        [] => None,
+    }
+}
+
+pub fn slice_first_last(s: &[u32]) -> u32 {
+    match s {
+        [first, .., last] => *first + *last,
+        _ => 0,
+    }
+}
+
+pub fn slice_rest(s: &[u32]) -> &[u32] {
+    match s {
+        [_, rest @ ..] => rest,
+        [] => s,
+    }
+}
+
+pub fn array_pattern(x: [u32; 4]) -> u32 {
+    let [a, _, c, _] = x;
+    a + c
+}
+
+pub fn array_subarray(x: &[u32; 4]) -> &[u32; 2] {
+    let [_, mid @ .., _] = x;
+    mid
+}
@@ -0,0 +1,50 @@
+//! Tests with casts
+#![allow(dead_code)]
+
+pub fn u32_to_u64(x: u32) -> u64 {
+    x as u64
+}
+
+pub fn i64_to_u8(x: i64) -> u8 {
+    x as u8
+}
+
+pub fn bool_to_u8(b: bool) -> u8 {
+    b as u8
+}
+
+pub fn char_to_u32(c: char) -> u32 {
+    c as u32
+}
+
+pub fn u8_to_char(x: u8) -> char {
+    x as char
+}
+
+pub enum Token {
+    Plus,
+    Minus,
+    Star,
+}
+
+pub enum Code {
+    Ok = 0,
+    NotFound = 404,
+    Error = 500,
+}
+
+pub fn token_to_i32(t: Token) -> i32 {
+    t as i32
+}
+
+pub fn code_to_u16(c: Code) -> u16 {
+    c as u16
+}
+
+pub fn digit(c: char) -> u32 {
+    (c as u32) - ('0' as u32)
+}
+
+pub fn to_slice(x: &[u32; 3]) -> &[u32] {
+    x
+}
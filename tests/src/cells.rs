@@ -0,0 +1,61 @@
+//! Interior mutability and shared ownership: `Cell`, `RefCell`, `Rc` and
+//! `Arc` are assumed types
+#![allow(dead_code)]
+
+use std::cell::{Cell, RefCell};
+use std::rc::Rc;
+use std::sync::Arc;
+
+pub fn cell_new(x: u32) -> Cell<u32> {
+    Cell::new(x)
+}
+
+pub fn cell_incr(c: &Cell<u32>) {
+    c.set(c.get() + 1)
+}
+
+/// A cache, in the style of the ones which motivated those assumed types
+pub struct Cache {
+    pub hits: Cell<u32>,
+    pub values: RefCell<Vec<u32>>,
+}
+
+pub fn cache_new() -> Cache {
+    Cache {
+        hits: Cell::new(0),
+        values: RefCell::new(Vec::new()),
+    }
+}
+
+pub fn cache_len(c: &Cache) -> usize {
+    c.values.borrow().len()
+}
+
+pub fn cache_push(c: &Cache, x: u32) {
+    c.hits.set(c.hits.get() + 1);
+    c.values.borrow_mut().push(x)
+}
+
+pub fn rc_new(x: u32) -> Rc<u32> {
+    Rc::new(x)
+}
+
+pub fn rc_share(x: &Rc<Cache>) -> (Rc<Cache>, Rc<Cache>) {
+    (x.clone(), x.clone())
+}
+
+pub fn rc_read(x: &Rc<u32>) -> u32 {
+    **x
+}
+
+pub fn arc_new(x: u32) -> Arc<u32> {
+    Arc::new(x)
+}
+
+pub fn arc_share(x: &Arc<u32>) -> Arc<u32> {
+    x.clone()
+}
+
+pub fn arc_read(x: Arc<u32>) -> u32 {
+    *x
+}
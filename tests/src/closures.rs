@@ -0,0 +1,64 @@
+//! Tests with closures
+#![allow(dead_code)]
+
+pub fn incr_u32(x: u32) -> u32 {
+    x + 1
+}
+
+/// A closure which doesn't capture anything
+pub fn test_closure_u32(x: u32) -> u32 {
+    let f = |y: u32| y + 1;
+    f(x)
+}
+
+/// A closure which captures a variable by reference (`Fn`)
+pub fn test_closure_capture_ref(x: u32, y: u32) -> u32 {
+    let f = |z: u32| x + z;
+    f(y)
+}
+
+/// A closure which mutates a captured variable (`FnMut`)
+pub fn test_closure_mut(x: u32) -> u32 {
+    let mut count = x;
+    let mut incr = || count += 1;
+    incr();
+    incr();
+    count
+}
+
+/// A closure which takes ownership of its state (`FnOnce`)
+pub fn test_closure_once(v: Vec<u32>) -> Vec<u32> {
+    let f = move || v;
+    f()
+}
+
+/// A closure with several arguments
+pub fn test_closure_args(x: u32) -> u32 {
+    let f = |a: u32, b: u32| a + b + x;
+    f(1, 2)
+}
+
+pub fn apply_to<F: Fn(u32) -> u32>(f: F, x: u32) -> u32 {
+    f(x)
+}
+
+pub fn apply_to_mut<F: FnMut(u32)>(mut f: F, x: u32) {
+    f(x)
+}
+
+/// Give a closure to a generic function
+pub fn test_apply_closure(x: u32) -> u32 {
+    apply_to(|y| y + x, 3)
+}
+
+pub fn test_apply_closure_mut(x: u32) -> u32 {
+    let mut sum = 0;
+    apply_to_mut(|y| sum += y, x);
+    sum
+}
+
+/// A closure inside a generic function
+pub fn test_closure_generic<T: Clone>(x: &T) -> T {
+    let f = |y: &T| y.clone();
+    f(x)
+}
@@ -0,0 +1,48 @@
+//! Tests with const generics
+#![allow(dead_code)]
+
+/// A structure parameterized by a const generic
+pub struct Buf<const N: usize> {
+    pub data: [u8; N],
+    pub len: usize,
+}
+
+/// An enumeration mixing type parameters and const generics
+pub enum Either<T, const N: usize> {
+    Single(T),
+    Many([T; N]),
+}
+
+pub fn buf_new<const N: usize>() -> Buf<N> {
+    Buf {
+        data: [0; N],
+        len: 0,
+    }
+}
+
+pub fn buf_len<const N: usize>(b: Buf<N>) -> usize {
+    b.len
+}
+
+pub fn array_id<const N: usize>(x: [u32; N]) -> [u32; N] {
+    x
+}
+
+pub fn mk_many<T, const N: usize>(x: [T; N]) -> Either<T, N> {
+    Either::Many(x)
+}
+
+/// Calls with instantiated const generics
+pub fn call_array_id() -> [u32; 2] {
+    array_id([0, 1])
+}
+
+pub fn buf_new_4() -> Buf<4> {
+    buf_new::<4>()
+}
+
+/// Call with a const generic coming from the caller
+pub fn call_buf_new<const N: usize>() -> usize {
+    let b = buf_new::<N>();
+    buf_len(b)
+}
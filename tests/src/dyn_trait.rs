@@ -0,0 +1,72 @@
+//! Tests with trait objects
+#![allow(dead_code)]
+
+pub trait Shape {
+    fn area(&self) -> u32;
+}
+
+pub struct Square {
+    pub side: u32,
+}
+
+impl Shape for Square {
+    fn area(&self) -> u32 {
+        self.side * self.side
+    }
+}
+
+pub struct Rectangle {
+    pub width: u32,
+    pub height: u32,
+}
+
+impl Shape for Rectangle {
+    fn area(&self) -> u32 {
+        self.width * self.height
+    }
+}
+
+/// Virtual call through a shared reference
+pub fn get_area(s: &dyn Shape) -> u32 {
+    s.area()
+}
+
+/// Unsizing coercion from `&Square` to `&dyn Shape`
+pub fn square_area(side: u32) -> u32 {
+    let sq = Square { side };
+    get_area(&sq)
+}
+
+/// Unsizing coercion from `Box<Rectangle>` to `Box<dyn Shape>`
+pub fn boxed_rectangle(width: u32, height: u32) -> Box<dyn Shape> {
+    Box::new(Rectangle { width, height })
+}
+
+/// Virtual call through a box
+pub fn boxed_area(s: Box<dyn Shape>) -> u32 {
+    s.area()
+}
+
+/// Trait object with an auto trait
+pub fn get_area_send(s: &(dyn Shape + Send)) -> u32 {
+    s.area()
+}
+
+pub trait Container<T> {
+    fn first(&self) -> T;
+}
+
+impl Container<u32> for [u32; 2] {
+    fn first(&self) -> u32 {
+        self[0]
+    }
+}
+
+/// Trait object with a generic trait
+pub fn first_of(c: &dyn Container<u32>) -> u32 {
+    c.first()
+}
+
+pub fn use_first_of(x: [u32; 2]) -> u32 {
+    first_of(&x)
+}
@@ -0,0 +1,51 @@
+//! External ADTs, extracted with `--transparent-external-adts`
+#![allow(dead_code)]
+
+use std::cmp::Ordering;
+use std::ops::Range;
+
+pub fn ok(x: u32) -> Result<u32, bool> {
+    Ok(x)
+}
+
+pub fn err() -> Result<u32, bool> {
+    Err(true)
+}
+
+pub fn unwrap_or_zero(r: Result<u32, bool>) -> u32 {
+    match r {
+        Ok(x) => x,
+        Err(_) => 0,
+    }
+}
+
+pub fn map_ok(x: u32) -> Result<u32, bool> {
+    // The constructor is used as a function
+    Option::Some(x).map(Ok).unwrap()
+}
+
+pub fn compare(x: u32, y: u32) -> Ordering {
+    if x < y {
+        Ordering::Less
+    } else if x == y {
+        Ordering::Equal
+    } else {
+        Ordering::Greater
+    }
+}
+
+pub fn is_less(o: Ordering) -> bool {
+    match o {
+        Ordering::Less => true,
+        Ordering::Equal | Ordering::Greater => false,
+    }
+}
+
+/// A structure whose fields are all public
+pub fn range(start: u32, end: u32) -> Range<u32> {
+    Range { start, end }
+}
+
+pub fn range_len(r: &Range<u32>) -> u32 {
+    r.end - r.start
+}
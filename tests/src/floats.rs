@@ -0,0 +1,58 @@
+//! Tests with floating-point numbers
+#![allow(dead_code)]
+
+const PI: f64 = 3.141592653589793;
+
+const NEG_ZERO: f32 = -0.0;
+
+const NAN: f64 = f64::NAN;
+
+pub fn add(x: f64, y: f64) -> f64 {
+    x + y
+}
+
+pub fn ops(x: f32, y: f32) -> f32 {
+    let z = x * y - x / y;
+    -z % 2.5
+}
+
+pub fn lt(x: f64, y: f64) -> bool {
+    x < y
+}
+
+pub fn circle_area(r: f64) -> f64 {
+    PI * r * r
+}
+
+pub fn to_fixed_point(x: f64) -> i32 {
+    (x * 65536.0) as i32
+}
+
+pub fn from_fixed_point(x: i32) -> f64 {
+    (x as f64) / 65536.0
+}
+
+pub fn widen(x: f32) -> f64 {
+    x as f64
+}
+
+pub fn narrow(x: f64) -> f32 {
+    x as f32
+}
+
+pub struct Point {
+    pub x: f32,
+    pub y: f32,
+}
+
+pub fn origin() -> Point {
+    Point { x: 0.0, y: 0.0 }
+}
+
+pub fn norm2(p: &Point) -> f32 {
+    p.x * p.x + p.y * p.y
+}
+
+pub fn use_consts() -> (f32, f64) {
+    (NEG_ZERO, NAN)
+}
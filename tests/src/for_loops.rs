@@ -0,0 +1,68 @@
+//! `for` loops over ranges and slices
+#![allow(dead_code)]
+
+pub fn sum_range(n: u32) -> u32 {
+    let mut s = 0;
+    for i in 0..n {
+        s += i;
+    }
+    s
+}
+
+pub fn sum_range_inclusive(n: usize) -> usize {
+    let mut s = 0;
+    for i in 1..=n {
+        s += i;
+    }
+    s
+}
+
+pub fn sum_slice_iter(v: &[u32]) -> u32 {
+    let mut s = 0;
+    for x in v.iter() {
+        s += *x;
+    }
+    s
+}
+
+pub fn sum_slice(v: &[u32]) -> u32 {
+    let mut s = 0;
+    for x in v {
+        s += *x;
+    }
+    s
+}
+
+pub fn incr_slice(v: &mut [u32]) {
+    for x in v.iter_mut() {
+        *x += 1;
+    }
+}
+
+pub fn clear_slice(v: &mut [u32]) {
+    for x in v {
+        *x = 0;
+    }
+}
+
+pub fn find(v: &[u32], y: u32) -> Option<usize> {
+    for i in 0..v.len() {
+        if v[i] == y {
+            return Some(i);
+        }
+    }
+    None
+}
+
+pub fn nested(n: usize, m: usize) -> usize {
+    let mut s = 0;
+    for i in 0..n {
+        for j in 0..m {
+            if j > i {
+                break;
+            }
+            s += j;
+        }
+    }
+    s
+}
@@ -0,0 +1,67 @@
+//! Tests with function pointers and function items used as values
+#![allow(dead_code)]
+
+pub fn incr(x: u32) -> u32 {
+    x + 1
+}
+
+pub fn decr(x: u32) -> u32 {
+    x - 1
+}
+
+/// Call a function through a function pointer
+pub fn apply(f: fn(u32) -> u32, x: u32) -> u32 {
+    f(x)
+}
+
+/// Give a function item to a function expecting a function pointer
+pub fn test_apply_incr(x: u32) -> u32 {
+    apply(incr, x)
+}
+
+/// Store function pointers in local variables
+pub fn test_fn_ptr_local(b: bool, x: u32) -> u32 {
+    let f: fn(u32) -> u32 = if b { incr } else { decr };
+    f(x)
+}
+
+/// Call a function through a function pointer taking a reference
+pub fn apply_ref(f: fn(&u32) -> u32, x: &u32) -> u32 {
+    f(x)
+}
+
+pub fn read(x: &u32) -> u32 {
+    *x
+}
+
+pub fn test_apply_ref(x: u32) -> u32 {
+    apply_ref(read, &x)
+}
+
+/// A function which is generic in the function it calls
+pub fn apply_generic<F: Fn(u32) -> u32>(f: F, x: u32) -> u32 {
+    f(x)
+}
+
+pub fn test_apply_generic(x: u32) -> u32 {
+    apply_generic(incr, x)
+}
+
+pub struct Wrapper(u32);
+
+/// Use constructors as functions
+pub fn test_ctors(x: Option<u32>) -> Option<Option<u32>> {
+    x.map(Some)
+}
+
+pub fn test_struct_ctor(x: Option<u32>) -> Option<Wrapper> {
+    x.map(Wrapper)
+}
+
+/// Call a diverging function through a function pointer
+pub fn apply_diverging(f: fn() -> !, b: bool) -> u32 {
+    if b {
+        f()
+    }
+    0
+}
@@ -0,0 +1,50 @@
+//! Inline assembly, which we translate to opaque statements
+#![allow(dead_code)]
+use std::arch::asm;
+
+pub fn nop() {
+    unsafe { asm!("nop", options(nomem, nostack, preserves_flags)) }
+}
+
+pub fn add_one(x: u64) -> u64 {
+    let y: u64;
+    unsafe {
+        asm!("mov {0}, {1}", "add {0}, 1", out(reg) y, in(reg) x, options(pure, nomem, nostack))
+    };
+    y
+}
+
+pub fn double(mut x: u64) -> u64 {
+    unsafe { asm!("add {0}, {0}", inout(reg) x, options(pure, nomem, nostack)) };
+    x
+}
+
+pub fn cpuid_max_leaf() -> u32 {
+    let eax: u32;
+    unsafe {
+        asm!(
+            "mov {tmp:r}, rbx",
+            "cpuid",
+            "mov rbx, {tmp:r}",
+            tmp = out(reg) _,
+            inlateout("eax") 0u32 => eax,
+            lateout("ecx") _,
+            lateout("edx") _,
+            options(nostack, preserves_flags),
+        )
+    };
+    eax
+}
+
+extern "C" fn callee() {}
+
+pub fn call_sym() {
+    unsafe { asm!("call {}", sym callee, out("rax") _) }
+}
+
+pub fn trap_if_zero(x: u32) -> u32 {
+    if x == 0 {
+        unsafe { asm!("ud2", options(noreturn, nomem, nostack)) }
+    }
+    x
+}
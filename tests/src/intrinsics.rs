@@ -0,0 +1,56 @@
+//! Tests with compiler intrinsics
+#![feature(core_intrinsics)]
+#![allow(dead_code)]
+#![allow(unused_attributes)]
+
+use core::intrinsics;
+
+pub fn size_of_u64() -> usize {
+    intrinsics::size_of::<u64>()
+}
+
+pub fn align_of_u32() -> usize {
+    intrinsics::min_align_of::<u32>()
+}
+
+pub fn likely_branch(b: bool) -> u32 {
+    if intrinsics::likely(b) {
+        0
+    } else {
+        1
+    }
+}
+
+pub fn transmute_u32(x: u32) -> i32 {
+    unsafe { intrinsics::transmute::<u32, i32>(x) }
+}
+
+pub unsafe fn assume_positive(x: u32) -> u32 {
+    intrinsics::assume(x > 0);
+    x
+}
+
+pub unsafe fn copy(src: *const u32, dst: *mut u32) {
+    intrinsics::copy_nonoverlapping(src, dst, 1)
+}
+
+pub unsafe fn unreachable_branch(b: bool) -> u32 {
+    if b {
+        0
+    } else {
+        intrinsics::unreachable()
+    }
+}
+
+/// `ctpop` is not in the list of the intrinsics we recognize
+pub fn count_ones(x: u32) -> u32 {
+    intrinsics::ctpop(x)
+}
+
+/// `abort` diverges: the call is followed by an unreachable block
+pub fn abort_if_zero(x: u32) -> u32 {
+    if x == 0 {
+        intrinsics::abort()
+    }
+    x
+}
@@ -1,9 +1,32 @@
+#![feature(core_intrinsics)]
+mod arrays;
+mod casts;
+mod cells;
+mod closures;
+mod const_generics;
 mod constants;
+mod dyn_trait;
 mod external;
+mod external_adts;
+mod floats;
+mod for_loops;
+mod function_pointers;
 mod hashmap;
+mod inline_asm;
+mod intrinsics;
 mod loops;
 mod loops_cfg;
 mod matches;
 mod nested_borrows;
 mod no_nested_borrows;
+mod panics;
 mod paper;
+mod raw_pointers;
+mod slices;
+mod strings;
+mod traits;
+mod transparent_extern;
+mod try_operator;
+mod unions;
+mod unwind;
+mod user_assumed;
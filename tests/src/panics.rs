@@ -0,0 +1,40 @@
+//! Tests with panics and assertions
+#![allow(dead_code)]
+
+pub fn explicit_panic(x: u32) -> u32 {
+    if x == 0 {
+        panic!()
+    }
+    x
+}
+
+pub fn unreachable_code(x: u32) -> u32 {
+    if x == 0 {
+        unreachable!()
+    }
+    x
+}
+
+pub fn user_assert(b: bool) {
+    assert!(b)
+}
+
+pub fn overflow(x: u32, y: u32) -> u32 {
+    x + y
+}
+
+pub fn neg_overflow(x: i32) -> i32 {
+    -x
+}
+
+pub fn division(x: u32, y: u32) -> u32 {
+    x / y
+}
+
+pub fn remainder(x: u32, y: u32) -> u32 {
+    x % y
+}
+
+pub fn index(a: [u32; 4], i: usize) -> u32 {
+    a[i]
+}
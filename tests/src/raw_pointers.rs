@@ -0,0 +1,63 @@
+//! Tests with raw pointers
+#![allow(dead_code)]
+
+pub struct Pair {
+    pub x: u32,
+    pub y: u32,
+}
+
+/// Coercion from a reference to a raw pointer
+pub fn as_ptr(x: &u32) -> *const u32 {
+    x as *const u32
+}
+
+/// Coercion from a mutable reference to a raw pointer
+pub fn as_mut_ptr(x: &mut u32) -> *mut u32 {
+    x as *mut u32
+}
+
+/// Raw pointer to a field
+pub fn field_ptr(p: &Pair) -> *const u32 {
+    core::ptr::addr_of!(p.y)
+}
+
+/// Casts between raw pointers
+pub fn to_const(p: *mut u32) -> *const u32 {
+    p as *const u32
+}
+
+pub fn to_bytes(p: *const u32) -> *const u8 {
+    p as *const u8
+}
+
+/// Casts between raw pointers and integers
+pub fn to_addr(p: *const Pair) -> usize {
+    p as usize
+}
+
+pub fn from_addr(a: usize) -> *const Pair {
+    a as *const Pair
+}
+
+/// Dereferences of raw pointers
+pub fn read(p: *const u32) -> u32 {
+    unsafe { *p }
+}
+
+pub fn write(p: *mut u32, x: u32) {
+    unsafe { *p = x }
+}
+
+/// Calls to unsafe functions
+pub fn swap_read(x: &mut u32, y: u32) -> u32 {
+    unsafe {
+        let old = core::ptr::read(x);
+        core::ptr::write(x, y);
+        old
+    }
+}
+
+/// A safe function, for comparison
+pub fn add(x: u32, y: u32) -> u32 {
+    x + y
+}
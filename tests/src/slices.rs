@@ -0,0 +1,74 @@
+//! Slice and array functions from the standard library, which we model
+//! as primitives
+#![allow(dead_code)]
+
+pub fn slice_len(s: &[u32]) -> usize {
+    s.len()
+}
+
+pub fn array_len(a: [u32; 4]) -> usize {
+    a.len()
+}
+
+pub fn slice_get(s: &[u32], i: usize) -> Option<&u32> {
+    s.get(i)
+}
+
+pub fn slice_get_mut(s: &mut [u32], i: usize) -> Option<&mut u32> {
+    s.get_mut(i)
+}
+
+pub fn slice_swap(s: &mut [u32]) {
+    s.swap(0, 1)
+}
+
+pub fn slice_split_at(s: &[u32]) -> (&[u32], &[u32]) {
+    s.split_at(1)
+}
+
+pub fn slice_split_at_mut(s: &mut [u32]) -> (&mut [u32], &mut [u32]) {
+    s.split_at_mut(1)
+}
+
+pub fn slice_iter(s: &[u32]) -> std::slice::Iter<'_, u32> {
+    s.iter()
+}
+
+pub fn slice_to_vec(s: &[u32]) -> Vec<u32> {
+    s.to_vec()
+}
+
+pub fn array_to_slice(a: &[u32; 4]) -> &[u32] {
+    a.as_slice()
+}
+
+pub fn array_to_slice_mut(a: &mut [u32; 4]) -> &mut [u32] {
+    a.as_mut_slice()
+}
+
+/// Indexing through the `Index` trait: the built-in indexing is
+/// translated to projections
+pub fn slice_index(s: &[u32], i: usize) -> &u32 {
+    std::ops::Index::index(s, i)
+}
+
+pub fn array_index_mut(a: &mut [u32; 4], i: usize) -> &mut u32 {
+    std::ops::IndexMut::index_mut(a, i)
+}
+
+/// The ranges are external structures: we need `--transparent-external-adts`
+pub fn slice_subslice(s: &[u32]) -> &[u32] {
+    &s[1..2]
+}
+
+pub fn slice_subslice_mut(s: &mut [u32]) -> &mut [u32] {
+    &mut s[1..2]
+}
+
+pub fn array_subslice(a: &[u32; 4]) -> &[u32] {
+    &a[1..3]
+}
+
+pub fn array_subslice_mut(a: &mut [u32; 4]) -> &mut [u32] {
+    &mut a[1..3]
+}
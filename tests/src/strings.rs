@@ -0,0 +1,49 @@
+//! String literals and byte strings
+#![allow(dead_code)]
+
+pub fn str_literal() -> &'static str {
+    "hello"
+}
+
+pub fn escaped_str() -> &'static str {
+    "a \"quoted\"\nline"
+}
+
+pub fn byte_string() -> &'static [u8; 3] {
+    b"abc"
+}
+
+pub fn byte_slice() -> &'static [u8] {
+    b"a\x00\xff"
+}
+
+pub const GREETING: &str = "hi";
+
+pub fn use_greeting() -> &'static str {
+    GREETING
+}
+
+pub const BYTES: &[u8] = b"xyz";
+
+pub fn use_bytes() -> &'static [u8] {
+    BYTES
+}
+
+pub struct Named {
+    pub name: &'static str,
+    pub id: u32,
+}
+
+pub const NAMED: Named = Named { name: "foo", id: 0 };
+
+pub fn get_named() -> Named {
+    NAMED
+}
+
+pub fn make_named(id: u32) -> Named {
+    Named { name: "bar", id }
+}
+
+pub fn pair() -> (&'static str, u32) {
+    ("a", 1)
+}
@@ -0,0 +1,181 @@
+//! Tests with traits
+#![allow(dead_code)]
+
+pub trait BoolTrait {
+    // Required method
+    fn get_bool(&self) -> bool;
+
+    // Provided method
+    fn ret_true(&self) -> bool {
+        true
+    }
+}
+
+impl BoolTrait for bool {
+    fn get_bool(&self) -> bool {
+        *self
+    }
+}
+
+pub fn test_bool_trait_bool(x: bool) -> bool {
+    x.get_bool() && x.ret_true()
+}
+
+impl<T> BoolTrait for Option<T> {
+    fn get_bool(&self) -> bool {
+        match self {
+            Option::Some(_) => true,
+            Option::None => false,
+        }
+    }
+}
+
+pub fn test_bool_trait_option<T>(x: Option<T>) -> bool {
+    x.get_bool() && x.ret_true()
+}
+
+pub fn test_bool_trait<T: BoolTrait>(x: T) -> bool {
+    x.get_bool()
+}
+
+pub fn test_bool_trait_provided<T: BoolTrait>(x: T) -> bool {
+    x.ret_true()
+}
+
+pub fn test_clone_u32(x: u32) -> u32 {
+    x.clone()
+}
+
+pub fn test_clone_pair(x: (u32, u32)) -> (u32, u32) {
+    x.clone()
+}
+
+pub trait ToU64 {
+    fn to_u64(self) -> u64;
+}
+
+impl ToU64 for u64 {
+    fn to_u64(self) -> u64 {
+        self
+    }
+}
+
+pub struct Wrapper<T> {
+    x: T,
+}
+
+impl<T: ToU64> ToU64 for Wrapper<T> {
+    fn to_u64(self) -> u64 {
+        self.x.to_u64()
+    }
+}
+
+pub fn f<T: ToU64>(x: (T, T)) -> u64 {
+    x.0.to_u64() + x.1.to_u64()
+}
+
+pub trait From<T> {
+    fn from(x: T) -> Self;
+}
+
+impl From<u32> for u64 {
+    fn from(x: u32) -> u64 {
+        x as u64
+    }
+}
+
+pub trait WithOutput {
+    type Output;
+
+    fn get_output(&self) -> Self::Output;
+}
+
+impl WithOutput for u32 {
+    type Output = u64;
+
+    fn get_output(&self) -> u64 {
+        *self as u64
+    }
+}
+
+impl<T: WithOutput> WithOutput for Wrapper<T> {
+    type Output = T::Output;
+
+    fn get_output(&self) -> T::Output {
+        self.x.get_output()
+    }
+}
+
+pub fn use_output<T: WithOutput>(x: &T) -> <T as WithOutput>::Output {
+    x.get_output()
+}
+
+pub fn use_output_u32(x: u32) -> <u32 as WithOutput>::Output {
+    x.get_output()
+}
+
+/// The signatures of the external functions called below contain projections
+/// (for instance, `<I as Iterator>::Item`).
+pub fn sum_vec(v: Vec<u32>) -> u32 {
+    let mut s = 0;
+    for x in v {
+        s += x;
+    }
+    s
+}
+
+pub fn sum_vec_ref(v: &Vec<u32>) -> u32 {
+    let mut s = 0;
+    for x in v {
+        s += *x;
+    }
+    s
+}
+
+pub fn sum_indices(v: &[u32]) -> usize {
+    let mut s = 0;
+    for (i, _) in v.iter().enumerate() {
+        s += i;
+    }
+    s
+}
+
+/// Traits and implementations with const generic parameters
+pub trait WithConstParam<const N: usize> {
+    fn get_x(&self) -> u32;
+}
+
+pub struct ConstWrapper<const N: usize> {
+    pub x: u32,
+}
+
+impl<const N: usize> WithConstParam<N> for ConstWrapper<N> {
+    fn get_x(&self) -> u32 {
+        self.x
+    }
+}
+
+impl WithConstParam<0> for bool {
+    fn get_x(&self) -> u32 {
+        if *self {
+            1
+        } else {
+            0
+        }
+    }
+}
+
+pub fn use_const_param(x: ConstWrapper<3>) -> u32 {
+    x.get_x()
+}
+
+/// Projections on traits with const generic parameters
+pub trait WithConstOutput<const N: usize> {
+    type Output;
+
+    fn get_const_output(&self) -> Self::Output;
+}
+
+pub fn use_const_output<T: WithConstOutput<3>>(x: &T) -> <T as WithConstOutput<3>>::Output {
+    x.get_const_output()
+}
@@ -0,0 +1,14 @@
+//! Extract the bodies of some external functions
+#![allow(dead_code)]
+
+pub fn use_identity(x: u32) -> u32 {
+    core::convert::identity(x)
+}
+
+pub fn use_drop(v: Vec<u32>) {
+    core::mem::drop(v)
+}
+
+pub fn use_max(x: u32, y: u32) -> u32 {
+    core::cmp::max(x, y)
+}
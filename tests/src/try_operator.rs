@@ -0,0 +1,58 @@
+//! The `?` operator applied to `Result` and `Option` values
+#![allow(dead_code)]
+
+pub fn incr(x: Result<u32, bool>) -> Result<u32, bool> {
+    let y = x?;
+    Ok(y + 1)
+}
+
+pub fn add_opts(x: Option<u32>, y: Option<u32>) -> Option<u32> {
+    Some(x? + y?)
+}
+
+pub fn first_some(v: &[Option<u32>; 3]) -> Option<u32> {
+    let mut s = 0;
+    let mut i = 0;
+    while i < 3 {
+        s += v[i]?;
+        i += 1;
+    }
+    Some(s)
+}
+
+pub fn checked_sum(x: u32, y: u32, z: u32) -> Option<u32> {
+    x.checked_add(y)?.checked_add(z)
+}
+
+pub enum Error {
+    Overflow(u32),
+    Underflow(u32),
+}
+
+pub fn check_incr(x: u32) -> Result<u32, Error> {
+    if x == u32::MAX {
+        Err(Error::Overflow(x))
+    } else {
+        Ok(x + 1)
+    }
+}
+
+pub fn incr_twice(x: u32) -> Result<u32, Error> {
+    let x = check_incr(x)?;
+    check_incr(x)
+}
+
+/// The error is converted with `From::from`: we keep the call to
+/// `FromResidual::from_residual`
+pub struct WrappedError(pub u8);
+
+impl From<u8> for WrappedError {
+    fn from(x: u8) -> WrappedError {
+        WrappedError(x)
+    }
+}
+
+pub fn convert_error(x: Result<u32, u8>) -> Result<u32, WrappedError> {
+    let y = x?;
+    Ok(y)
+}
@@ -0,0 +1,34 @@
+//! Tests with unions
+#![allow(dead_code)]
+
+pub union IntOrFloat {
+    pub i: u32,
+    pub f: f32,
+}
+
+pub union Bytes<T: Copy> {
+    pub value: T,
+    pub raw: [u8; 8],
+}
+
+pub fn from_int(i: u32) -> IntOrFloat {
+    IntOrFloat { i }
+}
+
+pub fn float_bits(f: f32) -> u32 {
+    let x = IntOrFloat { f };
+    unsafe { x.i }
+}
+
+pub fn set_int(x: &mut IntOrFloat, i: u32) {
+    x.i = i;
+}
+
+pub fn get_float(x: &IntOrFloat) -> f32 {
+    unsafe { x.f }
+}
+
+pub fn to_raw(x: u64) -> [u8; 8] {
+    let b = Bytes { value: x };
+    unsafe { b.raw }
+}
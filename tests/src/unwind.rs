@@ -0,0 +1,27 @@
+//! Tests with unwinding (see the `--unwind` option)
+#![allow(dead_code)]
+
+fn may_panic(x: u32) -> u32 {
+    if x == 0 {
+        panic!()
+    }
+    x
+}
+
+/// If `may_panic` panics, we need to drop the box while unwinding
+pub fn call_with_box(b: Box<u32>, x: u32) -> u32 {
+    let y = may_panic(x);
+    *b + y
+}
+
+/// Introduces a `DropAndReplace`
+pub fn replace_box(mut b: Box<u32>, x: u32) -> Box<u32> {
+    b = Box::new(x);
+    b
+}
+
+pub fn drop_vec(v: Vec<u32>, x: u32) -> u32 {
+    let y = may_panic(x);
+    drop(v);
+    y
+}
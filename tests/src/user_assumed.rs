@@ -0,0 +1,20 @@
+//! Primitives declared by the user (see `user_assumed.toml`)
+#![allow(dead_code)]
+
+use std::num::{NonZeroU32, Wrapping};
+
+pub fn max3(x: u32, y: u32, z: u32) -> u32 {
+    core::cmp::max(core::cmp::max(x, y), z)
+}
+
+pub fn non_zero(x: u32) -> Option<NonZeroU32> {
+    NonZeroU32::new(x)
+}
+
+pub fn get(x: NonZeroU32) -> u32 {
+    x.get()
+}
+
+pub fn swap_wrapping(x: (Wrapping<u32>, Wrapping<u32>)) -> (Wrapping<u32>, Wrapping<u32>) {
+    (x.1, x.0)
+}